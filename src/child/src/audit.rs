@@ -0,0 +1,244 @@
+use std::{cell::Cell, fmt::Debug, ops::Bound, time::Duration};
+
+use candid::Principal;
+use ic_cdk::{api::time, caller};
+use ic_cdk_timers::set_timer_interval;
+use ic_scalable_canister::ic_scalable_misc::models::date_models::DateRange;
+
+use shared::{
+    attendee_model::{Attendee, CursorPagedResponse, MAX_PAGE_LIMIT},
+    audit_helper::get_changed_actions,
+    audit_model::{AuditAction, AuditEntry},
+};
+
+use crate::store::{AUDIT_LOG, AUDIT_SEQUENCE};
+
+// Time the audit entries are kept
+const AUDIT_RETENTION_NANOS: u64 = 180 * 24 * 60 * 60 * 1_000_000_000;
+// Interval on which the expired audit entries are removed
+const RETENTION_INTERVAL_SECONDS: u64 = 24 * 60 * 60;
+// Max number of expired entries that are removed per run
+const MAX_REMOVALS_PER_RUN: usize = 10_000;
+// Max number of entries that are scanned for a single page
+const MAX_SCANNED_PER_PAGE: usize = 50_000;
+
+thread_local! {
+    // Principal that called the sibling canister of which a forwarded write is applied
+    static FORWARDED_ACTOR: Cell<Option<Principal>> = const { Cell::new(None) };
+}
+
+pub struct Audit;
+
+impl Audit {
+    // Method to start the timer that applies the retention policy,
+    // needs to be called on init and post_upgrade
+    pub fn start_timer() {
+        set_timer_interval(Duration::from_secs(RETENTION_INTERVAL_SECONDS), || {
+            Self::remove_expired_entries();
+        });
+    }
+
+    // Method to apply a write on behalf of the principal that called the sibling canister that forwarded the write
+    // the entries that are recorded while applying the write have that principal as actor
+    pub fn with_actor<T>(actor: Principal, apply: impl FnOnce() -> T) -> T {
+        FORWARDED_ACTOR.with(|forwarded| forwarded.set(Some(actor)));
+        let result = apply();
+        FORWARDED_ACTOR.with(|forwarded| forwarded.set(None));
+        result
+    }
+
+    // Method to append the changes of a write to an attendee record to the audit log, an entry per changed event
+    // called for every write to the attendee records, so the writes of the parent and sibling canisters are recorded as well
+    pub fn record_changes(principal: Principal, previous: &Attendee, current: &Attendee) {
+        let actor = Self::get_actor();
+        for (action, event_identifier, group_identifier) in
+            get_changed_actions(actor == principal, previous, current)
+        {
+            Self::_insert(
+                actor,
+                action,
+                principal,
+                Some(event_identifier),
+                Some(group_identifier),
+                Ok(()),
+            );
+        }
+    }
+
+    // Method to append the events of a restored attendee record to the audit log, an entry per event
+    // the restore replaces the records without a write per attendee, so it isnt recorded from the changes
+    pub fn record_restore(attendee: &Attendee) {
+        let actor = Self::get_actor();
+        let events = attendee
+            .joined
+            .iter()
+            .map(|(event_identifier, join)| (event_identifier, join.group_identifier))
+            .chain(
+                attendee
+                    .invites
+                    .iter()
+                    .map(|(event_identifier, invite)| (event_identifier, invite.group_identifier)),
+            );
+        for (event_identifier, group_identifier) in events {
+            Self::_insert(
+                actor,
+                AuditAction::RestoreData,
+                attendee.principal,
+                Some(*event_identifier),
+                Some(group_identifier),
+                Ok(()),
+            );
+        }
+    }
+
+    // Method to append an entry for a mutation that doesnt change the attendee record to the audit log
+    pub fn record<T, E: Debug>(
+        action: AuditAction,
+        target: Principal,
+        event_identifier: Option<Principal>,
+        group_identifier: Option<Principal>,
+        result: &Result<T, E>,
+    ) {
+        Self::_insert(
+            Self::get_actor(),
+            action,
+            target,
+            event_identifier,
+            group_identifier,
+            match result {
+                Ok(_) => Ok(()),
+                Err(err) => Err(format!("{:?}", err)),
+            },
+        );
+    }
+
+    // Method to append an entry for a failed mutation to the audit log
+    // the successful mutations are recorded from the changes to the attendee record
+    pub fn record_failure<T, E: Debug>(
+        action: AuditAction,
+        target: Principal,
+        event_identifier: Option<Principal>,
+        group_identifier: Option<Principal>,
+        result: &Result<T, E>,
+    ) {
+        if result.is_err() {
+            Self::record(action, target, event_identifier, group_identifier, result);
+        }
+    }
+
+    // Method to get the principal that made the current write, the caller or the principal of a forwarded write
+    pub fn get_actor() -> Principal {
+        FORWARDED_ACTOR
+            .with(|forwarded| forwarded.get())
+            .unwrap_or_else(caller)
+    }
+
+    fn _insert(
+        actor: Principal,
+        action: AuditAction,
+        target: Principal,
+        event_identifier: Option<Principal>,
+        group_identifier: Option<Principal>,
+        result: Result<(), String>,
+    ) {
+        let sequence = AUDIT_SEQUENCE.with(|sequence| {
+            let mut sequence = sequence.borrow_mut();
+            let next = sequence.get() + 1;
+            let _ = sequence.set(next);
+            next
+        });
+
+        // The log is keyed on the inverted sequence so iterating the log returns the newest entries first
+        AUDIT_LOG.with(|log| {
+            log.borrow_mut().insert(
+                u64::MAX - sequence,
+                AuditEntry {
+                    sequence,
+                    actor,
+                    action,
+                    target,
+                    event_identifier,
+                    group_identifier,
+                    result,
+                    created_at: time(),
+                },
+            )
+        });
+    }
+
+    // Method to get a page of the audit entries of a group, or of all the entries (also without a group) if no group is passed, newest first
+    // the cursor is the sequence of the last entry of the previous page
+    pub fn get_entries(
+        group_identifier: Option<Principal>,
+        range: Option<DateRange>,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> CursorPagedResponse<AuditEntry> {
+        let limit = limit.min(MAX_PAGE_LIMIT);
+        let start = match cursor.and_then(|_cursor| _cursor.parse::<u64>().ok()) {
+            None => Bound::Unbounded,
+            Some(_sequence) => Bound::Excluded(u64::MAX - _sequence),
+        };
+
+        AUDIT_LOG.with(|log| {
+            let log = log.borrow();
+            let mut last_scanned: Option<u64> = None;
+            let mut data: Vec<AuditEntry> = vec![];
+
+            for (key, entry) in log
+                .range((start, Bound::Unbounded))
+                .take(MAX_SCANNED_PER_PAGE)
+            {
+                if data.len() == limit {
+                    break;
+                }
+                last_scanned = Some(key);
+
+                let in_range = match &range {
+                    None => true,
+                    Some(_range) => {
+                        entry.created_at >= _range.start_date && entry.created_at <= _range.end_date
+                    }
+                };
+                let in_group =
+                    group_identifier.is_none() || entry.group_identifier == group_identifier;
+                if in_range && in_group {
+                    data.push(entry);
+                }
+            }
+
+            // There is a next page if there are older entries than the last scanned entry
+            let next_cursor = last_scanned
+                .filter(|_key| {
+                    log.range((Bound::Excluded(*_key), Bound::Unbounded))
+                        .next()
+                        .is_some()
+                })
+                .map(|_key| (u64::MAX - _key).to_string());
+
+            CursorPagedResponse { data, next_cursor }
+        })
+    }
+
+    // Method to remove the entries that are older than the retention period, oldest first
+    pub fn remove_expired_entries() -> usize {
+        let expired_before = time().saturating_sub(AUDIT_RETENTION_NANOS);
+
+        AUDIT_LOG.with(|log| {
+            let mut log = log.borrow_mut();
+            let mut removed = 0;
+
+            // The oldest entry is the last entry of the log
+            while removed < MAX_REMOVALS_PER_RUN {
+                match log.last_key_value() {
+                    Some((_, _entry)) if _entry.created_at < expired_before => {
+                        log.pop_last();
+                        removed += 1;
+                    }
+                    _ => break,
+                }
+            }
+            removed
+        })
+    }
+}
@@ -3,9 +3,13 @@ use ic_canister_backup::{logic::BACKUP, models::Chunk};
 use ic_cdk::{caller, query, update};
 use ic_scalable_canister::{ic_scalable_misc, store::Data};
 use ic_stable_structures::StableBTreeMap;
-use shared::attendee_model::Attendee;
+use shared::{attendee_model::Attendee, operator_model::OperatorRole};
 
-use crate::store::{ENTRIES, ENTRIES_MEMORY_ID, MEMORY_MANAGER, STABLE_DATA};
+use crate::{
+    audit::Audit,
+    roles::Roles,
+    store::{Store, ENTRIES, ENTRIES_MEMORY_ID, MEMORY_MANAGER, STABLE_DATA},
+};
 
 //
 #[update(guard = "is_owner")]
@@ -38,8 +42,12 @@ pub fn restore_data() {
     let _ = ENTRIES.with(|e| {
         data.entries.iter().for_each(|entry| {
             e.borrow_mut().insert(entry.0.to_string(), entry.1.clone());
+            Audit::record_restore(entry.1);
         });
     });
+
+    Store::rebuild_attendee_counts();
+    Store::rebuild_event_indexes();
 }
 
 // #[update(guard = "is_owner")]
@@ -86,15 +94,10 @@ fn clear_backup() {
     BACKUP.with(|b| b.borrow_mut().clear_backup());
 }
 
+// The parent can call the backup methods to execute the SNS proposals for restores
 pub fn is_owner() -> Result<(), String> {
-    const OWNERS: [&str; 2] = [
-        // catalyze backup dfx principal
-        "swcc7-vdu3r-tym5o-cfsiw-kpo3l-5qlgi-mq7al-xbn6l-bdspe-xjwau-wae",
-        // backup_canister
-        "srb2o-nqaaa-aaaap-abt3q-cai",
-    ];
-
-    match OWNERS.iter().any(|p| p == &caller().to_string()) {
+    let is_parent = caller() == STABLE_DATA.with(|data| data.borrow().get().parent);
+    match is_parent || Roles::has_role(OperatorRole::Owner, &caller()) {
         true => Ok(()),
         false => Err("Unauthorized".to_string()),
     }
@@ -0,0 +1,255 @@
+use std::{cell::Cell, collections::HashSet, time::Duration};
+
+use candid::Principal;
+use ic_cdk::{
+    api::{call, management_canister::main::raw_rand, time},
+    id,
+};
+use ic_cdk_timers::{set_timer, set_timer_interval};
+use ic_scalable_canister::ic_scalable_misc::{
+    enums::api_error_type::{ApiError, ApiErrorType},
+    helpers::error_helper::api_error,
+    models::identifier_model::Identifier,
+};
+
+use shared::{
+    calendar_helper::to_ics,
+    calendar_model::{CachedEvent, CalendarFeedToken, EventDetails},
+    hex_helper::to_hex,
+    lock_helper::LockGuard,
+    metrics_helper::record_call_failure,
+};
+
+use crate::store::{Store, CALENDAR_TOKENS, EVENT_CACHE};
+
+// Interval on which the event details of the joined events are refreshed
+const REFRESH_INTERVAL_SECONDS: u64 = 5 * 60;
+// Cached event details older than this are fetched again, in nanoseconds
+const EVENT_CACHE_TTL_NANOS: u64 = 60 * 60 * 1_000_000_000;
+// Max number of stale events that are fetched again from the event canisters per refresh,
+// events that are not cached yet are always fetched
+const MAX_FETCHES_PER_REFRESH: usize = 50;
+
+thread_local! {
+    // Prevents overlapping refreshes when a refresh takes longer than the interval
+    static IS_REFRESHING: Cell<bool> = const { Cell::new(false) };
+    // Set while a refresh for new events is scheduled, so a bulk change schedules a single refresh
+    static IS_REFRESH_SCHEDULED: Cell<bool> = const { Cell::new(false) };
+}
+
+pub struct Calendar;
+
+impl Calendar {
+    // Method to start the timer that refreshes the event details of the joined events,
+    // needs to be called on init and post_upgrade
+    pub fn start_timer() {
+        set_timer_interval(Duration::from_secs(REFRESH_INTERVAL_SECONDS), || {
+            ic_cdk::spawn(Self::refresh_event_cache())
+        });
+    }
+
+    // Method to create a new feed token for an attendee, an existing token of the attendee is revoked
+    pub async fn create_feed_token(principal: Principal) -> Result<String, ApiError> {
+        // The feed is served by the canister that holds the record of the attendee
+        Store::get_attending_from_principal(principal)?;
+
+        let token = match raw_rand().await {
+            Err(err) => {
+                return Err(api_error(
+                    ApiErrorType::BadRequest,
+                    "RANDOMNESS_UNAVAILABLE",
+                    &format!("{:?} - {}", err.0, err.1),
+                    "Calendar",
+                    "create_feed_token",
+                    None,
+                ))
+            }
+            Ok((_bytes,)) => to_hex(&_bytes),
+        };
+
+        Self::remove_feed_tokens(principal);
+        CALENDAR_TOKENS.with(|tokens| {
+            tokens.borrow_mut().insert(
+                token.clone(),
+                CalendarFeedToken {
+                    token: token.clone(),
+                    principal,
+                    created_at: time(),
+                },
+            )
+        });
+        Ok(token)
+    }
+
+    // Method to revoke the feed token of an attendee, the feed url stops working right away
+    pub fn revoke_feed_token(principal: Principal) -> Result<(), ApiError> {
+        match Self::remove_feed_tokens(principal) {
+            0 => Err(api_error(
+                ApiErrorType::NotFound,
+                "FEED_TOKEN_NOT_FOUND",
+                "There is no calendar feed token for this principal",
+                "Calendar",
+                "revoke_feed_token",
+                None,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get_feed_token(principal: Principal) -> Option<String> {
+        CALENDAR_TOKENS.with(|tokens| {
+            tokens
+                .borrow()
+                .iter()
+                .find(|(_, feed_token)| feed_token.principal == principal)
+                .map(|(token, _)| token)
+        })
+    }
+
+    // Method to fetch the details of new events right away instead of on the next interval,
+    // called when an attendee joins or is invited to an event that is not cached yet
+    pub fn schedule_refresh() {
+        if IS_REFRESH_SCHEDULED.with(|scheduled| scheduled.replace(true)) {
+            return;
+        }
+
+        set_timer(Duration::ZERO, || {
+            IS_REFRESH_SCHEDULED.with(|scheduled| scheduled.set(false));
+            ic_cdk::spawn(Self::refresh_event_cache())
+        });
+    }
+
+    // Method to get the iCalendar feed of the joined events of the principal that owns the token,
+    // returns none if the token is unknown, events without cached details are left out until they are fetched
+    pub fn get_feed(token: &str) -> Option<String> {
+        let feed_token = CALENDAR_TOKENS.with(|tokens| tokens.borrow().get(&token.to_string()))?;
+        let joined = Store::get_attending_from_principal(feed_token.principal).unwrap_or_default();
+
+        let mut events: Vec<CachedEvent> = EVENT_CACHE.with(|cache| {
+            let cache = cache.borrow();
+            joined
+                .iter()
+                .filter_map(|join| cache.get(&join.event_identifier.to_string()))
+                .collect()
+        });
+        events.sort_by_key(|event| event.details.date.start_date);
+
+        Some(to_ics("Events", id(), &events))
+    }
+
+    // Method to fetch the details of the events that are joined by or invited to attendees on this canister,
+    // events that are missing are all fetched and stale events up to the max per refresh, events without joins and invites are removed from the cache
+    // the details are used for the calendar feeds, the upcoming events of the group queries and to check if an event ended
+    pub async fn refresh_event_cache() {
+        // The guard releases the lock at the end of the method, also when a callback traps
+        let _guard = match LockGuard::acquire(&IS_REFRESHING) {
+            None => return,
+            Some(_guard) => _guard,
+        };
+
+        let now = time();
+        let events: HashSet<String> = Store::get_event_identifiers()
+            .iter()
+            .map(|event_identifier| event_identifier.to_string())
+            .collect();
+
+        let (missing_events, stale_events): (Vec<String>, Vec<String>) =
+            EVENT_CACHE.with(|cache| {
+                let mut cache = cache.borrow_mut();
+                let unused: Vec<String> = cache
+                    .iter()
+                    .filter(|(key, _)| !events.contains(key))
+                    .map(|(key, _)| key)
+                    .collect();
+                for key in unused {
+                    cache.remove(&key);
+                }
+
+                let missing = events
+                    .iter()
+                    .filter(|key| !cache.contains_key(key))
+                    .cloned()
+                    .collect();
+                let stale = events
+                    .iter()
+                    .filter(|key| {
+                        cache
+                            .get(key)
+                            .map(|_cached| _cached.fetched_at + EVENT_CACHE_TTL_NANOS <= now)
+                            .unwrap_or(false)
+                    })
+                    .take(MAX_FETCHES_PER_REFRESH)
+                    .cloned()
+                    .collect();
+                (missing, stale)
+            });
+
+        let due_events: Vec<Principal> = missing_events
+            .iter()
+            .chain(stale_events.iter())
+            .filter_map(|key| Principal::from_text(key).ok())
+            .collect();
+
+        for event_identifier in due_events {
+            // The event canister needs the group of the event to return the event
+            let group_identifier = match Store::get_cached_event_group(event_identifier) {
+                None => continue,
+                Some(_group_identifier) => _group_identifier,
+            };
+
+            // Failed fetches keep the stale details, they are retried on the next refresh
+            if let Ok(_details) = Self::fetch_event(event_identifier, group_identifier).await {
+                EVENT_CACHE.with(|cache| {
+                    cache.borrow_mut().insert(
+                        event_identifier.to_string(),
+                        CachedEvent {
+                            event_identifier,
+                            group_identifier,
+                            details: _details,
+                            fetched_at: time(),
+                        },
+                    )
+                });
+            }
+        }
+    }
+
+    // Method to get the details of an event from the event canister (inter-canister call)
+    async fn fetch_event(
+        event_identifier: Principal,
+        group_identifier: Principal,
+    ) -> Result<EventDetails, String> {
+        let (_, event_canister, _) = Identifier::decode(&event_identifier);
+        let result: Result<(Result<EventDetails, ApiError>,), _> = call::call(
+            event_canister,
+            "get_event",
+            (event_identifier, group_identifier),
+        )
+        .await;
+
+        match result {
+            Err(err) => {
+                record_call_failure("get_event");
+                Err(format!("{:?} - {}", err.0, err.1))
+            }
+            Ok((Err(err),)) => Err(format!("{:?}", err)),
+            Ok((Ok(_details),)) => Ok(_details),
+        }
+    }
+
+    // Method to remove the feed tokens of a principal, returns the number of removed tokens
+    fn remove_feed_tokens(principal: Principal) -> usize {
+        CALENDAR_TOKENS.with(|tokens| {
+            let mut tokens = tokens.borrow_mut();
+            let owned: Vec<String> = tokens
+                .iter()
+                .filter(|(_, feed_token)| feed_token.principal == principal)
+                .map(|(token, _)| token)
+                .collect();
+            for token in owned.iter() {
+                tokens.remove(token);
+            }
+            owned.len()
+        })
+    }
+}
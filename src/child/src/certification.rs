@@ -0,0 +1,500 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    ops::Bound,
+    time::Duration,
+};
+
+use candid::Principal;
+use ic_cdk::api::{data_certificate, set_certified_data};
+use ic_cdk_timers::set_timer;
+use ic_scalable_canister::ic_scalable_misc::{
+    enums::api_error_type::{ApiError, ApiErrorType},
+    helpers::error_helper::api_error,
+};
+use serde_bytes::ByteBuf;
+
+use shared::{
+    attendee_model::{Attendee, AttendeeStatus, EventCount},
+    certification_helper::{
+        membership_status_value, ATTENDEES_LABEL, COUNTS_LABEL, INVITES_LABEL,
+        LAST_BLOCK_HASH_LABEL, LAST_BLOCK_INDEX_LABEL, MEMBERS_LABEL,
+    },
+    certification_model::{CertifiedEventCounts, MembershipProof},
+    certified_map::{AsHashTree, CertifiedMap},
+    hash_tree::{encode_leb128, fork_hash, labeled_hash, leaf_hash, Hash, HashTree},
+    icrc3_model::ICRC3DataCertificate,
+};
+
+use crate::store::{ATTENDEE_COUNTS, BLOCKS, ENTRIES, INVITE_INDEX};
+
+thread_local! {
+    // Tree of which the root hash is the certified data of the canister
+    // kept on the heap and rebuilt from the stable data after an upgrade
+    static CERTIFIED_TREE: RefCell<CertifiedTree> = RefCell::new(CertifiedTree::default());
+    // Progress of the rebuild of the tree, none when the tree is ready
+    static REBUILD: RefCell<Option<RebuildState>> = const { RefCell::new(None) };
+    // Incremented on every rebuild, so the slices of a replaced rebuild stop
+    static REBUILD_GENERATION: Cell<u64> = const { Cell::new(0) };
+}
+
+// Max number of attendees that are added to the tree in a single slice of the rebuild
+const MAX_ATTENDEES_PER_REBUILD_SLICE: usize = 1_000;
+
+#[derive(Default)]
+struct RebuildState {
+    // Key of the last attendee that is added to the tree
+    cursor: Option<String>,
+    // Events of which the counts are set during this rebuild
+    counted_events: HashSet<Principal>,
+}
+
+#[derive(Default)]
+struct CertifiedTree {
+    // Attendee and invite counts per event, keyed by the event identifier bytes
+    counts: CertifiedMap<EventCounts>,
+    // Status of the attendees per event, keyed by the event identifier bytes and the principal bytes
+    members: CertifiedMap<CertifiedMap<Vec<u8>>>,
+    // Index and hash of the last block of the attendance block log, not set while the log is empty
+    tip: Option<(u64, Vec<u8>)>,
+}
+
+impl CertifiedTree {
+    fn root_hash(&self) -> Hash {
+        self.witness(None, None, false).digest()
+    }
+
+    // Method to build the tree with the passed subtrees, the subtrees that are not passed are pruned
+    // the labels are sorted: counts, last_block_hash, last_block_index, members
+    fn witness(
+        &self,
+        counts: Option<HashTree>,
+        members: Option<HashTree>,
+        include_tip: bool,
+    ) -> HashTree {
+        let counts = match counts {
+            Some(_counts) => HashTree::labeled(COUNTS_LABEL, _counts),
+            None => HashTree::Pruned(labeled_hash(COUNTS_LABEL, &self.counts.root_hash())),
+        };
+        let members = match members {
+            Some(_members) => HashTree::labeled(MEMBERS_LABEL, _members),
+            None => HashTree::Pruned(labeled_hash(MEMBERS_LABEL, &self.members.root_hash())),
+        };
+
+        match &self.tip {
+            None => HashTree::fork(counts, members),
+            Some((_index, _hash)) => {
+                let prune = |tree: HashTree| match include_tip {
+                    true => tree,
+                    false => HashTree::Pruned(tree.digest()),
+                };
+                HashTree::fork(
+                    HashTree::fork(
+                        counts,
+                        prune(HashTree::labeled(
+                            LAST_BLOCK_HASH_LABEL,
+                            HashTree::Leaf(_hash.clone()),
+                        )),
+                    ),
+                    HashTree::fork(
+                        prune(HashTree::labeled(
+                            LAST_BLOCK_INDEX_LABEL,
+                            HashTree::Leaf(encode_leb128(*_index)),
+                        )),
+                        members,
+                    ),
+                )
+            }
+        }
+    }
+
+    // Method to set the counts of an event from the indexes, events without attendees and invites are removed
+    fn set_counts(&mut self, event_identifier: &Principal) {
+        let counts = Certification::read_event_counts(event_identifier);
+        let key = event_identifier.as_slice().to_vec();
+        if counts.attendees == 0 && counts.invites == 0 {
+            self.counts.remove(&key);
+        } else {
+            self.counts.insert(key, counts);
+        }
+    }
+
+    // Method to set or remove the status of a principal for an event
+    fn set_membership(
+        &mut self,
+        event_identifier: &Principal,
+        principal: &Principal,
+        status: Option<&AttendeeStatus>,
+    ) {
+        let event_key = event_identifier.as_slice();
+        let principal_key = principal.as_slice().to_vec();
+
+        match status {
+            Some(_status) => {
+                let value = membership_status_value(_status).to_vec();
+                if self.members.get(event_key).is_none() {
+                    self.members.insert(event_key.to_vec(), CertifiedMap::new());
+                }
+                self.members
+                    .modify(event_key, |members| members.insert(principal_key, value));
+            }
+            None => {
+                let is_empty = self.members.modify(event_key, |members| {
+                    members.remove(&principal_key);
+                    members.is_empty()
+                });
+                // Events without members are removed so the tree only contains existing entries
+                if is_empty == Some(true) {
+                    self.members.remove(event_key);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct EventCounts {
+    attendees: u64,
+    invites: u64,
+}
+
+impl AsHashTree for EventCounts {
+    fn root_hash(&self) -> Hash {
+        fork_hash(
+            &labeled_hash(ATTENDEES_LABEL, &leaf_hash(&encode_leb128(self.attendees))),
+            &labeled_hash(INVITES_LABEL, &leaf_hash(&encode_leb128(self.invites))),
+        )
+    }
+
+    fn as_hash_tree(&self) -> HashTree {
+        HashTree::fork(
+            HashTree::labeled(
+                ATTENDEES_LABEL,
+                HashTree::Leaf(encode_leb128(self.attendees)),
+            ),
+            HashTree::labeled(INVITES_LABEL, HashTree::Leaf(encode_leb128(self.invites))),
+        )
+    }
+}
+
+pub struct Certification;
+
+impl Certification {
+    // Method to rebuild the certified tree from the stored attendees and the indexes,
+    // needs to be called on init and post_upgrade because the tree is not stored in stable memory
+    // the attendees are added in slices on a timer, the tree isnt certified until the last slice is added
+    pub fn rebuild() {
+        let tip = BLOCKS.with(|blocks| {
+            blocks
+                .borrow()
+                .last_key_value()
+                .map(|(index, block)| (index, block.hash.to_vec()))
+        });
+        // A new rebuild replaces a rebuild that is still in progress
+        let generation = REBUILD_GENERATION.with(|generation| {
+            generation.set(generation.get() + 1);
+            generation.get()
+        });
+
+        CERTIFIED_TREE.with(|certified| {
+            *certified.borrow_mut() = CertifiedTree {
+                tip,
+                ..Default::default()
+            }
+        });
+        REBUILD.with(|rebuild| *rebuild.borrow_mut() = Some(RebuildState::default()));
+        set_timer(Duration::ZERO, move || Self::rebuild_slice(generation));
+    }
+
+    // Method to add a slice of the attendees to the certified tree, schedules the next slice until all attendees are added
+    // writes during the rebuild update the tree as usual, adding an attendee again sets the same values
+    fn rebuild_slice(generation: u64) {
+        if REBUILD_GENERATION.with(|_generation| _generation.get()) != generation {
+            return;
+        }
+
+        let cursor = match REBUILD
+            .with(|rebuild| rebuild.borrow().as_ref().map(|state| state.cursor.clone()))
+        {
+            None => return,
+            Some(_cursor) => _cursor,
+        };
+
+        let attendees: Vec<(String, Attendee)> = ENTRIES.with(|entries| {
+            let entries = entries.borrow();
+            let start = match cursor {
+                None => Bound::Unbounded,
+                Some(_cursor) => Bound::Excluded(_cursor),
+            };
+            entries
+                .range((start, Bound::Unbounded))
+                .take(MAX_ATTENDEES_PER_REBUILD_SLICE)
+                .collect()
+        });
+
+        CERTIFIED_TREE.with(|certified| {
+            REBUILD.with(|rebuild| {
+                let mut certified = certified.borrow_mut();
+                let mut rebuild = rebuild.borrow_mut();
+                let state = rebuild.as_mut().expect("rebuild in progress");
+
+                for (_, attendee) in attendees.iter() {
+                    for event_identifier in Self::events(attendee, attendee) {
+                        certified.set_membership(
+                            &event_identifier,
+                            &attendee.principal,
+                            Self::status(attendee, &event_identifier).as_ref(),
+                        );
+                        // The counts are read from the indexes, so they only need to be set once per event
+                        if state.counted_events.insert(event_identifier) {
+                            certified.set_counts(&event_identifier);
+                        }
+                    }
+                }
+                state.cursor = attendees.last().map(|(key, _)| key.clone());
+            })
+        });
+
+        if attendees.len() < MAX_ATTENDEES_PER_REBUILD_SLICE {
+            REBUILD.with(|rebuild| *rebuild.borrow_mut() = None);
+            Self::certify();
+        } else {
+            set_timer(Duration::ZERO, move || Self::rebuild_slice(generation));
+        }
+    }
+
+    // The tree is ready when it isnt being rebuilt, the certified data and the proofs are only valid when it is ready
+    pub fn is_ready() -> bool {
+        REBUILD.with(|rebuild| rebuild.borrow().is_none())
+    }
+
+    // Method to update the certified tree after a change of the joins or invites of an attendee,
+    // called from the central write path of the store after the indexes are updated
+    pub fn record_changes(principal: Principal, previous: &Attendee, current: &Attendee) {
+        let changed_events: Vec<Principal> = Self::events(previous, current)
+            .into_iter()
+            .filter(|event_identifier| {
+                Self::status(previous, event_identifier) != Self::status(current, event_identifier)
+            })
+            .collect();
+
+        if changed_events.is_empty() {
+            return;
+        }
+
+        CERTIFIED_TREE.with(|certified| {
+            let mut certified = certified.borrow_mut();
+            for event_identifier in changed_events.iter() {
+                certified.set_membership(
+                    event_identifier,
+                    &principal,
+                    Self::status(current, event_identifier).as_ref(),
+                );
+                certified.set_counts(event_identifier);
+            }
+        });
+        Self::certify();
+    }
+
+    // Method to certify the last block of the attendance block log,
+    // called from the block log after a block is appended
+    pub fn set_tip(index: u64, hash: Vec<u8>) {
+        CERTIFIED_TREE.with(|certified| certified.borrow_mut().tip = Some((index, hash)));
+        Self::certify();
+    }
+
+    // Method to get the certificate and the witness of the last block of the attendance block log (ICRC-3)
+    // the certificate is only available in query calls
+    pub fn get_tip_certificate() -> Option<ICRC3DataCertificate> {
+        if !Self::is_ready() {
+            return None;
+        }
+        let certificate = data_certificate()?;
+        CERTIFIED_TREE.with(|certified| {
+            let certified = certified.borrow();
+            // Without blocks there is no tip to certify
+            certified.tip.as_ref()?;
+            Some(ICRC3DataCertificate {
+                certificate: ByteBuf::from(certificate),
+                hash_tree: ByteBuf::from(certified.witness(None, None, true).to_cbor()),
+            })
+        })
+    }
+
+    // Method to get the counts of an event with the witness and the certificate to verify them
+    // the certificate is only available in query calls
+    pub fn get_certified_event_counts(event_identifier: Principal) -> CertifiedEventCounts {
+        CERTIFIED_TREE.with(|certified| {
+            let certified = certified.borrow();
+            let key = event_identifier.as_slice();
+            let counts = certified.counts.get(key).cloned().unwrap_or_default();
+            let witness = certified.witness(
+                Some(
+                    certified
+                        .counts
+                        .witness(key, |counts| counts.as_hash_tree()),
+                ),
+                None,
+                false,
+            );
+
+            CertifiedEventCounts {
+                event_identifier,
+                attendees: counts.attendees,
+                invites: counts.invites,
+                // The counts can't be verified while the tree is rebuilt
+                certificate: match Self::is_ready() {
+                    true => data_certificate().map(ByteBuf::from),
+                    false => None,
+                },
+                witness: ByteBuf::from(witness.to_cbor()),
+            }
+        })
+    }
+
+    // Method to get the counts of an event without the proof, read from the indexes while the tree is rebuilt
+    pub fn get_event_count(event_identifier: Principal) -> EventCount {
+        let counts = match Self::is_ready() {
+            false => Self::read_event_counts(&event_identifier),
+            true => CERTIFIED_TREE.with(|certified| {
+                certified
+                    .borrow()
+                    .counts
+                    .get(event_identifier.as_slice())
+                    .cloned()
+                    .unwrap_or_default()
+            }),
+        };
+
+        EventCount {
+            event_identifier,
+            attendees: counts.attendees,
+            invites: counts.invites,
+        }
+    }
+
+    // Method to get the status of a principal for an event with the witness and the certificate to verify it
+    pub fn get_membership_proof(
+        principal: Principal,
+        event_identifier: Principal,
+    ) -> Result<MembershipProof, ApiError> {
+        if !Self::is_ready() {
+            return Err(api_error(
+                ApiErrorType::BadRequest,
+                "CERTIFICATION_NOT_READY",
+                "The certified data is being rebuilt, try again later",
+                "Certification",
+                "get_membership_proof",
+                None,
+            ));
+        }
+
+        CERTIFIED_TREE.with(|certified| {
+            let certified = certified.borrow();
+            let event_key = event_identifier.as_slice();
+            let principal_key = principal.as_slice();
+
+            let status = certified
+                .members
+                .get(event_key)
+                .and_then(|members| members.get(principal_key))
+                .and_then(|value| {
+                    vec![
+                        AttendeeStatus::Joined,
+                        AttendeeStatus::Invited,
+                        AttendeeStatus::Requested,
+                    ]
+                    .into_iter()
+                    .find(|status| membership_status_value(status) == value.as_slice())
+                });
+
+            let status = match status {
+                None => {
+                    return Err(api_error(
+                        ApiErrorType::NotFound,
+                        "MEMBERSHIP_NOT_FOUND",
+                        "The principal is not an attendee or invitee of the event",
+                        "Certification",
+                        "get_membership_proof",
+                        None,
+                    ))
+                }
+                Some(_status) => _status,
+            };
+
+            let witness = certified.witness(
+                None,
+                Some(certified.members.witness(event_key, |members| {
+                    members.witness(principal_key, |value| value.as_hash_tree())
+                })),
+                false,
+            );
+
+            Ok(MembershipProof {
+                principal,
+                event_identifier,
+                status,
+                certificate: data_certificate().map(ByteBuf::from),
+                witness: ByteBuf::from(witness.to_cbor()),
+            })
+        })
+    }
+
+    // Events the attendee joined or is invited for, before or after a change
+    fn events(previous: &Attendee, current: &Attendee) -> Vec<Principal> {
+        let mut events: Vec<Principal> = previous
+            .joined
+            .keys()
+            .chain(previous.invites.keys())
+            .chain(current.joined.keys())
+            .chain(current.invites.keys())
+            .copied()
+            .collect();
+        events.sort();
+        events.dedup();
+        events
+    }
+
+    // A join takes precedence over an invite for the same event
+    fn status(attendee: &Attendee, event_identifier: &Principal) -> Option<AttendeeStatus> {
+        if attendee.joined.contains_key(event_identifier) {
+            return Some(AttendeeStatus::Joined);
+        }
+        attendee
+            .invites
+            .get(event_identifier)
+            .map(|invite| AttendeeStatus::from(&invite.invite_type))
+    }
+
+    fn read_event_counts(event_identifier: &Principal) -> EventCounts {
+        EventCounts {
+            attendees: ATTENDEE_COUNTS.with(|attendee_counts| {
+                attendee_counts
+                    .borrow()
+                    .get(&event_identifier.to_string())
+                    .unwrap_or(0)
+            }),
+            invites: Self::count_event_invites(event_identifier),
+        }
+    }
+
+    fn count_event_invites(event_identifier: &Principal) -> u64 {
+        let prefix = format!("{}:", event_identifier);
+        INVITE_INDEX.with(|index| {
+            index
+                .borrow()
+                .range(prefix.clone()..)
+                .take_while(|(key, _)| key.starts_with(&prefix))
+                .count() as u64
+        })
+    }
+
+    // The partial tree of a rebuild is not certified
+    fn certify() {
+        if !Self::is_ready() {
+            return;
+        }
+        let root_hash = CERTIFIED_TREE.with(|certified| certified.borrow().root_hash());
+        set_certified_data(&root_hash);
+    }
+}
@@ -8,11 +8,12 @@ use ic_cdk::{
             provisional::CanisterIdRecord,
         },
     },
-    caller, id, init, query, update,
+    caller, id, init, post_upgrade, query, update,
 };
 
 use ic_scalable_canister::ic_scalable_misc::{
     enums::api_error_type::ApiError,
+    helpers::serialize_helper::deserialize,
     models::http_models::{HttpRequest, HttpResponse},
 };
 #[allow(unused_imports)]
@@ -20,8 +21,24 @@ use ic_scalable_canister::{
     ic_methods,
     store::{Data, Metadata},
 };
+use shared::{
+    attendee_model::Attendee,
+    metrics_helper::{is_metrics_request, render_attendee_metrics},
+    operator_model::{OperatorRole, OperatorRoles},
+};
 
-use crate::store::{ENTRIES, STABLE_DATA};
+use crate::{
+    audit::Audit,
+    calendar::Calendar,
+    certification::Certification,
+    history::History,
+    http::Http,
+    metrics::Metrics,
+    outbox::Outbox,
+    roles::Roles,
+    store::{Store, ATTENDEE_COUNTS, ENTRIES, HISTORY, STABLE_DATA},
+    subscriptions::Subscriptions,
+};
 
 #[query]
 pub fn sanity_check() -> String {
@@ -30,11 +47,19 @@ pub fn sanity_check() -> String {
 
 #[update(guard = "auth")]
 async fn add_entry_by_parent(entry: Vec<u8>) -> Result<(), ApiError> {
-    STABLE_DATA.with(|v| {
+    let attendee = deserialize::<Attendee>(entry.clone());
+    let result = STABLE_DATA.with(|v| {
         ENTRIES.with(|entries| {
             Data::add_entry_by_parent(v, entries, caller(), entry, Some("eae".to_string()))
         })
-    })
+    });
+
+    // Index the moved attendee and register it in the directory of the parent
+    if let (Ok(_), Ok(_attendee)) = (&result, attendee) {
+        Store::index_attendee(_attendee.principal);
+        Outbox::enqueue_directory_registration(_attendee.principal);
+    }
+    result
 }
 
 #[update]
@@ -42,20 +67,81 @@ fn accept_cycles() -> u64 {
     ic_methods::accept_cycles()
 }
 
+// HTTP request handler, serves the read-only JSON routes and the canister metrics
 #[query]
 fn http_request(req: HttpRequest) -> HttpResponse {
-    STABLE_DATA.with(|data| {
+    if let Some(_response) = Http::handle(&req) {
+        return _response;
+    }
+
+    let is_metrics = is_metrics_request(&req.method, &req.url);
+    let mut response = STABLE_DATA.with(|data| {
         ENTRIES.with(|entries| {
             Data::http_request_with_metrics(data, entries.borrow().len() as usize, req, vec![])
         })
-    })
+    });
+
+    // Append the attendee domain metrics to the canister metrics
+    if is_metrics && response.status_code == 200 {
+        response
+            .body
+            .extend(render_attendee_metrics(&Metrics::get_attendee_metrics()).into_bytes());
+    }
+    response
 }
 
+// The operator roles are passed by the parent, canisters installed without roles only accept the role management methods
 #[init]
-pub fn init(parent: Principal, name: String, identifier: usize) {
+pub fn init(
+    parent: Principal,
+    name: String,
+    identifier: usize,
+    operator_roles: Option<OperatorRoles>,
+) {
     STABLE_DATA.with(|data| {
         ic_methods::init(data, parent, name, identifier);
-    })
+    });
+
+    if let Some(_operator_roles) = operator_roles {
+        Roles::set(_operator_roles);
+    }
+
+    Store::set_event_index_version();
+    Certification::rebuild();
+    Outbox::start_timer();
+    Audit::start_timer();
+    Subscriptions::start_timer();
+    Calendar::start_timer();
+}
+
+// Timers are not persisted over upgrades, so they need to be restarted
+// The parent passes its operator roles on upgrade, so the roles of this canister are in sync after an upgrade
+#[post_upgrade]
+pub fn post_upgrade(operator_roles: Option<OperatorRoles>) {
+    if let Some(_operator_roles) = operator_roles {
+        Roles::set(_operator_roles);
+    }
+
+    // Build the attendee count index for canisters that were created before it existed
+    if ATTENDEE_COUNTS.with(|c| c.borrow().is_empty()) && ENTRIES.with(|e| !e.borrow().is_empty()) {
+        Store::rebuild_attendee_counts();
+    }
+
+    // Build the event indexes once for canisters that were created before they (or the latest index) existed
+    Store::migrate_event_indexes();
+
+    // The history is empty when upgrading from a version without history
+    if HISTORY.with(|h| h.borrow().is_empty()) && ENTRIES.with(|e| !e.borrow().is_empty()) {
+        History::backfill();
+    }
+
+    // The certified counts are kept on the heap, so they are rebuilt after every upgrade
+    Certification::rebuild();
+
+    Outbox::start_timer();
+    Audit::start_timer();
+    Subscriptions::start_timer();
+    Calendar::start_timer();
 }
 
 // Hacky way to expose the candid interface to the outside world
@@ -64,11 +150,23 @@ pub fn __export_did_tmp_() -> String {
     use candid::export_service;
     use candid::Principal;
     use shared::attendee_model::*;
+    use shared::audit_model::*;
+    use shared::certification_model::*;
+    use shared::chunk_model::*;
+    use shared::export_model::*;
+    use shared::history_model::*;
+    use shared::icrc3_model::*;
+    use shared::metrics_model::*;
+    use shared::operator_model::*;
+    use shared::outbox_model::*;
+    use shared::subscription_model::*;
 
     use ic_canister_backup::models::*;
     use ic_cdk::api::management_canister::http_request::HttpResponse;
     use ic_scalable_canister::ic_scalable_misc::enums::api_error_type::ApiError;
+    use ic_scalable_canister::ic_scalable_misc::models::date_models::DateRange;
     use ic_scalable_canister::ic_scalable_misc::models::http_models::HttpRequest;
+    use ic_scalable_canister::ic_scalable_misc::models::paged_response_models::PagedResponse;
     export_service!();
     __export_service()
 }
@@ -86,9 +184,25 @@ async fn canister_status() -> Result<(CanisterStatusResponse,), (RejectionCode,
 }
 
 pub fn is_monitor() -> Result<(), String> {
-    const OWNERS: [&str; 1] = ["6or45-oyaaa-aaaap-absua-cai"];
+    match Roles::has_role(OperatorRole::Monitor, &caller()) {
+        true => Ok(()),
+        false => Err("Unauthorized".to_string()),
+    }
+}
+
+// Guard for the attendance log, the blocks contain the invites so they can only be read by the monitors and the subscribed canisters
+pub fn is_monitor_or_subscriber() -> Result<(), String> {
+    match Roles::has_role(OperatorRole::Monitor, &caller())
+        || Subscriptions::get_subscription(caller()).is_some()
+    {
+        true => Ok(()),
+        false => Err("Unauthorized".to_string()),
+    }
+}
 
-    match OWNERS.iter().any(|p| p == &caller().to_string()) {
+// Guard for the role management, the parent is a controller of this canister
+pub fn is_role_manager() -> Result<(), String> {
+    match Roles::can_manage(&caller()) {
         true => Ok(()),
         false => Err("Unauthorized".to_string()),
     }
@@ -0,0 +1,269 @@
+use std::collections::{HashMap, HashSet};
+
+use candid::Principal;
+use ic_cdk::api::time;
+use ic_scalable_canister::ic_scalable_misc::{
+    enums::api_error_type::{ApiError, ApiErrorType},
+    helpers::error_helper::api_error,
+    models::date_models::DateRange,
+};
+use ic_scalable_canister::store::Data;
+
+use shared::{
+    attendee_model::MAX_PAGE_LIMIT,
+    hex_helper::{from_hex, to_hex},
+    history_model::{
+        AttendanceAction, AttendanceHistory, AttendanceHistoryEntry, AttendanceHistoryPrivacy,
+        AttendanceStats,
+    },
+};
+
+use crate::store::{Store, ENTRIES, HISTORY, HISTORY_PRIVACY, STABLE_DATA};
+
+// Number of groups that are returned as most active groups
+const MAX_ACTIVE_GROUPS: usize = 5;
+
+pub struct History;
+
+impl History {
+    // Method to add an entry to the attendance history of a principal
+    pub fn record(
+        principal: Principal,
+        event_identifier: Principal,
+        group_identifier: Principal,
+        action: AttendanceAction,
+    ) {
+        Self::_insert(
+            principal,
+            event_identifier,
+            group_identifier,
+            action,
+            time(),
+        );
+    }
+
+    // Method to add the current joins of the stored attendees to the empty history
+    // used once after upgrading from a version without history
+    pub fn backfill() {
+        ENTRIES.with(|entries| {
+            entries.borrow().iter().for_each(|(_, _attendee)| {
+                _attendee
+                    .joined
+                    .iter()
+                    .for_each(|(_event_identifier, _join)| {
+                        Self::_insert(
+                            _attendee.principal,
+                            *_event_identifier,
+                            _join.group_identifier,
+                            AttendanceAction::Joined,
+                            _join.created_at,
+                        )
+                    })
+            })
+        });
+    }
+
+    // The key is ordered by time, the sequence keeps the entries of a principal that are added in the same message apart
+    // (ex; a join and a check-in) and keeps them in the order they are added
+    fn _insert(
+        principal: Principal,
+        event_identifier: Principal,
+        group_identifier: Principal,
+        action: AttendanceAction,
+        created_at: u64,
+    ) {
+        HISTORY.with(|history| {
+            let mut history = history.borrow_mut();
+            let prefix = format!("{}:{:020}:", principal, created_at);
+            let sequence = history
+                .range(prefix.clone()..)
+                .take_while(|(key, _)| key.starts_with(&prefix))
+                .count();
+
+            history.insert(
+                format!("{}{:05}:{}", prefix, sequence, event_identifier),
+                AttendanceHistoryEntry {
+                    event_identifier,
+                    group_identifier,
+                    action,
+                    created_at,
+                },
+            )
+        });
+    }
+
+    // Method to get a page of the attendance history of a principal and the stats over the history within the range
+    // the stats are calculated over all the entries within the range, not only over the page
+    // the history of a principal that hid it can only be read by the principal itself
+    pub fn get_attendance_history(
+        caller: Principal,
+        principal: Principal,
+        range: Option<DateRange>,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> Result<AttendanceHistory, ApiError> {
+        if caller != principal && Self::get_privacy(principal) == AttendanceHistoryPrivacy::Private
+        {
+            return Err(api_error(
+                ApiErrorType::Unauthorized,
+                "HISTORY_HIDDEN",
+                "The attendance history of this principal is private",
+                STABLE_DATA
+                    .with(|data| Data::get_name(data.borrow().get()))
+                    .as_str(),
+                "get_attendance_history",
+                None,
+            ));
+        }
+
+        let prefix = format!("{}:", principal);
+        let entries: Vec<(String, AttendanceHistoryEntry)> = HISTORY.with(|history| {
+            history
+                .borrow()
+                .range(prefix.clone()..)
+                .take_while(|(key, _)| key.starts_with(&prefix))
+                .filter(|(_, entry)| match &range {
+                    None => true,
+                    Some(_range) => {
+                        entry.created_at >= _range.start_date && entry.created_at <= _range.end_date
+                    }
+                })
+                .map(|(key, entry)| (key[prefix.len()..].to_string(), entry))
+                .collect()
+        });
+
+        let stats = Self::get_stats(
+            &entries
+                .iter()
+                .map(|(_, entry)| entry.clone())
+                .collect::<Vec<AttendanceHistoryEntry>>(),
+        );
+
+        // The cursor is the key of the last entry of the previous page without the principal
+        let start = match cursor.as_deref().map(Self::decode_cursor) {
+            None => 0,
+            Some(Some(_key)) => entries.partition_point(|(key, _)| key <= &_key),
+            // an invalid cursor results in an empty page, so a caller doesnt start over from the first page
+            Some(None) => entries.len(),
+        };
+        let limit = limit.min(MAX_PAGE_LIMIT);
+        // Take one more entry than the limit to see if there is a next page
+        let mut page: Vec<(String, AttendanceHistoryEntry)> =
+            entries.into_iter().skip(start).take(limit + 1).collect();
+
+        let next_cursor = if page.len() > limit {
+            page.truncate(limit);
+            page.last().map(|(key, _)| to_hex(key.as_bytes()))
+        } else {
+            None
+        };
+
+        Ok(AttendanceHistory {
+            principal,
+            entries: page.into_iter().map(|(_, entry)| entry).collect(),
+            next_cursor,
+            stats,
+        })
+    }
+
+    fn decode_cursor(cursor: &str) -> Option<String> {
+        String::from_utf8(from_hex(cursor)?).ok()
+    }
+
+    // Method to get the time of the last check-in of a principal at an event
+    // the privacy setting doesnt apply, the check-ins of an event are part of the data of the event
+    pub fn get_last_check_in(principal: Principal, event_identifier: Principal) -> Option<u64> {
+        let prefix = format!("{}:", principal);
+        HISTORY.with(|history| {
+            history
+                .borrow()
+                .range(prefix.clone()..)
+                .take_while(|(key, _)| key.starts_with(&prefix))
+                .map(|(_, entry)| entry)
+                .filter(|entry| {
+                    entry.event_identifier == event_identifier
+                        && entry.action == AttendanceAction::CheckedIn
+                })
+                .map(|entry| entry.created_at)
+                .last()
+        })
+    }
+
+    // Method to set if the attendance history of the caller can be read by others
+    pub fn set_privacy(caller: Principal, privacy: AttendanceHistoryPrivacy) {
+        HISTORY_PRIVACY.with(|privacies| {
+            let mut privacies = privacies.borrow_mut();
+            match privacy {
+                AttendanceHistoryPrivacy::Public => privacies.remove(&caller.to_string()),
+                AttendanceHistoryPrivacy::Private => privacies.insert(caller.to_string(), time()),
+            }
+        });
+    }
+
+    pub fn get_privacy(principal: Principal) -> AttendanceHistoryPrivacy {
+        match HISTORY_PRIVACY
+            .with(|privacies| privacies.borrow().contains_key(&principal.to_string()))
+        {
+            true => AttendanceHistoryPrivacy::Private,
+            false => AttendanceHistoryPrivacy::Public,
+        }
+    }
+
+    // Method to calculate the stats over the history entries, the entries are ordered by time
+    fn get_stats(entries: &[AttendanceHistoryEntry]) -> AttendanceStats {
+        use AttendanceAction::*;
+        let mut joined_events: HashMap<Principal, Principal> = HashMap::new();
+        let mut current_events: HashSet<Principal> = HashSet::new();
+        let mut checked_in_events: HashSet<Principal> = HashSet::new();
+
+        for entry in entries {
+            match entry.action {
+                Joined => {
+                    joined_events.insert(entry.event_identifier, entry.group_identifier);
+                    current_events.insert(entry.event_identifier);
+                }
+                Left | Removed => {
+                    current_events.remove(&entry.event_identifier);
+                }
+                CheckedIn => {
+                    checked_in_events.insert(entry.event_identifier);
+                }
+            }
+        }
+
+        // Events that are still joined and ended but the principal never checked in at
+        // upcoming events and events of which the dates are not fetched yet are not counted
+        let now = time();
+        let ended_events: Vec<&Principal> = current_events
+            .iter()
+            .filter(|event_identifier| Store::has_event_ended(event_identifier, now))
+            .collect();
+        let no_shows = ended_events
+            .iter()
+            .filter(|event_identifier| !checked_in_events.contains(event_identifier))
+            .count();
+        let no_show_rate = match ended_events.len() {
+            0 => 0.0,
+            _total => no_shows as f64 / _total as f64,
+        };
+
+        let mut group_counts: HashMap<Principal, u64> = HashMap::new();
+        joined_events.values().for_each(|group_identifier| {
+            *group_counts.entry(*group_identifier).or_insert(0) += 1;
+        });
+        let mut most_active_groups: Vec<(Principal, u64)> = group_counts.into_iter().collect();
+        most_active_groups.sort_by(|(a_group, a_count), (b_group, b_count)| {
+            b_count
+                .cmp(a_count)
+                .then_with(|| a_group.to_string().cmp(&b_group.to_string()))
+        });
+        most_active_groups.truncate(MAX_ACTIVE_GROUPS);
+
+        AttendanceStats {
+            total_events_joined: joined_events.len() as u64,
+            total_events_attended: checked_in_events.len() as u64,
+            no_show_rate,
+            most_active_groups,
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use ic_scalable_canister::ic_scalable_misc::{
+    helpers::paging_helper::get_paged_data,
+    models::http_models::{HeaderField, HttpRequest, HttpResponse},
+};
+
+use shared::http_helper::{
+    api_error_response, error_response, json_response, method_not_allowed_response, HttpRoute,
+};
+
+use crate::{
+    calendar::Calendar, certification::Certification, store::Store, transaction_log::TransactionLog,
+};
+
+pub struct Http;
+
+impl Http {
+    // Method to handle the read-only JSON routes, returns none for the paths that are not part of the api
+    pub fn handle(req: &HttpRequest) -> Option<HttpResponse> {
+        let route = HttpRoute::parse(req);
+        if !matches!(
+            route.path.first().map(|segment| segment.as_str()),
+            Some("events") | Some("attendees") | Some("calendar") | Some("icrc3")
+        ) {
+            return None;
+        }
+
+        if route.method != "GET" {
+            return Some(method_not_allowed_response());
+        }
+
+        let response = if route.matches(&["events", ":event_identifier", "attendees"]) {
+            Self::get_event_attendees(&route)
+        } else if route.matches(&["events", ":event_identifier", "count"]) {
+            Self::get_event_count(&route)
+        } else if route.matches(&["attendees", ":principal"]) {
+            Self::get_attendee(&route)
+        } else if route.matches(&["calendar", ":token"]) {
+            Self::get_calendar_feed(&route)
+        } else if route.matches(&["icrc3", "block-types"]) {
+            Ok(json_response(
+                200,
+                &TransactionLog::get_block_type_documentation(),
+            ))
+        } else {
+            Err(error_response(404, "Route not found"))
+        };
+
+        Some(response.unwrap_or_else(|err| err))
+    }
+
+    // GET /events/{event_identifier}/attendees?limit=&cursor=
+    fn get_event_attendees(route: &HttpRoute) -> Result<HttpResponse, HttpResponse> {
+        let event_identifier = route.principal(1)?;
+        let page =
+            Store::get_event_attendees_page_v2(event_identifier, route.limit()?, route.cursor());
+        Ok(json_response(200, &page))
+    }
+
+    // GET /events/{event_identifier}/count
+    fn get_event_count(route: &HttpRoute) -> Result<HttpResponse, HttpResponse> {
+        let event_identifier = route.principal(1)?;
+        Ok(json_response(
+            200,
+            &Certification::get_event_count(event_identifier),
+        ))
+    }
+
+    // GET /attendees/{principal}?limit=&page=
+    fn get_attendee(route: &HttpRoute) -> Result<HttpResponse, HttpResponse> {
+        let principal = route.principal(1)?;
+        let (limit, page) = (route.limit()?, route.page()?);
+        match Store::get_attending_from_principal_v2(principal) {
+            Ok(_joined) => Ok(json_response(200, &get_paged_data(_joined, limit, page))),
+            Err(err) => Err(api_error_response(&err)),
+        }
+    }
+
+    // GET /calendar/{token}.ics
+    fn get_calendar_feed(route: &HttpRoute) -> Result<HttpResponse, HttpResponse> {
+        let token = route.path[1].trim_end_matches(".ics");
+        match Calendar::get_feed(token) {
+            None => Err(error_response(404, "Calendar feed not found")),
+            Some(_feed) => Ok(HttpResponse {
+                status_code: 200,
+                headers: vec![HeaderField(
+                    "content-type".to_string(),
+                    "text/calendar; charset=utf-8".to_string(),
+                )],
+                body: _feed.into_bytes(),
+            }),
+        }
+    }
+}
@@ -1,7 +1,18 @@
 pub static IDENTIFIER_KIND: &str = "eae";
 
+pub mod audit;
 pub mod backup;
+pub mod calendar;
+pub mod certification;
 pub mod default;
+pub mod history;
+pub mod http;
 pub mod methods;
+pub mod metrics;
+pub mod outbox;
+pub mod roles;
+pub mod snapshot;
 mod stable_backup;
 pub mod store;
+pub mod subscriptions;
+pub mod transaction_log;
@@ -1,10 +1,50 @@
 use candid::Principal;
 use ic_cdk::{caller, query, update};
-use ic_scalable_canister::ic_scalable_misc::enums::api_error_type::ApiError;
+use ic_scalable_canister::{
+    ic_scalable_misc::{
+        enums::api_error_type::{ApiError, ApiErrorType},
+        helpers::{error_helper::api_error, paging_helper::get_paged_data},
+        models::{date_models::DateRange, paged_response_models::PagedResponse},
+    },
+    store::Data,
+};
 
-use shared::attendee_model::{Attendee, InviteAttendeeResponse, JoinedAttendeeResponse};
+use shared::{
+    attendee_model::{
+        Attendee, AttendeeListRequest, CursorPagedResponse, ForwardedMutation, GroupAttendee,
+        GroupAttendeeRequest, GroupEventAttendance, InviteAttendeeResponse,
+        InviteAttendeeResponseV2, JoinedAttendeeResponse, JoinedAttendeeResponseV2,
+    },
+    audit_model::{AuditAction, AuditEntry},
+    certification_model::{CertifiedEventCounts, MembershipProof},
+    chunk_model::ChunkedData,
+    export_model::EventExportRow,
+    history_model::{AttendanceHistory, AttendanceHistoryPrivacy},
+    icrc3_model::{
+        GetArchivesArgs, GetBlocksArgs, GetBlocksResult, ICRC3ArchiveInfo, ICRC3DataCertificate,
+        SupportedBlockType,
+    },
+    metrics_model::AttendeeMetrics,
+    operator_model::OperatorRoles,
+    outbox_model::{OutboxEntry, OutboxStatus},
+    subscription_model::{
+        AttendeeChangeBatch, Subscription, SubscriptionRequest, SubscriptionStatus,
+    },
+};
 
-use crate::store::STABLE_DATA;
+use crate::{
+    audit::Audit,
+    calendar::Calendar,
+    certification::Certification,
+    default::{is_monitor, is_monitor_or_subscriber, is_role_manager},
+    history::History,
+    metrics::Metrics,
+    outbox::Outbox,
+    roles::Roles,
+    store::STABLE_DATA,
+    subscriptions::{Subscriptions, MAX_CHANGES_PER_BATCH},
+    transaction_log::TransactionLog,
+};
 
 use super::store::Store;
 
@@ -15,7 +55,15 @@ async fn join_event(
     event_identifier: Principal,
     group_identifier: Principal,
 ) -> Result<(Principal, Attendee), ApiError> {
-    Store::join_event(caller(), event_identifier, group_identifier).await
+    let result = Store::join_event(caller(), event_identifier, group_identifier).await;
+    Audit::record_failure(
+        AuditAction::Join,
+        caller(),
+        Some(event_identifier),
+        Some(group_identifier),
+        &result,
+    );
+    result
 }
 
 // Method to invite a member to an event
@@ -26,10 +74,20 @@ async fn invite_to_event(
     member_identifier: Principal,
     group_identifier: Principal,
 ) -> Result<(Principal, Attendee), ApiError> {
-    match Store::can_write(caller(), group_identifier, member_identifier).await {
-        Ok(_) => Store::invite_to_event(event_identifier, attendee_principal, group_identifier),
+    let result = match Store::can_write(caller(), group_identifier, member_identifier).await {
+        Ok(_) => {
+            Store::invite_to_event(event_identifier, attendee_principal, group_identifier).await
+        }
         Err(err) => Err(err),
-    }
+    };
+    Audit::record_failure(
+        AuditAction::Invite,
+        attendee_principal,
+        Some(event_identifier),
+        Some(group_identifier),
+        &result,
+    );
+    result
 }
 
 // Method to accept an invite to an event as a admin
@@ -40,10 +98,20 @@ async fn accept_user_request_event_invite(
     member_identifier: Principal,
     group_identifier: Principal,
 ) -> Result<(Principal, Attendee), ApiError> {
-    match Store::can_write(caller(), group_identifier, member_identifier).await {
-        Ok(_) => Store::accept_user_request_event_invite(attendee_principal, event_identifier),
+    let result = match Store::can_write(caller(), group_identifier, member_identifier).await {
+        Ok(_) => {
+            Store::accept_user_request_event_invite(attendee_principal, event_identifier).await
+        }
         Err(err) => Err(err),
-    }
+    };
+    Audit::record_failure(
+        AuditAction::AcceptUserRequest,
+        attendee_principal,
+        Some(event_identifier),
+        Some(group_identifier),
+        &result,
+    );
+    result
 }
 
 // Method to accept an invite to an event as a user
@@ -51,7 +119,16 @@ async fn accept_user_request_event_invite(
 async fn accept_owner_request_event_invite(
     event_identifier: Principal,
 ) -> Result<(Principal, Attendee), ApiError> {
-    Store::accept_owner_request_event_invite(caller(), event_identifier)
+    let group_identifier = Store::get_event_group(caller(), event_identifier);
+    let result = Store::accept_owner_request_event_invite(caller(), event_identifier).await;
+    Audit::record_failure(
+        AuditAction::AcceptOwnerRequest,
+        caller(),
+        Some(event_identifier),
+        group_identifier,
+        &result,
+    );
+    result
 }
 
 // Method to get the number of attendees for an event
@@ -66,6 +143,29 @@ fn get_event_invites_count(event_identifiers: Vec<Principal>) -> Vec<(Principal,
     Store::get_group_invites_count(event_identifiers)
 }
 
+// Method to get the attendee and invite count of an event with a certificate and witness to verify them,
+// needs to be called directly on this canister because certificates are not available in inter-canister calls
+#[query]
+fn get_certified_event_counts(event_identifier: Principal) -> CertifiedEventCounts {
+    Certification::get_certified_event_counts(event_identifier)
+}
+
+// Method to get the status of a principal for an event with a certificate and witness to verify it,
+// needs to be called directly on the child canister that holds the record of the principal
+#[query]
+fn get_membership_proof(
+    principal: Principal,
+    event_identifier: Principal,
+) -> Result<MembershipProof, ApiError> {
+    Certification::get_membership_proof(principal, event_identifier)
+}
+
+// Method to check if the certified data is rebuilt after an install or upgrade, used by the health check of the rollouts
+#[query]
+fn is_certification_ready() -> bool {
+    Certification::is_ready()
+}
+
 // Method to get the attendees for an event
 #[query]
 fn get_event_attendees(
@@ -74,12 +174,108 @@ fn get_event_attendees(
     Ok(Store::get_event_attendees(event_identifier))
 }
 
+// Method to get a page of the attendees for an event, ordered by principal
+// pass the next_cursor of the previous response to get the next page
+#[query]
+fn get_event_attendees_page(
+    event_identifier: Principal,
+    limit: usize,
+    cursor: Option<String>,
+) -> CursorPagedResponse<JoinedAttendeeResponse> {
+    Store::get_event_attendees_page(event_identifier, limit, cursor)
+}
+
+// Method to get a page of the invites for an event, ordered by principal
+// pass the next_cursor of the previous response to get the next page
+// This methods is used by the parent canister, that checks the read permission of the caller
+#[query(guard = "is_parent")]
+fn get_event_invites_page(
+    event_identifier: Principal,
+    limit: usize,
+    cursor: Option<String>,
+) -> CursorPagedResponse<InviteAttendeeResponse> {
+    Store::get_event_invites_page(event_identifier, limit, cursor)
+}
+
+// Method to get the attendees for an event with the group, status and timestamps of the join
+#[query]
+fn get_event_attendees_v2(event_identifier: Principal) -> Vec<JoinedAttendeeResponseV2> {
+    Store::get_event_attendees_v2(event_identifier)
+}
+
+// Method to get a page of the attendees for an event with the group, status and timestamps of the join
+// pass the next_cursor of the previous response to get the next page
+#[query]
+fn get_event_attendees_page_v2(
+    event_identifier: Principal,
+    limit: usize,
+    cursor: Option<String>,
+) -> CursorPagedResponse<JoinedAttendeeResponseV2> {
+    Store::get_event_attendees_page_v2(event_identifier, limit, cursor)
+}
+
+// Method to get a page of the invites for an event with the group, status and timestamps of the invite
+// pass the next_cursor of the previous response to get the next page
+// This methods is used by the parent canister, that checks the read permission of the caller
+#[query(guard = "is_parent")]
+fn get_event_invites_page_v2(
+    event_identifier: Principal,
+    limit: usize,
+    cursor: Option<String>,
+) -> CursorPagedResponse<InviteAttendeeResponseV2> {
+    Store::get_event_invites_page_v2(event_identifier, limit, cursor)
+}
+
+// Method to get the participation of the attendees in the events of a group, ordered by principal
+// This methods is used by the parent canister, that checks the read permission of the caller
+#[query(guard = "is_parent")]
+fn get_group_attendees(request: GroupAttendeeRequest) -> PagedResponse<GroupAttendee> {
+    get_paged_data(
+        Store::get_group_attendees(request.group_identifier, request.upcoming_only),
+        request.limit,
+        request.page,
+    )
+}
+
+// Method to get the number of attendees and invites per event of a group, ordered by event
+// This methods is used by the parent canister, that checks the read permission of the caller
+#[query(guard = "is_parent")]
+fn get_group_event_attendance(
+    request: GroupAttendeeRequest,
+) -> PagedResponse<GroupEventAttendance> {
+    get_paged_data(
+        Store::get_group_event_attendance(request.group_identifier, request.upcoming_only),
+        request.limit,
+        request.page,
+    )
+}
+
 // Method to get the caller his joined events and invites
 #[query]
 fn get_self() -> Result<(Principal, Attendee), ApiError> {
     Store::get_self(caller())
 }
 
+// Method to get the joined events and invites of a principal
+// This methods is used by the parent canister to find attendees across the child canisters
+#[query]
+fn get_attendee_by_principal(principal: Principal) -> Result<(Principal, Attendee), ApiError> {
+    if caller() != STABLE_DATA.with(|data| data.borrow().get().parent) {
+        return Err(api_error(
+            ApiErrorType::Unauthorized,
+            "UNAUTHORIZED",
+            "Only the parent canister can call this method",
+            STABLE_DATA
+                .with(|data| Data::get_name(data.borrow().get()))
+                .as_str(),
+            "get_attendee_by_principal",
+            None,
+        ));
+    }
+
+    Store::get_attendee_by_principal(principal)
+}
+
 // Method to get the principal joined events
 #[query]
 fn get_attending_from_principal(
@@ -88,16 +284,83 @@ fn get_attending_from_principal(
     Store::get_attending_from_principal(principal)
 }
 
+// Method to get the principal joined events with the group, status and timestamps of the join
+#[query]
+fn get_attending_from_principal_v2(
+    principal: Principal,
+) -> Result<Vec<JoinedAttendeeResponseV2>, ApiError> {
+    Store::get_attending_from_principal_v2(principal)
+}
+
+// Method to get a page of the attendance history of a principal with the stats over the history within the range
+// pass the next_cursor of the previous response to get the next page
+// the history of a principal that hid it can only be read by the principal itself
+#[query]
+fn get_attendance_history(
+    principal: Principal,
+    range: Option<DateRange>,
+    limit: usize,
+    cursor: Option<String>,
+) -> Result<AttendanceHistory, ApiError> {
+    History::get_attendance_history(caller(), principal, range, limit, cursor)
+}
+
+// Method to set if the attendance history of the caller can be read by others
+#[update(guard = "auth")]
+fn set_attendance_history_privacy(privacy: AttendanceHistoryPrivacy) {
+    History::set_privacy(caller(), privacy)
+}
+
+// Method to check in an attendee at an event as a admin
+#[update(guard = "auth")]
+async fn check_in_attendee(
+    attendee_principal: Principal,
+    event_identifier: Principal,
+    group_identifier: Principal,
+    member_identifier: Principal,
+) -> Result<(), ApiError> {
+    let result = match Store::can_write(caller(), group_identifier, member_identifier).await {
+        Ok(_caller) => Store::check_in_attendee(attendee_principal, event_identifier).await,
+        Err(err) => Err(err),
+    };
+    Audit::record_failure(
+        AuditAction::CheckIn,
+        attendee_principal,
+        Some(event_identifier),
+        Some(group_identifier),
+        &result,
+    );
+    result
+}
+
 // Method to leave an event as a user
 #[update(guard = "auth")]
-fn leave_event(event_identifier: Principal) -> Result<(), ApiError> {
-    Store::remove_join_from_attendee(caller(), event_identifier)
+async fn leave_event(event_identifier: Principal) -> Result<(), ApiError> {
+    let group_identifier = Store::get_event_group(caller(), event_identifier);
+    let result = Store::remove_join_from_attendee(caller(), caller(), event_identifier).await;
+    Audit::record_failure(
+        AuditAction::Leave,
+        caller(),
+        Some(event_identifier),
+        group_identifier,
+        &result,
+    );
+    result
 }
 
 // Method to remove an event invite as a user
 #[update(guard = "auth")]
-fn remove_invite(event_identifier: Principal) -> Result<(), ApiError> {
-    Store::remove_invite_from_event(caller(), event_identifier)
+async fn remove_invite(event_identifier: Principal) -> Result<(), ApiError> {
+    let group_identifier = Store::get_event_group(caller(), event_identifier);
+    let result = Store::remove_invite_from_event(caller(), event_identifier).await;
+    Audit::record_failure(
+        AuditAction::RemoveInvite,
+        caller(),
+        Some(event_identifier),
+        group_identifier,
+        &result,
+    );
+    result
 }
 
 // Method to remove an event attendee as a admin
@@ -108,10 +371,20 @@ async fn remove_attendee_from_event(
     group_identifier: Principal,
     member_identifier: Principal,
 ) -> Result<(), ApiError> {
-    match Store::can_delete(caller(), group_identifier, member_identifier).await {
-        Ok(_caller) => Store::remove_join_from_attendee(attendee_principal, event_identifier),
+    let result = match Store::can_delete(caller(), group_identifier, member_identifier).await {
+        Ok(_caller) => {
+            Store::remove_join_from_attendee(caller(), attendee_principal, event_identifier).await
+        }
         Err(err) => Err(err),
-    }
+    };
+    Audit::record_failure(
+        AuditAction::RemoveAttendee,
+        attendee_principal,
+        Some(event_identifier),
+        Some(group_identifier),
+        &result,
+    );
+    result
 }
 
 // Method to remove an event invite as a admin
@@ -122,10 +395,18 @@ async fn remove_attendee_invite_from_event(
     group_identifier: Principal,
     member_identifier: Principal,
 ) -> Result<(), ApiError> {
-    match Store::can_delete(caller(), group_identifier, member_identifier).await {
-        Ok(_caller) => Store::remove_invite_from_event(principal, event_identifier),
+    let result = match Store::can_delete(caller(), group_identifier, member_identifier).await {
+        Ok(_caller) => Store::remove_invite_from_event(principal, event_identifier).await,
         Err(err) => Err(err),
-    }
+    };
+    Audit::record_failure(
+        AuditAction::RemoveAttendeeInvite,
+        principal,
+        Some(event_identifier),
+        Some(group_identifier),
+        &result,
+    );
+    result
 }
 
 // Method to get event invites for a specific event inside a group
@@ -141,48 +422,339 @@ async fn get_event_invites(
     }
 }
 
+// Method to get event invites for a specific event inside a group with the status and timestamps of the invite
+#[update]
+async fn get_event_invites_v2(
+    event_identifier: Principal,
+    group_identifier: Principal,
+    member_identifier: Principal,
+) -> Result<Vec<InviteAttendeeResponseV2>, ApiError> {
+    match Store::can_read(caller(), group_identifier, member_identifier).await {
+        Ok(_caller) => Ok(Store::get_event_invites_v2(event_identifier)),
+        Err(err) => Err(err),
+    }
+}
+
 // Method to add the owner as an attendee
 #[update(guard = "auth")]
-fn add_owner_as_attendee(
+async fn add_owner_as_attendee(
     user_principal: Principal,
     event_identifier: Principal,
     group_identifier: Principal,
 ) -> Result<(), bool> {
-    Store::add_owner_as_attendee(user_principal, event_identifier, group_identifier)
+    let result =
+        Store::add_owner_as_attendee(user_principal, event_identifier, group_identifier).await;
+    Audit::record_failure(
+        AuditAction::AddOwner,
+        user_principal,
+        Some(event_identifier),
+        Some(group_identifier),
+        &result,
+    );
+    result
+}
+
+// Method to apply a write that is forwarded by a sibling canister that doesnt hold the attendee record
+// the actor is the principal that called the sibling canister, the write is audited with that principal
+#[update(guard = "auth")]
+async fn apply_forwarded_mutation(
+    mutation: ForwardedMutation,
+    actor: Option<Principal>,
+) -> Result<(Principal, Attendee), ApiError> {
+    Store::apply_forwarded_mutation(caller(), actor, mutation).await
+}
+
+// Method to get the principals of all the attendees on this canister
+// This methods is used by the parent canister to deduplicate attendee records
+#[query(guard = "is_parent")]
+fn get_attendee_principals() -> Vec<Principal> {
+    Store::get_attendee_principals()
+}
+
+// Method to merge an attendee record from a sibling canister into the record on this canister
+// This methods is used by the parent canister to deduplicate attendee records
+#[update(guard = "is_parent")]
+fn merge_attendee(attendee: Attendee) -> Result<(Principal, Attendee), ApiError> {
+    let target = attendee.principal;
+    let result = TransactionLog::with_deduplication(|| Store::merge_attendee(attendee));
+    Audit::record_failure(AuditAction::MergeAttendee, target, None, None, &result);
+    result
+}
+
+// Method to remove a (duplicate) attendee record from this canister
+// This methods is used by the parent canister to deduplicate attendee records
+#[update(guard = "is_parent")]
+fn remove_attendee(principal: Principal) -> Result<(), ApiError> {
+    let result = TransactionLog::with_deduplication(|| Store::remove_attendee(principal));
+    Audit::record_failure(
+        AuditAction::RemoveAttendeeRecord,
+        principal,
+        None,
+        None,
+        &result,
+    );
+    result
 }
 
 // COMPOSITE_QUERY PREPARATION
 // This methods is used by the parent canister to get members the (this) child canister
-// Data serialized and send as byte array chunks ` (bytes, (start_chunk, end_chunk)) `
+// Data serialized and send as byte array chunks, every chunk contains the snapshot id, total chunks, total bytes and hash of the data
+// The first chunk is requested without a snapshot id, the other chunks with the snapshot id of the first chunk
+// Chunks of a snapshot that expired return a SNAPSHOT_EXPIRED error
 // The parent canister can then deserialize the data and pass it to the frontend
 #[query]
 fn get_chunked_join_data(
-    event_identifier: Principal,
+    request: AttendeeListRequest,
     chunk: usize,
     max_bytes_per_chunk: usize,
-) -> (Vec<u8>, (usize, usize)) {
+    snapshot_id: Option<u64>,
+) -> Result<ChunkedData, ApiError> {
     if caller() != STABLE_DATA.with(|data| data.borrow().get().parent) {
-        return (vec![], (0, 0));
+        return Err(api_error(
+            ApiErrorType::Unauthorized,
+            "UNAUTHORIZED",
+            "Only the parent canister can call this method",
+            STABLE_DATA
+                .with(|data| Data::get_name(data.borrow().get()))
+                .as_str(),
+            "get_chunked_join_data",
+            None,
+        ));
     }
 
-    Store::get_chunked_join_data(&event_identifier, chunk, max_bytes_per_chunk)
+    Store::get_chunked_join_data(&request, chunk, max_bytes_per_chunk, snapshot_id)
 }
 
 // COMPOSITE_QUERY PREPARATION
 // This methods is used by the parent canister to get members the (this) child canister
-// Data serialized and send as byte array chunks ` (bytes, (start_chunk, end_chunk)) `
+// Data serialized and send as byte array chunks, every chunk contains the snapshot id, total chunks, total bytes and hash of the data
+// The first chunk is requested without a snapshot id, the other chunks with the snapshot id of the first chunk
+// Chunks of a snapshot that expired return a SNAPSHOT_EXPIRED error
 // The parent canister can then deserialize the data and pass it to the frontend
 #[query]
 fn get_chunked_invite_data(
-    event_identifier: Principal,
+    request: AttendeeListRequest,
     chunk: usize,
     max_bytes_per_chunk: usize,
-) -> (Vec<u8>, (usize, usize)) {
+    snapshot_id: Option<u64>,
+) -> Result<ChunkedData, ApiError> {
     if caller() != STABLE_DATA.with(|data| data.borrow().get().parent) {
-        return (vec![], (0, 0));
+        return Err(api_error(
+            ApiErrorType::Unauthorized,
+            "UNAUTHORIZED",
+            "Only the parent canister can call this method",
+            STABLE_DATA
+                .with(|data| Data::get_name(data.borrow().get()))
+                .as_str(),
+            "get_chunked_invite_data",
+            None,
+        ));
+    }
+
+    Store::get_chunked_invite_data(&request, chunk, max_bytes_per_chunk, snapshot_id)
+}
+
+// Method to cache the serialized members for a request, so the chunks can still be read after the data changes
+// returns the snapshot id that is passed to get_chunked_join_data
+#[update(guard = "is_parent")]
+fn create_join_data_snapshot(request: AttendeeListRequest) -> Result<u64, ApiError> {
+    Store::create_join_data_snapshot(&request)
+}
+
+// Method to cache the serialized invites for a request, so the chunks can still be read after the data changes
+// returns the snapshot id that is passed to get_chunked_invite_data
+#[update(guard = "is_parent")]
+fn create_invite_data_snapshot(request: AttendeeListRequest) -> Result<u64, ApiError> {
+    Store::create_invite_data_snapshot(&request)
+}
+
+// Method to get a page of the audit log entries of a group, newest first
+// pass the next_cursor of the previous response to get the next page
+#[update]
+async fn get_audit_log(
+    group_identifier: Principal,
+    member_identifier: Principal,
+    range: Option<DateRange>,
+    limit: usize,
+    cursor: Option<String>,
+) -> Result<CursorPagedResponse<AuditEntry>, ApiError> {
+    match Store::can_read(caller(), group_identifier, member_identifier).await {
+        Ok(_caller) => Ok(Audit::get_entries(
+            Some(group_identifier),
+            range,
+            limit,
+            cursor,
+        )),
+        Err(err) => Err(err),
     }
+}
 
-    Store::get_chunked_invite_data(&event_identifier, chunk, max_bytes_per_chunk)
+// Method to get a page of all the audit log entries, including the entries without a group, newest first
+// pass the next_cursor of the previous response to get the next page
+#[query(guard = "is_monitor")]
+fn get_all_audit_log(
+    range: Option<DateRange>,
+    limit: usize,
+    cursor: Option<String>,
+) -> CursorPagedResponse<AuditEntry> {
+    Audit::get_entries(None, range, limit, cursor)
+}
+
+// ICRC-3 method to get the blocks of the attendance log (monitors and subscribed canisters only)
+#[query(guard = "is_monitor_or_subscriber")]
+fn icrc3_get_blocks(args: Vec<GetBlocksArgs>) -> GetBlocksResult {
+    TransactionLog::get_blocks(args)
+}
+
+// ICRC-3 method to get the archives of the attendance log, the blocks are never archived
+#[query]
+fn icrc3_get_archives(_args: GetArchivesArgs) -> Vec<ICRC3ArchiveInfo> {
+    vec![]
+}
+
+// ICRC-3 method to get the certificate of the last block of the attendance log, only available in query calls
+#[query]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    Certification::get_tip_certificate()
+}
+
+// ICRC-3 method to get the block types of the attendance log
+#[query]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    TransactionLog::get_supported_block_types()
+}
+
+// Method for a subscribed canister to update the filter and method of its subscription, also resumes a failed subscription
+// the changes are delivered in batches to the callback method, the subscription is created by a monitor
+#[update]
+fn subscribe_attendee_changes(request: SubscriptionRequest) -> Result<Subscription, ApiError> {
+    Subscriptions::subscribe(caller(), request, false)
+}
+
+// Method to subscribe a canister on the attendee changes
+#[update(guard = "is_monitor")]
+fn create_attendee_subscription(
+    subscriber: Principal,
+    request: SubscriptionRequest,
+) -> Result<Subscription, ApiError> {
+    Subscriptions::subscribe(subscriber, request, true)
+}
+
+#[update]
+fn unsubscribe_attendee_changes() -> Result<(), ApiError> {
+    Subscriptions::unsubscribe(caller())
+}
+
+// Method to get the subscription of the calling canister
+#[query]
+fn get_attendee_subscription() -> Option<Subscription> {
+    Subscriptions::get_subscription(caller())
+}
+
+// Method to get the changes starting from a sequence, used by subscribers to fetch missed changes
+// pass the next_sequence of the previous batch to get the next batch (monitors and subscribed canisters only)
+#[query(guard = "is_monitor_or_subscriber")]
+fn get_attendee_changes(
+    from_sequence: u64,
+    limit: usize,
+    event_identifiers: Vec<Principal>,
+) -> AttendeeChangeBatch {
+    TransactionLog::get_changes(
+        from_sequence,
+        limit.min(MAX_CHANGES_PER_BATCH),
+        &event_identifiers,
+    )
+}
+
+// Method to get the subscriptions on the attendee changes
+#[query(guard = "is_monitor")]
+fn get_attendee_subscriptions(status: Option<SubscriptionStatus>) -> Vec<Subscription> {
+    Subscriptions::get_subscriptions(status)
+}
+
+// Method to create a secret token for the calendar feed of the caller, served at `/calendar/{token}.ics`
+// creating a new token revokes the previous token of the caller
+#[update(guard = "auth")]
+async fn create_calendar_feed_token() -> Result<String, ApiError> {
+    Calendar::create_feed_token(caller()).await
+}
+
+// Method to revoke the calendar feed token of the caller
+#[update(guard = "auth")]
+fn revoke_calendar_feed_token() -> Result<(), ApiError> {
+    Calendar::revoke_feed_token(caller())
+}
+
+// Method to get the calendar feed token of the caller
+#[query(guard = "auth")]
+fn get_calendar_feed_token() -> Option<String> {
+    Calendar::get_feed_token(caller())
+}
+
+// Method to fetch the details of the joined events right away (monitor only)
+#[update(guard = "is_monitor")]
+async fn refresh_calendar_event_cache() {
+    Calendar::refresh_event_cache().await
+}
+
+// Method used by the parent to export the joins and invites of an event with the check-ins
+// pass the next_cursor of the previous response to get the next page, the joins are returned before the invites
+#[query(guard = "is_parent")]
+fn get_event_export_page(
+    event_identifier: Principal,
+    limit: usize,
+    cursor: Option<String>,
+) -> CursorPagedResponse<EventExportRow> {
+    Store::get_event_export_page(event_identifier, limit, cursor)
+}
+
+// Method to get the attendee domain metrics of this canister, used by the parent to aggregate the `/metrics` output
+#[query]
+fn get_attendee_metrics() -> AttendeeMetrics {
+    Metrics::get_attendee_metrics()
+}
+
+// Method to get the pending and failed attendee count notifications
+#[query(guard = "is_monitor")]
+fn get_outbox_entries(status: Option<OutboxStatus>) -> Vec<OutboxEntry> {
+    Outbox::get_entries(status)
+}
+
+// Method to reschedule the failed attendee count notifications
+#[update(guard = "is_monitor")]
+fn retry_failed_outbox_entries() -> usize {
+    Outbox::retry_failed()
+}
+
+// Method to queue all the attendees of this canister for registration in the directory of the parent
+#[update(guard = "is_monitor")]
+fn backfill_attendee_directory() -> usize {
+    Outbox::enqueue_all_directory_registrations()
+}
+
+// Method to send the absolute attendee counts of this canister to the event canisters
+#[update(guard = "is_monitor")]
+async fn reconcile_attendee_counts() {
+    Store::reconcile_attendee_counts().await
+}
+
+// Method to get the owner and monitor principals of this canister
+#[query]
+fn get_operator_roles() -> OperatorRoles {
+    Roles::get()
+}
+
+// Method used by the parent to replace the owner and monitor principals, an update older than the stored roles is ignored
+#[update(guard = "is_role_manager")]
+fn set_operator_roles(operator_roles: OperatorRoles) -> OperatorRoles {
+    Roles::set(operator_roles)
+}
+
+pub fn is_parent() -> Result<(), String> {
+    match caller() == STABLE_DATA.with(|data| data.borrow().get().parent) {
+        true => Ok(()),
+        false => Err("Unauthorized".to_string()),
+    }
 }
 
 pub fn auth() -> Result<(), String> {
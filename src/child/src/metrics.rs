@@ -0,0 +1,59 @@
+use candid::Principal;
+use ic_cdk::api::time;
+
+use shared::{
+    metrics_helper::{get_call_failures, top_events},
+    metrics_model::AttendeeMetrics,
+    outbox_model::OutboxStatus,
+};
+
+use crate::{
+    store::{ATTENDEE_COUNTS, DIRECTORY_QUEUE, ENTRIES, INVITE_INDEX, OUTBOX},
+    transaction_log::TransactionLog,
+};
+
+// Window of the recent changes, in nanoseconds
+const RECENT_CHANGES_NANOS: u64 = 60 * 60 * 1_000_000_000;
+
+pub struct Metrics;
+
+impl Metrics {
+    // Method to get the attendee domain metrics of this canister, read from the indexes so no attendee records are loaded
+    pub fn get_attendee_metrics() -> AttendeeMetrics {
+        let counts: Vec<(Principal, u64)> = ATTENDEE_COUNTS.with(|counts| {
+            counts
+                .borrow()
+                .iter()
+                .filter_map(|(event_identifier, count)| {
+                    Principal::from_text(event_identifier)
+                        .ok()
+                        .map(|_event_identifier| (_event_identifier, count))
+                })
+                .collect()
+        });
+
+        let (outbox_pending, outbox_failed) = OUTBOX.with(|outbox| {
+            outbox
+                .borrow()
+                .iter()
+                .fold((0, 0), |(pending, failed), (_, entry)| match entry.status {
+                    OutboxStatus::Pending => (pending + 1, failed),
+                    OutboxStatus::Failed => (pending, failed + 1),
+                })
+        });
+
+        AttendeeMetrics {
+            attendees: ENTRIES.with(|entries| entries.borrow().len()),
+            joins: counts.iter().map(|(_, count)| count).sum(),
+            invites: INVITE_INDEX.with(|index| index.borrow().len()),
+            changes_last_hour: TransactionLog::count_changes_since(
+                time().saturating_sub(RECENT_CHANGES_NANOS),
+            ),
+            outbox_pending,
+            outbox_failed,
+            directory_queue: DIRECTORY_QUEUE.with(|queue| queue.borrow().len()),
+            call_failures: get_call_failures(),
+            top_events: top_events(counts),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::{cell::Cell, time::Duration};
+
+use candid::Principal;
+use ic_cdk::{api::time, id};
+use ic_cdk_timers::set_timer_interval;
+
+use shared::lock_helper::LockGuard;
+use shared::outbox_model::{AttendeeCountDelta, OutboxEntry, OutboxStatus};
+
+use crate::store::{Store, DIRECTORY_QUEUE, ENTRIES, OUTBOX};
+
+// Interval on which the outbox is drained
+const OUTBOX_INTERVAL_SECONDS: u64 = 10;
+// Interval on which the absolute attendee counts are send to the event canisters
+const RECONCILE_INTERVAL_SECONDS: u64 = 6 * 60 * 60;
+// Max number of entries that are delivered per drain
+const MAX_ENTRIES_PER_DRAIN: usize = 50;
+// Max number of principals that are registered in the directory of the parent per drain
+const MAX_REGISTRATIONS_PER_DRAIN: usize = 1000;
+// After this number of failed attempts an entry is marked as failed
+const MAX_ATTEMPTS: u32 = 10;
+// Backoff bounds in nanoseconds
+const BASE_BACKOFF_NANOS: u64 = 10 * 1_000_000_000;
+const MAX_BACKOFF_NANOS: u64 = 60 * 60 * 1_000_000_000;
+
+thread_local! {
+    // Prevents overlapping drains when a drain takes longer than the interval
+    static IS_DRAINING: Cell<bool> = const { Cell::new(false) };
+}
+
+pub struct Outbox;
+
+impl Outbox {
+    // Method to start the timers that drain the outbox and reconcile the attendee counts,
+    // needs to be called on init and post_upgrade
+    pub fn start_timer() {
+        set_timer_interval(Duration::from_secs(OUTBOX_INTERVAL_SECONDS), || {
+            ic_cdk::spawn(Self::drain())
+        });
+        set_timer_interval(Duration::from_secs(RECONCILE_INTERVAL_SECONDS), || {
+            ic_cdk::spawn(Store::reconcile_attendee_counts())
+        });
+    }
+
+    // Method to queue an attendee count delta for an event
+    // an existing entry for the same event is reused so the deltas are send as a single call
+    pub fn enqueue(event_identifier: Principal, delta: i64) {
+        let now = time();
+        OUTBOX.with(|outbox| {
+            let mut outbox = outbox.borrow_mut();
+            let entry = match outbox.get(&event_identifier.to_string()) {
+                // If there is no pending entry, create a new one
+                None => OutboxEntry {
+                    event_identifier,
+                    status: OutboxStatus::Pending,
+                    pending_delta: delta,
+                    in_flight: None,
+                    attempts: 0,
+                    next_attempt_at: now,
+                    last_error: None,
+                    updated_at: now,
+                    created_at: now,
+                },
+                // If there is an entry, add the delta and (re)schedule it right away
+                Some(mut _entry) => {
+                    _entry.status = OutboxStatus::Pending;
+                    _entry.pending_delta += delta;
+                    _entry.attempts = 0;
+                    _entry.next_attempt_at = now;
+                    _entry.updated_at = now;
+                    _entry
+                }
+            };
+            outbox.insert(event_identifier.to_string(), entry);
+        });
+    }
+
+    // Method to deliver the pending entries that are due
+    pub async fn drain() {
+        // The guard releases the lock at the end of the method, also when a callback traps
+        let _guard = match LockGuard::acquire(&IS_DRAINING) {
+            None => return,
+            Some(_guard) => _guard,
+        };
+
+        let now = time();
+        let due_entries: Vec<OutboxEntry> = OUTBOX.with(|outbox| {
+            outbox
+                .borrow()
+                .iter()
+                .map(|(_, entry)| entry)
+                .filter(|entry| entry.status == OutboxStatus::Pending)
+                .filter(|entry| entry.next_attempt_at <= now)
+                .take(MAX_ENTRIES_PER_DRAIN)
+                .collect()
+        });
+
+        for entry in due_entries {
+            if let Some(delta) = Self::take_delta(entry.event_identifier) {
+                let result = Store::send_attendee_count_delta(delta.clone()).await;
+                Self::complete(delta, result);
+            }
+        }
+
+        Self::drain_directory_registrations().await;
+    }
+
+    // Method to queue the registration of an attendee principal in the directory of the parent
+    pub fn enqueue_directory_registration(principal: Principal) {
+        DIRECTORY_QUEUE.with(|queue| queue.borrow_mut().insert(principal.to_string(), time()));
+    }
+
+    // Method to queue the registration of all the attendees of this canister, returns the number of queued principals
+    pub fn enqueue_all_directory_registrations() -> usize {
+        let principals: Vec<Principal> = ENTRIES.with(|entries| {
+            entries
+                .borrow()
+                .iter()
+                .map(|(_, attendee)| attendee.principal)
+                .collect()
+        });
+
+        principals
+            .iter()
+            .for_each(|principal| Self::enqueue_directory_registration(*principal));
+        principals.len()
+    }
+
+    // Method to register the queued principals in a single call, failed registrations are retried on the next drain
+    async fn drain_directory_registrations() {
+        let principals: Vec<Principal> = DIRECTORY_QUEUE.with(|queue| {
+            queue
+                .borrow()
+                .iter()
+                .take(MAX_REGISTRATIONS_PER_DRAIN)
+                .filter_map(|(principal, _)| Principal::from_text(principal).ok())
+                .collect()
+        });
+
+        if principals.is_empty() {
+            return;
+        }
+
+        if Store::register_attendee_canisters(principals.clone())
+            .await
+            .is_ok()
+        {
+            DIRECTORY_QUEUE.with(|queue| {
+                let mut queue = queue.borrow_mut();
+                principals.iter().for_each(|principal| {
+                    queue.remove(&principal.to_string());
+                });
+            });
+        }
+    }
+
+    // Method to get the delta that needs to be delivered for an event
+    // pending deltas are only moved into a new delivery when there is nothing in flight
+    fn take_delta(event_identifier: Principal) -> Option<AttendeeCountDelta> {
+        let key = event_identifier.to_string();
+        OUTBOX.with(|outbox| {
+            let mut outbox = outbox.borrow_mut();
+            let mut entry = outbox.get(&key)?;
+
+            if let Some(in_flight) = entry.in_flight.clone() {
+                return Some(in_flight);
+            }
+
+            // The deltas cancelled each other out, there is nothing to deliver
+            if entry.pending_delta == 0 {
+                outbox.remove(&key);
+                return None;
+            }
+
+            let delta = AttendeeCountDelta {
+                event_identifier,
+                child_identifier: id(),
+                sequence: Store::next_count_sequence(),
+                delta: entry.pending_delta,
+                created_at: time(),
+            };
+
+            entry.pending_delta = 0;
+            entry.in_flight = Some(delta.clone());
+            outbox.insert(key, entry);
+            Some(delta)
+        })
+    }
+
+    // Method to process the result of a delivery
+    fn complete(delivered: AttendeeCountDelta, result: Result<(), String>) {
+        let key = delivered.event_identifier.to_string();
+        OUTBOX.with(|outbox| {
+            let mut outbox = outbox.borrow_mut();
+            let mut entry = match outbox.get(&key) {
+                None => return,
+                Some(_entry) => _entry,
+            };
+
+            match result {
+                Ok(_) => {
+                    entry.in_flight = None;
+                    // Deltas that were queued while the call was in flight are picked up by the next drain
+                    if entry.pending_delta == 0 {
+                        outbox.remove(&key);
+                    } else {
+                        entry.attempts = 0;
+                        entry.last_error = None;
+                        entry.next_attempt_at = time();
+                        entry.updated_at = time();
+                        outbox.insert(key, entry);
+                    }
+                }
+                Err(err) => {
+                    entry.attempts += 1;
+                    entry.last_error = Some(err);
+                    entry.updated_at = time();
+
+                    if entry.attempts >= MAX_ATTEMPTS {
+                        entry.status = OutboxStatus::Failed;
+                    } else {
+                        entry.next_attempt_at = time() + Self::backoff(entry.attempts);
+                    }
+                    outbox.insert(key, entry);
+                }
+            }
+        });
+    }
+
+    // Exponential backoff based on the number of attempts
+    fn backoff(attempts: u32) -> u64 {
+        BASE_BACKOFF_NANOS
+            .saturating_mul(2_u64.saturating_pow(attempts.saturating_sub(1)))
+            .min(MAX_BACKOFF_NANOS)
+    }
+
+    // Method to get the outbox entries, optionally filtered on status
+    pub fn get_entries(status: Option<OutboxStatus>) -> Vec<OutboxEntry> {
+        OUTBOX.with(|outbox| {
+            outbox
+                .borrow()
+                .iter()
+                .map(|(_, entry)| entry)
+                .filter(|entry| match &status {
+                    None => true,
+                    Some(_status) => &entry.status == _status,
+                })
+                .collect()
+        })
+    }
+
+    // Method to check if there are undelivered deltas for an event
+    pub fn has_entry(event_identifier: &Principal) -> bool {
+        OUTBOX.with(|outbox| outbox.borrow().contains_key(&event_identifier.to_string()))
+    }
+
+    // Method to reschedule all the failed entries, returns the number of rescheduled entries
+    pub fn retry_failed() -> usize {
+        let failed = Self::get_entries(Some(OutboxStatus::Failed));
+        failed
+            .iter()
+            .for_each(|entry| Self::enqueue(entry.event_identifier, 0));
+        failed.len()
+    }
+}
@@ -0,0 +1,43 @@
+use candid::Principal;
+
+use shared::{
+    operator_helper::{has_operator_role, is_controller_or_sns_governance},
+    operator_model::{OperatorRole, OperatorRoles},
+};
+
+use crate::store::OPERATOR_ROLES;
+
+pub struct Roles;
+
+impl Roles {
+    pub fn get() -> OperatorRoles {
+        OPERATOR_ROLES.with(|roles| roles.borrow().get().clone())
+    }
+
+    // Method to replace the roles, an update that is older than the stored roles is ignored
+    // so a delayed propagation from the parent can't undo a newer change
+    pub fn set(roles: OperatorRoles) -> OperatorRoles {
+        OPERATOR_ROLES.with(|stored| {
+            let mut stored = stored.borrow_mut();
+            if roles.updated_at >= stored.get().updated_at {
+                let _ = stored.set(roles);
+            }
+            stored.get().clone()
+        })
+    }
+
+    pub fn has_role(role: OperatorRole, principal: &Principal) -> bool {
+        OPERATOR_ROLES.with(|roles| has_operator_role(roles.borrow().get(), role, principal))
+    }
+
+    // Method to check if the principal can manage the roles, the parent is a controller of this canister
+    pub fn can_manage(principal: &Principal) -> bool {
+        OPERATOR_ROLES.with(|roles| {
+            is_controller_or_sns_governance(
+                roles.borrow().get(),
+                principal,
+                ic_cdk::api::is_controller(principal),
+            )
+        })
+    }
+}
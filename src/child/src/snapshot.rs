@@ -0,0 +1,73 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use ic_cdk::api::time;
+
+// Time a cached snapshot can be used to read chunks from
+const SNAPSHOT_TTL_NANOS: u64 = 5 * 60 * 1_000_000_000;
+// Max number of serialized bytes that are kept in the cache, the oldest snapshots are dropped first
+const MAX_CACHED_BYTES: usize = 200 * 1024 * 1024;
+
+struct CachedSnapshot {
+    bytes: Vec<u8>,
+    created_at: u64,
+}
+
+thread_local! {
+    // Serialized chunk data keyed by snapshot id and request key
+    // only update calls can fill the cache, state changes made by query calls are discarded
+    static SNAPSHOTS: RefCell<HashMap<(u64, Vec<u8>), CachedSnapshot>> = RefCell::new(HashMap::new());
+}
+
+pub struct Snapshots;
+
+impl Snapshots {
+    // Method to get the serialized data of a snapshot that is not expired
+    pub fn get(snapshot_id: u64, key: &[u8]) -> Option<Vec<u8>> {
+        SNAPSHOTS.with(|snapshots| {
+            snapshots
+                .borrow()
+                .get(&(snapshot_id, key.to_vec()))
+                .filter(|_snapshot| !Self::_is_expired(_snapshot))
+                .map(|_snapshot| _snapshot.bytes.clone())
+        })
+    }
+
+    // Method to store the serialized data of a snapshot, returns false if the data doesnt fit in the cache
+    pub fn insert(snapshot_id: u64, key: Vec<u8>, bytes: Vec<u8>) -> bool {
+        if bytes.len() > MAX_CACHED_BYTES {
+            return false;
+        }
+
+        SNAPSHOTS.with(|snapshots| {
+            let mut snapshots = snapshots.borrow_mut();
+            snapshots.retain(|_, _snapshot| !Self::_is_expired(_snapshot));
+
+            // Drop the oldest snapshots until the new snapshot fits
+            let mut cached_bytes: usize = snapshots.values().map(|s| s.bytes.len()).sum();
+            while cached_bytes + bytes.len() > MAX_CACHED_BYTES {
+                let oldest = snapshots
+                    .iter()
+                    .min_by_key(|(_, _snapshot)| _snapshot.created_at)
+                    .map(|(_key, _)| _key.clone());
+
+                match oldest.and_then(|_key| snapshots.remove(&_key)) {
+                    Some(_removed) => cached_bytes -= _removed.bytes.len(),
+                    None => break,
+                }
+            }
+
+            snapshots.insert(
+                (snapshot_id, key),
+                CachedSnapshot {
+                    bytes,
+                    created_at: time(),
+                },
+            );
+            true
+        })
+    }
+
+    fn _is_expired(snapshot: &CachedSnapshot) -> bool {
+        time().saturating_sub(snapshot.created_at) > SNAPSHOT_TTL_NANOS
+    }
+}
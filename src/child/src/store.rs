@@ -1,4 +1,10 @@
-use std::{cell::RefCell, collections::HashMap, iter::FromIterator, vec};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    iter::FromIterator,
+    ops::Bound,
+    vec,
+};
 
 use candid::Principal;
 use ic_cdk::{
@@ -16,6 +22,7 @@ use ic_scalable_canister::ic_scalable_misc::{
         serialize_helper::serialize,
     },
     models::{
+        canister_models::ScalableCanisterDetails,
         identifier_model::Identifier,
         permissions_models::{PermissionActionType, PermissionType},
     },
@@ -27,16 +34,64 @@ use ic_stable_structures::{
     {DefaultMemoryImpl, StableBTreeMap, StableCell},
 };
 
-use shared::attendee_model::{
-    Attendee, Invite, InviteAttendeeResponse, InviteType, Join, JoinedAttendeeResponse,
+use shared::{
+    attendee_model::{
+        Attendee, AttendeeListRequest, AttendeeStatus, CursorPagedResponse, ForwardedMutation,
+        GroupAttendee, GroupEventAttendance, Invite, InviteAttendeeResponse,
+        InviteAttendeeResponseV2, InviteType, Join, JoinedAttendeeResponse,
+        JoinedAttendeeResponseV2, MAX_PAGE_LIMIT,
+    },
+    audit_model::{AuditAction, AuditEntry},
+    calendar_model::{CachedEvent, CalendarFeedToken},
+    chunk_helper::get_chunk,
+    chunk_model::ChunkedData,
+    cursor_helper::{decode_cursor, encode_cursor},
+    export_model::EventExportRow,
+    history_model::{AttendanceAction, AttendanceHistoryEntry},
+    icrc3_model::StoredBlock,
+    metrics_helper::record_call_failure,
+    operator_model::OperatorRoles,
+    outbox_model::{AttendeeCountDelta, AttendeeCountReconciliation, OutboxEntry},
+    subscription_model::Subscription,
 };
 
-use crate::IDENTIFIER_KIND;
+use crate::{
+    audit::Audit, calendar::Calendar, certification::Certification, history::History,
+    outbox::Outbox, snapshot::Snapshots, transaction_log::TransactionLog, IDENTIFIER_KIND,
+};
 
 type Memory = VirtualMemory<DefaultMemoryImpl>;
 
 pub static DATA_MEMORY_ID: MemoryId = MemoryId::new(0);
 pub static ENTRIES_MEMORY_ID: MemoryId = MemoryId::new(1);
+pub static OUTBOX_MEMORY_ID: MemoryId = MemoryId::new(2);
+pub static ATTENDEE_COUNTS_MEMORY_ID: MemoryId = MemoryId::new(3);
+pub static COUNT_SEQUENCE_MEMORY_ID: MemoryId = MemoryId::new(4);
+pub static DIRECTORY_QUEUE_MEMORY_ID: MemoryId = MemoryId::new(5);
+pub static JOIN_INDEX_MEMORY_ID: MemoryId = MemoryId::new(6);
+pub static INVITE_INDEX_MEMORY_ID: MemoryId = MemoryId::new(7);
+pub static DATA_VERSION_MEMORY_ID: MemoryId = MemoryId::new(8);
+pub static HISTORY_MEMORY_ID: MemoryId = MemoryId::new(9);
+pub static HISTORY_PRIVACY_MEMORY_ID: MemoryId = MemoryId::new(10);
+pub static AUDIT_LOG_MEMORY_ID: MemoryId = MemoryId::new(11);
+pub static AUDIT_SEQUENCE_MEMORY_ID: MemoryId = MemoryId::new(12);
+pub static BLOCKS_MEMORY_ID: MemoryId = MemoryId::new(13);
+pub static SUBSCRIPTIONS_MEMORY_ID: MemoryId = MemoryId::new(14);
+pub static CALENDAR_TOKENS_MEMORY_ID: MemoryId = MemoryId::new(15);
+pub static EVENT_CACHE_MEMORY_ID: MemoryId = MemoryId::new(16);
+pub static OPERATOR_ROLES_MEMORY_ID: MemoryId = MemoryId::new(17);
+pub static ZEROED_COUNTS_MEMORY_ID: MemoryId = MemoryId::new(18);
+pub static EVENT_DATA_VERSIONS_MEMORY_ID: MemoryId = MemoryId::new(19);
+pub static GROUP_INDEX_MEMORY_ID: MemoryId = MemoryId::new(20);
+pub static EVENT_INDEX_VERSION_MEMORY_ID: MemoryId = MemoryId::new(21);
+
+// Version of the event indexes (join, invite and group index), increase it when an index is added or its keys change
+// so the indexes are rebuilt once on the next upgrade
+pub const EVENT_INDEX_VERSION_CURRENT: u64 = 1;
+
+// Kinds of the entries in the group index
+const GROUP_INDEX_JOINED: &str = "joined";
+const GROUP_INDEX_INVITED: &str = "invited";
 
 thread_local! {
 
@@ -56,6 +111,156 @@ thread_local! {
             MEMORY_MANAGER.with(|m| m.borrow().get(ENTRIES_MEMORY_ID)),
         )
     );
+
+    // Pending attendee count notifications for the event canisters, keyed by event identifier
+    pub static OUTBOX: RefCell<StableBTreeMap<String, OutboxEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(OUTBOX_MEMORY_ID)),
+        )
+    );
+
+    // Number of joined attendees per event identifier, kept in sync on every join and leave
+    pub static ATTENDEE_COUNTS: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(ATTENDEE_COUNTS_MEMORY_ID)),
+        )
+    );
+
+    // Last sequence number used for the attendee count updates send to the event canisters
+    pub static COUNT_SEQUENCE: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(COUNT_SEQUENCE_MEMORY_ID)),
+            0,
+        ).expect("failed")
+    );
+
+    // Attendee principals that still need to be registered in the directory of the parent, with the time they were queued
+    pub static DIRECTORY_QUEUE: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(DIRECTORY_QUEUE_MEMORY_ID)),
+        )
+    );
+
+    // Attendees per event ordered by principal, keyed by "{event_identifier}:{principal}" with the attendee identifier as value
+    pub static JOIN_INDEX: RefCell<StableBTreeMap<String, String, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(JOIN_INDEX_MEMORY_ID)),
+        )
+    );
+
+    // Invites per event ordered by principal, keyed by "{event_identifier}:{principal}" with the attendee identifier as value
+    pub static INVITE_INDEX: RefCell<StableBTreeMap<String, String, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(INVITE_INDEX_MEMORY_ID)),
+        )
+    );
+
+    // Version of the attendee data, increased on every write
+    pub static DATA_VERSION: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(DATA_VERSION_MEMORY_ID)),
+            0,
+        ).expect("failed")
+    );
+
+    // Attendance history per principal, keyed by "{principal}:{created_at}:{event_identifier}"
+    pub static HISTORY: RefCell<StableBTreeMap<String, AttendanceHistoryEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(HISTORY_MEMORY_ID)),
+        )
+    );
+
+    // Principals that have hidden their attendance history, with the time it was hidden
+    pub static HISTORY_PRIVACY: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(HISTORY_PRIVACY_MEMORY_ID)),
+        )
+    );
+
+    // Append-only log of the attendee mutations, keyed by the inverted sequence so the newest entries come first
+    pub static AUDIT_LOG: RefCell<StableBTreeMap<u64, AuditEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(AUDIT_LOG_MEMORY_ID)),
+        )
+    );
+
+    // Last sequence number used for the audit log
+    pub static AUDIT_SEQUENCE: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(AUDIT_SEQUENCE_MEMORY_ID)),
+            0,
+        ).expect("failed")
+    );
+
+    // ICRC-3 block log of the attendance changes, keyed by block index
+    pub static BLOCKS: RefCell<StableBTreeMap<u64, StoredBlock, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(BLOCKS_MEMORY_ID)),
+        )
+    );
+
+    // Canisters that subscribed on the attendee changes, keyed by subscriber principal
+    pub static SUBSCRIPTIONS: RefCell<StableBTreeMap<String, Subscription, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(SUBSCRIPTIONS_MEMORY_ID)),
+        )
+    );
+
+    // Secret tokens that give access to the calendar feed of an attendee, keyed by token
+    pub static CALENDAR_TOKENS: RefCell<StableBTreeMap<String, CalendarFeedToken, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(CALENDAR_TOKENS_MEMORY_ID)),
+        )
+    );
+
+    // Event details fetched from the event canisters for the joined events, keyed by event identifier
+    // used for the calendar feeds and to check if an event ended
+    pub static EVENT_CACHE: RefCell<StableBTreeMap<String, CachedEvent, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(EVENT_CACHE_MEMORY_ID)),
+        )
+    );
+
+    // Owner and monitor principals, seeded at init and kept in sync by the parent
+    pub static OPERATOR_ROLES: RefCell<StableCell<OperatorRoles, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(OPERATOR_ROLES_MEMORY_ID)),
+            OperatorRoles::default(),
+        ).expect("failed")
+    );
+
+    // Events whose attendee count dropped to 0, the 0 is send once with the next reconciliation
+    pub static ZEROED_COUNTS: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(ZEROED_COUNTS_MEMORY_ID)),
+        )
+    );
+
+    // Data version per event, the data version at the last write to the joins or invites of the event
+    // used as the snapshot id of the chunked data, so a write to an other event doesnt expire the snapshot
+    pub static EVENT_DATA_VERSIONS: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(EVENT_DATA_VERSIONS_MEMORY_ID)),
+        )
+    );
+
+    // Joins and invites per group, keyed by "{group_identifier}:{joined|invited}:{event_identifier}:{principal}" with the attendee identifier as value
+    pub static GROUP_INDEX: RefCell<StableBTreeMap<String, String, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(GROUP_INDEX_MEMORY_ID)),
+        )
+    );
+
+    // Version of the event indexes that are stored, 0 for canisters created before the version was stored
+    pub static EVENT_INDEX_VERSION: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(EVENT_INDEX_VERSION_MEMORY_ID)),
+            0,
+        ).expect("failed")
+    );
+
+    // Cached child canisters of the same parent, refreshed when an unknown canister forwards a write
+    pub static SIBLINGS: RefCell<Vec<Principal>> = const { RefCell::new(vec![]) };
 }
 
 pub struct Store;
@@ -78,93 +283,132 @@ impl Store {
             Err(err) => Err(err),
             // if the call succeeds, continue
             Ok((_, _event_privacy)) => {
-                // get the attendee for the caller
-                match Self::_get_attendee_from_caller(caller) {
-                    // if the attendee is not found, do nothing
-                    None => {}
-                    // if the attendee is found, continue
-                    Some((_identifier, mut _exisiting_attendee)) => {
-                        // if the event identifier is already found in the joined array, throw an error
-                        if let Some(_) = _exisiting_attendee.joined.get(&event_identifier) {
-                            return Err(api_error(
-                                ApiErrorType::BadRequest,
-                                "ALREADY_JOINED",
-                                "You are already part of this event",
-                                STABLE_DATA
-                                    .with(|data| Data::get_name(data.borrow().get()))
-                                    .as_str(),
-                                "join_group",
-                                None,
-                            ));
-                        }
-                        // if the event identifier is already found in the invites array, throw an error
-                        if let Some(_) = _exisiting_attendee.invites.get(&event_identifier) {
-                            return Err(api_error(
-                                ApiErrorType::BadRequest,
-                                "PENDING_INVITE",
-                                "There is already a pending invite for this event",
-                                STABLE_DATA
-                                    .with(|data| Data::get_name(data.borrow().get()))
-                                    .as_str(),
-                                "join_event",
-                                None,
-                            ));
-                        }
+                // if the attendee record lives on a sibling canister, forward the join to that canister
+                if Self::_get_attendee_from_caller(caller).is_none() {
+                    if let Some(_canister) = Self::_claim_attendee_canister(caller).await? {
+                        return Self::_forward_mutation(
+                            _canister,
+                            ForwardedMutation::Join {
+                                principal: caller,
+                                event_identifier,
+                                group_identifier,
+                                event_privacy: _event_privacy,
+                            },
+                        )
+                        .await;
                     }
-                };
+                }
 
-                // add the event invite or join to the attendee
-                let updated_attendee = Self::add_invite_or_join_event_to_attendee(
+                Self::join_event_with_privacy(
                     caller,
-                    event_identifier.clone(),
+                    event_identifier,
                     group_identifier,
-                    Self::_get_attendee_from_caller(caller),
                     _event_privacy,
-                );
+                )
+            }
+        }
+    }
 
-                match updated_attendee {
-                    // If something went wrong, return the error
-                    Err(err) => Err(err),
-                    // If the attendee was updated or added, continue
-                    Ok(_updated_attendee) => match Self::_get_attendee_from_caller(caller) {
-                        None => {
-                            let result = STABLE_DATA.with(|data| {
-                                ENTRIES.with(|entries| {
-                                    Data::add_entry(
-                                        data,
-                                        entries,
-                                        _updated_attendee,
-                                        Some(IDENTIFIER_KIND.to_string()),
-                                    )
-                                })
-                            });
-                            ic_cdk::spawn(Self::update_attendee_count_on_event(event_identifier));
-                            result
-                        }
-                        Some((_identifier, _)) => {
-                            let result = STABLE_DATA.with(|data| {
-                                ENTRIES.with(|entries| {
-                                    Data::update_entry(
-                                        data,
-                                        entries,
-                                        _identifier,
-                                        _updated_attendee,
-                                    )
-                                })
-                            });
-                            ic_cdk::spawn(Self::update_attendee_count_on_event(event_identifier));
-                            result
-                        }
-                    },
+    // Method to join an event of which the privacy is already known
+    pub fn join_event_with_privacy(
+        caller: Principal,
+        event_identifier: Principal,
+        group_identifier: Principal,
+        event_privacy: Privacy,
+    ) -> Result<(Principal, Attendee), ApiError> {
+        // get the attendee for the caller
+        match Self::_get_attendee_from_caller(caller) {
+            // if the attendee is not found, do nothing
+            None => {}
+            // if the attendee is found, continue
+            Some((_identifier, mut _exisiting_attendee)) => {
+                // if the event identifier is already found in the joined array, throw an error
+                if let Some(_) = _exisiting_attendee.joined.get(&event_identifier) {
+                    return Err(api_error(
+                        ApiErrorType::BadRequest,
+                        "ALREADY_JOINED",
+                        "You are already part of this event",
+                        STABLE_DATA
+                            .with(|data| Data::get_name(data.borrow().get()))
+                            .as_str(),
+                        "join_group",
+                        None,
+                    ));
+                }
+                // if the event identifier is already found in the invites array, throw an error
+                if let Some(_) = _exisiting_attendee.invites.get(&event_identifier) {
+                    return Err(api_error(
+                        ApiErrorType::BadRequest,
+                        "PENDING_INVITE",
+                        "There is already a pending invite for this event",
+                        STABLE_DATA
+                            .with(|data| Data::get_name(data.borrow().get()))
+                            .as_str(),
+                        "join_event",
+                        None,
+                    ));
                 }
             }
-        }
+        };
+
+        // add the event invite or join to the attendee
+        let updated_attendee = Self::add_invite_or_join_event_to_attendee(
+            caller,
+            event_identifier.clone(),
+            group_identifier,
+            Self::_get_attendee_from_caller(caller),
+            event_privacy,
+        );
+
+        let result = match updated_attendee {
+            // If something went wrong, return the error
+            Err(err) => Err(err),
+            // If the attendee was updated or added, continue
+            Ok(_updated_attendee) => match Self::_get_attendee_from_caller(caller) {
+                None => Self::_add_attendee(_updated_attendee),
+                Some((_identifier, _)) => Self::_update_attendee(_identifier, _updated_attendee),
+            },
+        };
+
+        // Private events only add an invite, so the join is only recorded if the attendee joined
+        Self::_record_join(&result, event_identifier);
+        result
     }
 
     // Method to remove a event attendee entry from an attendee
-    pub fn remove_join_from_attendee(
+    // if the attendee record lives on a sibling canister, the write is forwarded to that canister
+    pub async fn remove_join_from_attendee(
+        caller: Principal,
+        attendee_principal: Principal,
+        event_identifier: Principal,
+    ) -> Result<(), ApiError> {
+        // The attendee left the event if the attendee is the caller, otherwise an admin removed the attendee
+        let action = match caller == attendee_principal {
+            true => AttendanceAction::Left,
+            false => AttendanceAction::Removed,
+        };
+
+        if let Some(_canister) = Self::_get_sibling_attendee_canister(attendee_principal).await? {
+            return Self::_forward_mutation(
+                _canister,
+                ForwardedMutation::RemoveJoin {
+                    principal: attendee_principal,
+                    event_identifier,
+                    action,
+                },
+            )
+            .await
+            .map(|_| ());
+        }
+
+        Self::_remove_join_from_attendee(attendee_principal, event_identifier, action)
+    }
+
+    // Method to remove a event attendee entry from the attendee record on this canister
+    fn _remove_join_from_attendee(
         attendee_principal: Principal,
         event_identifier: Principal,
+        action: AttendanceAction,
     ) -> Result<(), ApiError> {
         match Self::_get_attendee_from_caller(attendee_principal) {
             // if the attendee is not found, return an error
@@ -174,21 +418,43 @@ impl Store {
             )),
             // if the attendee is found, continue
             Some((_identifier, mut _attendee)) => {
-                _attendee.joined.remove(&event_identifier);
-                let _ = STABLE_DATA.with(|data| {
-                    ENTRIES
-                        .with(|entries| Data::update_entry(data, entries, _identifier, _attendee))
-                });
-
-                // update the attendee count on the event canister (fire-and-forget)
-                ic_cdk::spawn(Self::update_attendee_count_on_event(event_identifier));
+                if let Some(_join) = _attendee.joined.remove(&event_identifier) {
+                    History::record(
+                        attendee_principal,
+                        event_identifier,
+                        _join.group_identifier,
+                        action,
+                    );
+                }
+                let _ = Self::_update_attendee(_identifier, _attendee);
                 return Ok(());
             }
         }
     }
 
     // Method to remove an invite from an attendee
-    pub fn remove_invite_from_event(
+    // if the attendee record lives on a sibling canister, the write is forwarded to that canister
+    pub async fn remove_invite_from_event(
+        attendee_principal: Principal,
+        event_identifier: Principal,
+    ) -> Result<(), ApiError> {
+        if let Some(_canister) = Self::_get_sibling_attendee_canister(attendee_principal).await? {
+            return Self::_forward_mutation(
+                _canister,
+                ForwardedMutation::RemoveInvite {
+                    principal: attendee_principal,
+                    event_identifier,
+                },
+            )
+            .await
+            .map(|_| ());
+        }
+
+        Self::_remove_invite_from_event(attendee_principal, event_identifier)
+    }
+
+    // Method to remove an invite from the attendee record on this canister
+    fn _remove_invite_from_event(
         attendee_principal: Principal,
         event_identifier: Principal,
     ) -> Result<(), ApiError> {
@@ -201,10 +467,7 @@ impl Store {
             // if the attendee is found, continue
             Some((_identifier, mut _attendee)) => {
                 _attendee.invites.remove(&event_identifier);
-                let _ = STABLE_DATA.with(|data| {
-                    ENTRIES
-                        .with(|entries| Data::update_entry(data, entries, _identifier, _attendee))
-                });
+                let _ = Self::_update_attendee(_identifier, _attendee);
                 Ok(())
             }
         }
@@ -289,6 +552,21 @@ impl Store {
         }
     }
 
+    // Method to get an attendee entry by principal, used by the parent canister
+    pub fn get_attendee_by_principal(
+        principal: Principal,
+    ) -> Result<(Principal, Attendee), ApiError> {
+        match Self::_get_attendee_from_caller(principal) {
+            // if the attendee is not found, return an error
+            None => Err(Self::_attendee_not_found_error(
+                "get_attendee_by_principal",
+                None,
+            )),
+            // if the attendee is found, return the attendee
+            Some(_attendee) => Ok(_attendee),
+        }
+    }
+
     // Method to get the attending entries from a principal
     pub fn get_attending_from_principal(
         principal: Principal,
@@ -337,31 +615,17 @@ impl Store {
 
     // Method to get the event attendees count from multiple events
     pub fn get_event_attendees_count(event_identifiers: Vec<Principal>) -> Vec<(Principal, usize)> {
-        // Create the initial attendees count array
-        let mut attendees_count: Vec<(Principal, usize)> = vec![];
-
-        ENTRIES.with(|entries| {
-            // Get the attendees from the data canister
-            let attendees = Data::get_entries(entries);
-
-            // Loop through the event identifiers
-            for event_identifier in event_identifiers {
-                let count = attendees
-                    .iter()
-                    // Filter the attendees to only those that have joined the event
-                    .filter(|(_identifier, _attendee)| {
-                        _attendee
-                            .joined
-                            .iter()
-                            .any(|(_event_identifier, _)| _event_identifier == &event_identifier)
-                    })
-                    .count();
-                // Push the event identifier and the count to the attendees count array
-                attendees_count.push((event_identifier, count));
-            }
-        });
-
-        attendees_count
+        ATTENDEE_COUNTS.with(|counts| {
+            let counts = counts.borrow();
+            event_identifiers
+                .into_iter()
+                // Get the count from the attendee count index, events without attendees are not stored
+                .map(|event_identifier| {
+                    let count = counts.get(&event_identifier.to_string()).unwrap_or(0);
+                    (event_identifier, count as usize)
+                })
+                .collect()
+        })
     }
 
     // Method to get the group invites from a single group
@@ -419,39 +683,318 @@ impl Store {
         })
     }
 
-    // Method to invite
-    pub fn invite_to_event(
-        event_identifier: Principal,
-        attendee_principal: Principal,
+    // Method to get the attending entries from a principal with the join details
+    pub fn get_attending_from_principal_v2(
+        principal: Principal,
+    ) -> Result<Vec<JoinedAttendeeResponseV2>, ApiError> {
+        match Self::_get_attendee_from_caller(principal) {
+            // if the attendee is not found, return an error
+            None => Err(Self::_attendee_not_found_error(
+                "get_attending_from_principal_v2",
+                None,
+            )),
+            // if the attendee is found, return the joined events
+            Some((_identifier, _attendee)) => Ok(_attendee
+                .joined
+                .keys()
+                .filter_map(|_event_identifier| {
+                    Self::map_attendee_to_joined_attendee_response_v2(
+                        &_identifier,
+                        &_attendee,
+                        *_event_identifier,
+                    )
+                })
+                .collect()),
+        }
+    }
+
+    // Method to get the event attendees from a single event with the join details
+    pub fn get_event_attendees_v2(event_identifier: Principal) -> Vec<JoinedAttendeeResponseV2> {
+        JOIN_INDEX
+            .with(|index| Self::_get_event_index_attendees(&index.borrow(), &event_identifier))
+            .iter()
+            .filter_map(|(_identifier, _attendee)| {
+                Self::map_attendee_to_joined_attendee_response_v2(
+                    _identifier,
+                    _attendee,
+                    event_identifier,
+                )
+            })
+            .collect()
+    }
+
+    // Method to get the event invites from a single event with the invite details
+    pub fn get_event_invites_v2(event_identifier: Principal) -> Vec<InviteAttendeeResponseV2> {
+        INVITE_INDEX
+            .with(|index| Self::_get_event_index_attendees(&index.borrow(), &event_identifier))
+            .iter()
+            .filter_map(|(_identifier, _attendee)| {
+                Self::map_attendee_to_invite_attendee_response_v2(
+                    _identifier,
+                    _attendee,
+                    event_identifier,
+                )
+            })
+            .collect()
+    }
+
+    // Method to get the participation of the attendees in the events of a group, ordered by principal
+    pub fn get_group_attendees(
         group_identifier: Principal,
-    ) -> Result<(Principal, Attendee), ApiError> {
-        // Create the initial invite
-        let invite = Invite {
-            invite_type: InviteType::OwnerRequest,
-            group_identifier: group_identifier.clone(),
-            updated_at: time(),
-            created_at: time(),
-        };
+        upcoming_only: bool,
+    ) -> Vec<GroupAttendee> {
+        // Joined events and the number of invites per attendee identifier
+        let mut participation: HashMap<String, (Vec<Principal>, usize)> = HashMap::new();
+        for (kind, event_identifier, identifier) in
+            Self::_get_group_index_entries(group_identifier, upcoming_only)
+        {
+            let (joined, invite_count) = participation.entry(identifier).or_default();
+            match kind.as_str() {
+                GROUP_INDEX_JOINED => joined.push(event_identifier),
+                _ => *invite_count += 1,
+            }
+        }
 
-        match Self::_get_attendee_from_caller(attendee_principal) {
-            // If the attendee is not found, create a new attendee and add the invite to the invites array
-            None => {
-                let attendee = Attendee {
-                    principal: attendee_principal,
-                    joined: HashMap::new(),
-                    invites: HashMap::from_iter(vec![(event_identifier, invite)]),
-                };
-                // Add the attendee to the data canister
-                STABLE_DATA.with(|data| {
-                    ENTRIES.with(|entries| {
-                        Data::add_entry(data, entries, attendee, Some(IDENTIFIER_KIND.to_string()))
+        let mut group_attendees: Vec<GroupAttendee> = ENTRIES.with(|entries| {
+            let entries = entries.borrow();
+            participation
+                .into_iter()
+                .filter_map(|(identifier, (joined, invite_count))| {
+                    let attendee = entries.get(&identifier)?;
+                    let joined_at: Vec<u64> = joined
+                        .iter()
+                        .filter_map(|_event| attendee.joined.get(_event))
+                        .map(|_join| _join.created_at)
+                        .collect();
+
+                    Some(GroupAttendee {
+                        group_identifier,
+                        principal: attendee.principal,
+                        joined_count: joined_at.len(),
+                        invite_count,
+                        first_joined_at: joined_at.iter().min().copied(),
+                        last_joined_at: joined_at.iter().max().copied(),
                     })
                 })
-            }
-            // If the attendee is found
-            Some((_identifier, mut _attendee)) => {
-                if _attendee.joined.contains_key(&event_identifier) {
-                    return Err(api_error(
+                .collect()
+        });
+
+        group_attendees.sort_by_cached_key(|_attendee| _attendee.principal.to_string());
+        group_attendees
+    }
+
+    // Method to get the number of attendees and invites per event of a group, ordered by event
+    pub fn get_group_event_attendance(
+        group_identifier: Principal,
+        upcoming_only: bool,
+    ) -> Vec<GroupEventAttendance> {
+        let mut attendance: HashMap<Principal, GroupEventAttendance> = HashMap::new();
+
+        for (kind, event_identifier, _) in
+            Self::_get_group_index_entries(group_identifier, upcoming_only)
+        {
+            let _attendance =
+                Self::_group_event_attendance(&mut attendance, group_identifier, event_identifier);
+            match kind.as_str() {
+                GROUP_INDEX_JOINED => _attendance.attendee_count += 1,
+                _ => _attendance.invite_count += 1,
+            }
+        }
+
+        let mut attendance: Vec<GroupEventAttendance> = attendance.into_values().collect();
+        attendance.sort_by_cached_key(|_attendance| _attendance.event_identifier.to_string());
+        attendance
+    }
+
+    // Method to get the kind, event and attendee identifier of the joins and invites of a group from the group index
+    fn _get_group_index_entries(
+        group_identifier: Principal,
+        upcoming_only: bool,
+    ) -> Vec<(String, Principal, String)> {
+        let prefix = format!("{}:", group_identifier);
+        let now = time();
+
+        GROUP_INDEX.with(|index| {
+            index
+                .borrow()
+                .range(prefix.clone()..)
+                .take_while(|(key, _)| key.starts_with(&prefix))
+                .filter_map(|(key, identifier)| {
+                    let mut parts = key[prefix.len()..].split(':');
+                    let kind = parts.next()?.to_string();
+                    let event_identifier = Principal::from_text(parts.next()?).ok()?;
+                    Some((kind, event_identifier, identifier))
+                })
+                .filter(|(_, event_identifier, _)| {
+                    !upcoming_only || Self::is_upcoming_event(event_identifier, now)
+                })
+                .collect()
+        })
+    }
+
+    // Method to check if an event did not end yet, based on the cached event details
+    // returns true for events of which the details are not fetched yet, so they are not left out before they are fetched
+    pub fn is_upcoming_event(event_identifier: &Principal, now: u64) -> bool {
+        EVENT_CACHE.with(|cache| {
+            cache
+                .borrow()
+                .get(&event_identifier.to_string())
+                .map(|_cached| {
+                    _cached
+                        .details
+                        .date
+                        .end_date
+                        .max(_cached.details.date.start_date)
+                        >= now
+                })
+                .unwrap_or(true)
+        })
+    }
+
+    // Method to check if an event ended, based on the cached event details
+    // returns false for events of which the details are not fetched yet
+    pub fn has_event_ended(event_identifier: &Principal, now: u64) -> bool {
+        EVENT_CACHE.with(|cache| {
+            cache
+                .borrow()
+                .get(&event_identifier.to_string())
+                .map(|_cached| {
+                    _cached
+                        .details
+                        .date
+                        .end_date
+                        .max(_cached.details.date.start_date)
+                        < now
+                })
+                .unwrap_or(false)
+        })
+    }
+
+    // Method to get the group of an event to fetch its details, from the joins or invites on this canister, None if nobody joined or is invited to the event
+    pub fn get_cached_event_group(event_identifier: Principal) -> Option<Principal> {
+        let prefix = format!("{}:", event_identifier);
+        let first_identifier = |index: &StableBTreeMap<String, String, Memory>| {
+            index
+                .range(prefix.clone()..)
+                .next()
+                .filter(|(key, _)| key.starts_with(&prefix))
+                .map(|(_, identifier)| identifier)
+        };
+
+        if let Some(_identifier) = JOIN_INDEX.with(|index| first_identifier(&index.borrow())) {
+            let attendee = ENTRIES.with(|entries| entries.borrow().get(&_identifier))?;
+            return attendee
+                .joined
+                .get(&event_identifier)
+                .map(|_join| _join.group_identifier);
+        }
+
+        let identifier = INVITE_INDEX.with(|index| first_identifier(&index.borrow()))?;
+        let attendee = ENTRIES.with(|entries| entries.borrow().get(&identifier))?;
+        attendee
+            .invites
+            .get(&event_identifier)
+            .map(|_invite| _invite.group_identifier)
+    }
+
+    // Method to get the events that are joined by or invited to attendees on this canister
+    // the invite index is read one event at a time, so the invites themselves are not scanned
+    pub fn get_event_identifiers() -> Vec<Principal> {
+        let mut events: Vec<Principal> = ATTENDEE_COUNTS.with(|counts| {
+            counts
+                .borrow()
+                .iter()
+                .filter_map(|(key, _)| Principal::from_text(key).ok())
+                .collect()
+        });
+
+        INVITE_INDEX.with(|index| {
+            let index = index.borrow();
+            let mut start = String::new();
+            // The keys are "{event_identifier}:{principal}", ';' is the character after ':' so the range starts at the next event
+            while let Some((key, _)) = index.range(start.clone()..).next() {
+                let event = key.split(':').next().unwrap_or_default().to_string();
+                if let Ok(_event_identifier) = Principal::from_text(&event) {
+                    events.push(_event_identifier);
+                }
+                start = format!("{};", event);
+            }
+        });
+
+        events.sort();
+        events.dedup();
+        events
+    }
+
+    // Method to get the attendance of an event from the attendance map, creates an empty entry if there is none
+    fn _group_event_attendance(
+        attendance: &mut HashMap<Principal, GroupEventAttendance>,
+        group_identifier: Principal,
+        event_identifier: Principal,
+    ) -> &mut GroupEventAttendance {
+        attendance
+            .entry(event_identifier)
+            .or_insert(GroupEventAttendance {
+                group_identifier,
+                event_identifier,
+                attendee_count: 0,
+                invite_count: 0,
+            })
+    }
+
+    // Method to invite
+    pub async fn invite_to_event(
+        event_identifier: Principal,
+        attendee_principal: Principal,
+        group_identifier: Principal,
+    ) -> Result<(Principal, Attendee), ApiError> {
+        // if the attendee record lives on a sibling canister, forward the invite to that canister
+        if Self::_get_attendee_from_caller(attendee_principal).is_none() {
+            if let Some(_canister) = Self::_claim_attendee_canister(attendee_principal).await? {
+                return Self::_forward_mutation(
+                    _canister,
+                    ForwardedMutation::Invite {
+                        principal: attendee_principal,
+                        event_identifier,
+                        group_identifier,
+                    },
+                )
+                .await;
+            }
+        }
+
+        Self::_invite_to_event(event_identifier, attendee_principal, group_identifier)
+    }
+
+    // Method to add an invite to the attendee record on this canister
+    fn _invite_to_event(
+        event_identifier: Principal,
+        attendee_principal: Principal,
+        group_identifier: Principal,
+    ) -> Result<(Principal, Attendee), ApiError> {
+        // Create the initial invite
+        let invite = Invite {
+            invite_type: InviteType::OwnerRequest,
+            group_identifier: group_identifier.clone(),
+            updated_at: time(),
+            created_at: time(),
+        };
+
+        match Self::_get_attendee_from_caller(attendee_principal) {
+            // If the attendee is not found, create a new attendee and add the invite to the invites array
+            None => {
+                let attendee = Attendee {
+                    principal: attendee_principal,
+                    joined: HashMap::new(),
+                    invites: HashMap::from_iter(vec![(event_identifier, invite)]),
+                };
+                // Add the attendee to the data canister
+                Self::_add_attendee(attendee)
+            }
+            // If the attendee is found
+            Some((_identifier, mut _attendee)) => {
+                if _attendee.joined.contains_key(&event_identifier) {
+                    return Err(api_error(
                         ApiErrorType::BadRequest,
                         "ALREADY_JOINED",
                         "You already joined this event",
@@ -465,16 +1008,33 @@ impl Store {
                 // add the invite to the invites array
                 _attendee.invites.insert(event_identifier, invite);
                 // Update the attendee in the data canister
-                STABLE_DATA.with(|data| {
-                    ENTRIES
-                        .with(|entries| Data::update_entry(data, entries, _identifier, _attendee))
-                })
+                Self::_update_attendee(_identifier, _attendee)
             }
         }
     }
 
     // Method to accept an invite as a admin
-    pub fn accept_user_request_event_invite(
+    // if the attendee record lives on a sibling canister, the write is forwarded to that canister
+    pub async fn accept_user_request_event_invite(
+        attendee_principal: Principal,
+        event_identifier: Principal,
+    ) -> Result<(Principal, Attendee), ApiError> {
+        if let Some(_canister) = Self::_get_sibling_attendee_canister(attendee_principal).await? {
+            return Self::_forward_mutation(
+                _canister,
+                ForwardedMutation::AcceptUserRequest {
+                    principal: attendee_principal,
+                    event_identifier,
+                },
+            )
+            .await;
+        }
+
+        Self::_accept_user_request_event_invite(attendee_principal, event_identifier)
+    }
+
+    // Method to accept an invite on the attendee record on this canister as a admin
+    fn _accept_user_request_event_invite(
         attendee_principal: Principal,
         event_identifier: Principal,
     ) -> Result<(Principal, Attendee), ApiError> {
@@ -527,14 +1087,8 @@ impl Store {
                         );
 
                         // Update the attendee in the data canister
-                        let result = STABLE_DATA.with(|data| {
-                            ENTRIES.with(|entries| {
-                                Data::update_entry(data, entries, _identifier, _attendee)
-                            })
-                        });
-
-                        // Update the attendee count on the event canister (fire-and-forget)
-                        ic_cdk::spawn(Self::update_attendee_count_on_event(event_identifier));
+                        let result = Self::_update_attendee(_identifier, _attendee);
+                        Self::_record_join(&result, event_identifier);
                         result
                     }
                 }
@@ -543,7 +1097,27 @@ impl Store {
     }
 
     // Method to accept an invite as a user
-    pub fn accept_owner_request_event_invite(
+    // if the attendee record lives on a sibling canister, the write is forwarded to that canister
+    pub async fn accept_owner_request_event_invite(
+        caller: Principal,
+        event_identifier: Principal,
+    ) -> Result<(Principal, Attendee), ApiError> {
+        if let Some(_canister) = Self::_get_sibling_attendee_canister(caller).await? {
+            return Self::_forward_mutation(
+                _canister,
+                ForwardedMutation::AcceptOwnerRequest {
+                    principal: caller,
+                    event_identifier,
+                },
+            )
+            .await;
+        }
+
+        Self::_accept_owner_request_event_invite(caller, event_identifier)
+    }
+
+    // Method to accept an invite on the attendee record on this canister as a user
+    fn _accept_owner_request_event_invite(
         caller: Principal,
         event_identifier: Principal,
     ) -> Result<(Principal, Attendee), ApiError> {
@@ -593,12 +1167,9 @@ impl Store {
                             );
 
                             // Update the attendee in the data canister
-                            let response = ENTRIES.with(|entries| {
-                                Data::update_entry(data, entries, _identifier, _attendee)
-                            });
-                            // Update the attendee count on the event canister (fire-and-forget)
-                            ic_cdk::spawn(Self::update_attendee_count_on_event(event_identifier));
-                            response
+                            let result = Self::_update_attendee(_identifier, _attendee);
+                            Self::_record_join(&result, event_identifier);
+                            result
                         }
                     }
                 }
@@ -620,6 +1191,10 @@ impl Store {
             )
             .await;
 
+        if event_privacy_response.is_err() {
+            record_call_failure("get_event_privacy_and_owner");
+        }
+
         STABLE_DATA.with(|data| match event_privacy_response {
             // If the inter-canister call fails, return an error
             Err(err) => Err(api_error(
@@ -648,7 +1223,11 @@ impl Store {
             event_identifier,
             attendee_identifier: identifier.clone(),
             principal: attendee.principal,
-            group_identifier: event_identifier,
+            group_identifier: attendee
+                .joined
+                .get(&event_identifier)
+                .map(|_join| _join.group_identifier)
+                .unwrap_or(Principal::anonymous()),
         }
     }
 
@@ -667,7 +1246,10 @@ impl Store {
             event_identifier,
             attendee_identifier: identifier.clone(),
             principal: attendee.principal,
-            group_identifier: event_identifier,
+            group_identifier: match invite {
+                None => Principal::anonymous(),
+                Some((_, _invite)) => _invite.group_identifier,
+            },
             invite_type: match invite {
                 None => InviteType::None,
                 Some((_, _invite)) => _invite.invite_type.clone(),
@@ -675,6 +1257,152 @@ impl Store {
         }
     }
 
+    // Method used to map the attendee to a joined attendee response with the join details
+    fn map_attendee_to_joined_attendee_response_v2(
+        identifier: &Principal,
+        attendee: &Attendee,
+        event_identifier: Principal,
+    ) -> Option<JoinedAttendeeResponseV2> {
+        let join = attendee.joined.get(&event_identifier)?;
+        Some(JoinedAttendeeResponseV2::new(
+            join,
+            Self::map_attendee_to_joined_attendee_response(identifier, attendee, event_identifier),
+        ))
+    }
+
+    // Method used to map the attendee to an invite attendee response with the invite details
+    fn map_attendee_to_invite_attendee_response_v2(
+        identifier: &Principal,
+        attendee: &Attendee,
+        event_identifier: Principal,
+    ) -> Option<InviteAttendeeResponseV2> {
+        let invite = attendee.invites.get(&event_identifier)?;
+        Some(InviteAttendeeResponseV2::new(
+            invite,
+            Self::map_attendee_to_invite_attendee_response(identifier, attendee, event_identifier),
+        ))
+    }
+
+    // Method to add the join of a successful write to the attendance history
+    fn _record_join(result: &Result<(Principal, Attendee), ApiError>, event_identifier: Principal) {
+        if let Ok((_, _attendee)) = result {
+            if let Some(_join) = _attendee.joined.get(&event_identifier) {
+                History::record(
+                    _attendee.principal,
+                    event_identifier,
+                    _join.group_identifier,
+                    AttendanceAction::Joined,
+                );
+            }
+        }
+    }
+
+    // Method to get the group of the join or invite of a principal for an event
+    pub fn get_event_group(principal: Principal, event_identifier: Principal) -> Option<Principal> {
+        let (_, attendee) = Self::_get_attendee_from_caller(principal)?;
+        match attendee.joined.get(&event_identifier) {
+            Some(_join) => Some(_join.group_identifier),
+            None => attendee
+                .invites
+                .get(&event_identifier)
+                .map(|_invite| _invite.group_identifier),
+        }
+    }
+
+    // Method to check in an attendee at an event, the attendee needs to have joined the event
+    // if the attendee record lives on a sibling canister, the check in is forwarded to that canister
+    pub async fn check_in_attendee(
+        attendee_principal: Principal,
+        event_identifier: Principal,
+    ) -> Result<(), ApiError> {
+        if let Some(_canister) = Self::_get_sibling_attendee_canister(attendee_principal).await? {
+            return Self::_forward_mutation(
+                _canister,
+                ForwardedMutation::CheckIn {
+                    principal: attendee_principal,
+                    event_identifier,
+                },
+            )
+            .await
+            .map(|_| ());
+        }
+
+        Self::_check_in_attendee(attendee_principal, event_identifier)
+    }
+
+    // Method to check in an attendee with a record on this canister
+    fn _check_in_attendee(
+        attendee_principal: Principal,
+        event_identifier: Principal,
+    ) -> Result<(), ApiError> {
+        let attendee = match Self::_get_attendee_from_caller(attendee_principal) {
+            None => return Err(Self::_attendee_not_found_error("check_in_attendee", None)),
+            Some((_, _attendee)) => _attendee,
+        };
+
+        match attendee.joined.get(&event_identifier) {
+            None => Err(api_error(
+                ApiErrorType::BadRequest,
+                "NOT_JOINED",
+                "The attendee has not joined this event",
+                STABLE_DATA
+                    .with(|data| Data::get_name(data.borrow().get()))
+                    .as_str(),
+                "check_in_attendee",
+                None,
+            )),
+            Some(_join) => {
+                History::record(
+                    attendee_principal,
+                    event_identifier,
+                    _join.group_identifier,
+                    AttendanceAction::CheckedIn,
+                );
+                // A check in doesnt change the attendee record, so it is audited here
+                let result = Ok(());
+                Audit::record(
+                    AuditAction::CheckIn,
+                    attendee_principal,
+                    Some(event_identifier),
+                    Some(_join.group_identifier),
+                    &result,
+                );
+                result
+            }
+        }
+    }
+
+    // Method to store a new attendee and register the principal in the directory of the parent
+    fn _add_attendee(attendee: Attendee) -> Result<(Principal, Attendee), ApiError> {
+        let result = STABLE_DATA.with(|data| {
+            ENTRIES.with(|entries| {
+                Data::add_entry(data, entries, attendee, Some(IDENTIFIER_KIND.to_string()))
+            })
+        });
+
+        if let Ok((_identifier, _attendee)) = &result {
+            Self::sync_attendee_indexes(_identifier, None, Some(_attendee));
+            Outbox::enqueue_directory_registration(_attendee.principal);
+        }
+        result
+    }
+
+    // Method to store an updated attendee and keep the indexes in sync with the changes
+    fn _update_attendee(
+        identifier: Principal,
+        attendee: Attendee,
+    ) -> Result<(Principal, Attendee), ApiError> {
+        let previous = ENTRIES.with(|entries| entries.borrow().get(&identifier.to_string()));
+        let result = STABLE_DATA.with(|data| {
+            ENTRIES.with(|entries| Data::update_entry(data, entries, identifier, attendee))
+        });
+
+        if let Ok((_, _attendee)) = &result {
+            Self::sync_attendee_indexes(&identifier, previous.as_ref(), Some(_attendee));
+        }
+        result
+    }
+
     // Method to get the attendee from the caller principal
     fn _get_attendee_from_caller(caller: Principal) -> Option<(Principal, Attendee)> {
         let attendees = ENTRIES.with(|entries| Data::get_entries(entries));
@@ -720,13 +1448,11 @@ impl Store {
     }
 
     // Method to add the owner of an event as an attendee
-    pub fn add_owner_as_attendee(
+    pub async fn add_owner_as_attendee(
         user_principal: Principal,
         event_identifier: Principal,
         group_identifier: Principal,
     ) -> Result<(), bool> {
-        let attendee = Self::_get_attendee_from_caller(user_principal);
-
         // Decode the event and group identifiers and see if they are valid
         let (_, _event_canister, _event_kind) = Identifier::decode(&event_identifier);
         let (_, _, _group_kind) = Identifier::decode(&group_identifier);
@@ -746,6 +1472,43 @@ impl Store {
             return Err(false);
         }
 
+        // if the attendee record lives on a sibling canister, forward the join to that canister
+        if Self::_get_attendee_from_caller(user_principal).is_none() {
+            let claim = Self::_claim_attendee_canister(user_principal)
+                .await
+                .map_err(|_| false)?;
+            if let Some(_canister) = claim {
+                let result = Self::_forward_mutation(
+                    _canister,
+                    ForwardedMutation::AddOwner {
+                        principal: user_principal,
+                        event_identifier,
+                        group_identifier,
+                    },
+                )
+                .await;
+
+                return match result {
+                    Ok(_) => Ok(()),
+                    Err(ApiError::BadRequest(_error)) if _error.tag == "ALREADY_JOINED" => {
+                        Err(true)
+                    }
+                    Err(_) => Err(false),
+                };
+            }
+        }
+
+        Self::_add_owner_as_attendee(user_principal, event_identifier, group_identifier)
+    }
+
+    // Method to add the owner of an event as an attendee on this canister
+    fn _add_owner_as_attendee(
+        user_principal: Principal,
+        event_identifier: Principal,
+        group_identifier: Principal,
+    ) -> Result<(), bool> {
+        let attendee = Self::_get_attendee_from_caller(user_principal);
+
         // Create the intial join object
         let join = Join {
             created_at: time(),
@@ -762,12 +1525,8 @@ impl Store {
                     invites: HashMap::new(),
                 };
                 // Add the attendee to the attendees
-                let _ = STABLE_DATA.with(|data| {
-                    ENTRIES.with(|entries| {
-                        Data::add_entry(data, entries, attendee, Some(IDENTIFIER_KIND.to_string()))
-                    })
-                });
-                ic_cdk::spawn(Self::update_attendee_count_on_event(event_identifier));
+                let result = Self::_add_attendee(attendee);
+                Self::_record_join(&result, event_identifier);
                 Ok(())
             }
             // If the attendee exists, continue
@@ -783,73 +1542,982 @@ impl Store {
                 } else {
                     _attendee.joined.insert(event_identifier, join);
                     // Update the attendee
-                    let _ = STABLE_DATA.with(|data| {
-                        ENTRIES.with(|entries| {
-                            Data::update_entry(data, entries, _identifier, _attendee)
-                        })
-                    });
-                    ic_cdk::spawn(Self::update_attendee_count_on_event(event_identifier));
+                    let result = Self::_update_attendee(_identifier, _attendee);
+                    Self::_record_join(&result, event_identifier);
                     return Ok(());
                 }
             }
         }
     }
 
-    // Method to update the attendee count on the event
-    #[allow(unused_must_use)]
-    async fn update_attendee_count_on_event(event_identifier: Principal) -> () {
-        // Get the attendee count for the event
-        let event_attendees_count_array =
-            Self::get_event_attendees_count(vec![event_identifier.clone()]);
+    // Method to claim the attendee record of a principal on the parent before it is created (inter-canister call)
+    // the parent keeps the first canister that claims a principal, so a record is only created on one canister
+    // returns the sibling canister that holds the record, or None if the record is stored on this canister
+    async fn _claim_attendee_canister(principal: Principal) -> Result<Option<Principal>, ApiError> {
+        let parent = STABLE_DATA.with(|data| data.borrow().get().parent);
+        let result: Result<(Result<Principal, ApiError>,), _> =
+            call::call(parent, "claim_attendee_canister", (principal,)).await;
+
+        match result {
+            // the record is not created when the claim fails, to prevent a duplicate record on an other canister
+            Err(err) => {
+                record_call_failure("claim_attendee_canister");
+                Err(api_error(
+                    ApiErrorType::BadRequest,
+                    "INTER_CANISTER_CALL_FAILED",
+                    err.1.as_str(),
+                    STABLE_DATA
+                        .with(|data| Data::get_name(data.borrow().get()))
+                        .as_str(),
+                    "claim_attendee_canister",
+                    None,
+                ))
+            }
+            Ok((Err(err),)) => Err(err),
+            Ok((Ok(_canister),)) if _canister != id() => Ok(Some(_canister)),
+            Ok((Ok(_),)) => Ok(None),
+        }
+    }
 
-        // Set the initial count to 0
-        let mut count = 0;
+    // Method to get the sibling canister that holds the attendee record of a principal that has no record on this canister (inter-canister call)
+    // returns None if the record is on this canister or not known to the parent
+    async fn _get_sibling_attendee_canister(
+        principal: Principal,
+    ) -> Result<Option<Principal>, ApiError> {
+        if Self::_get_attendee_from_caller(principal).is_some() {
+            return Ok(None);
+        }
 
-        // If the attendee count array is not empty, set the count to the first element
-        if event_attendees_count_array.len() > 0 {
-            count = event_attendees_count_array[0].1;
-        };
+        let parent = STABLE_DATA.with(|data| data.borrow().get().parent);
+        let result: Result<(Result<Principal, ApiError>,), _> =
+            call::call(parent, "get_attendee_canister", (principal,)).await;
 
-        // Decode the event identifier and call the update attendee count method on the event
-        let (_, event_canister, _) = Identifier::decode(&event_identifier);
-        call::call::<(Principal, Principal, usize), ()>(
-            event_canister,
-            "update_attendee_count_on_event",
-            (event_identifier.clone(), id(), count),
-        )
-        .await;
+        match result {
+            Err(err) => {
+                record_call_failure("get_attendee_canister");
+                Err(api_error(
+                    ApiErrorType::BadRequest,
+                    "INTER_CANISTER_CALL_FAILED",
+                    err.1.as_str(),
+                    STABLE_DATA
+                        .with(|data| Data::get_name(data.borrow().get()))
+                        .as_str(),
+                    "get_sibling_attendee_canister",
+                    None,
+                ))
+            }
+            Ok((Ok(_canister),)) if _canister != id() => Ok(Some(_canister)),
+            Ok(_) => Ok(None),
+        }
     }
 
-    // This method is used for role / permission based access control
-    pub async fn can_write(
-        caller: Principal,
-        group_identifier: Principal,
-        member_identifier: Principal,
-    ) -> Result<Principal, ApiError> {
-        Self::check_permission(
-            caller,
-            group_identifier,
-            member_identifier,
-            PermissionActionType::Write,
-            PermissionType::Attendee(None),
+    // Method to forward a write to the canister that holds the attendee record (inter-canister call)
+    async fn _forward_mutation(
+        canister: Principal,
+        mutation: ForwardedMutation,
+    ) -> Result<(Principal, Attendee), ApiError> {
+        // The caller is passed along so the sibling canister audits the write with the principal that made it
+        let result: Result<(Result<(Principal, Attendee), ApiError>,), _> = call::call(
+            canister,
+            "apply_forwarded_mutation",
+            (mutation, Some(caller())),
         )
-        .await
-    }
+        .await;
 
-    // This method is used for role / permission based access control
-    pub async fn can_read(
-        caller: Principal,
-        group_identifier: Principal,
-        member_identifier: Principal,
-    ) -> Result<Principal, ApiError> {
-        Self::check_permission(
-            caller,
-            group_identifier,
-            member_identifier,
-            PermissionActionType::Read,
-            PermissionType::Attendee(None),
-        )
-        .await
+        match result {
+            Err(err) => {
+                record_call_failure("apply_forwarded_mutation");
+                Err(api_error(
+                    ApiErrorType::BadRequest,
+                    "INTER_CANISTER_CALL_FAILED",
+                    err.1.as_str(),
+                    STABLE_DATA
+                        .with(|data| Data::get_name(data.borrow().get()))
+                        .as_str(),
+                    "forward_mutation",
+                    None,
+                ))
+            }
+            Ok((_result,)) => _result,
+        }
+    }
+
+    // Method to apply a write that is forwarded by a sibling canister
+    pub async fn apply_forwarded_mutation(
+        caller: Principal,
+        actor: Option<Principal>,
+        mutation: ForwardedMutation,
+    ) -> Result<(Principal, Attendee), ApiError> {
+        if !Self::_is_sibling(caller).await {
+            return Err(api_error(
+                ApiErrorType::Unauthorized,
+                "UNKNOWN_CANISTER",
+                "The caller principal isnt a sibling of this canister",
+                STABLE_DATA
+                    .with(|data| Data::get_name(data.borrow().get()))
+                    .as_str(),
+                "apply_forwarded_mutation",
+                None,
+            ));
+        }
+
+        // The writes are audited with the principal that called the sibling canister
+        Audit::with_actor(actor.unwrap_or(caller), || {
+            use ForwardedMutation::*;
+            match mutation {
+                Join {
+                    principal,
+                    event_identifier,
+                    group_identifier,
+                    event_privacy,
+                } => Self::join_event_with_privacy(
+                    principal,
+                    event_identifier,
+                    group_identifier,
+                    event_privacy,
+                ),
+                Invite {
+                    principal,
+                    event_identifier,
+                    group_identifier,
+                } => Self::_invite_to_event(event_identifier, principal, group_identifier),
+                AddOwner {
+                    principal,
+                    event_identifier,
+                    group_identifier,
+                } => {
+                    match Self::_add_owner_as_attendee(
+                        principal,
+                        event_identifier,
+                        group_identifier,
+                    ) {
+                        Ok(_) => Self::get_attendee_by_principal(principal),
+                        Err(_already_joined) => Err(api_error(
+                            ApiErrorType::BadRequest,
+                            match _already_joined {
+                                true => "ALREADY_JOINED",
+                                false => "UNSUPPORTED",
+                            },
+                            "The owner could not be added as attendee",
+                            STABLE_DATA
+                                .with(|data| Data::get_name(data.borrow().get()))
+                                .as_str(),
+                            "apply_forwarded_mutation",
+                            None,
+                        )),
+                    }
+                }
+                RemoveJoin {
+                    principal,
+                    event_identifier,
+                    action,
+                } => Self::_remove_join_from_attendee(principal, event_identifier, action)
+                    .and_then(|_| Self::get_attendee_by_principal(principal)),
+                RemoveInvite {
+                    principal,
+                    event_identifier,
+                } => Self::_remove_invite_from_event(principal, event_identifier)
+                    .and_then(|_| Self::get_attendee_by_principal(principal)),
+                AcceptUserRequest {
+                    principal,
+                    event_identifier,
+                } => Self::_accept_user_request_event_invite(principal, event_identifier),
+                AcceptOwnerRequest {
+                    principal,
+                    event_identifier,
+                } => Self::_accept_owner_request_event_invite(principal, event_identifier),
+                CheckIn {
+                    principal,
+                    event_identifier,
+                } => Self::_check_in_attendee(principal, event_identifier)
+                    .and_then(|_| Self::get_attendee_by_principal(principal)),
+            }
+        })
+    }
+
+    // Method to check if a principal is a child canister of the same parent (inter-canister call on a cache miss)
+    async fn _is_sibling(principal: Principal) -> bool {
+        if SIBLINGS.with(|siblings| siblings.borrow().contains(&principal)) {
+            return true;
+        }
+
+        let parent = STABLE_DATA.with(|data| data.borrow().get().parent);
+        let result: Result<(Vec<ScalableCanisterDetails>,), _> =
+            call::call(parent, "get_canisters", ()).await;
+
+        match result {
+            Err(_) => {
+                record_call_failure("get_canisters");
+                false
+            }
+            Ok((_canisters,)) => {
+                let siblings: Vec<Principal> = _canisters
+                    .into_iter()
+                    .map(|canister| canister.principal)
+                    .filter(|canister| canister != &id())
+                    .collect();
+                let is_sibling = siblings.contains(&principal);
+                SIBLINGS.with(|s| *s.borrow_mut() = siblings);
+                is_sibling
+            }
+        }
+    }
+
+    // Method to merge an attendee record from a sibling canister into the record on this canister
+    // joins take precedence over invites for the same event
+    pub fn merge_attendee(attendee: Attendee) -> Result<(Principal, Attendee), ApiError> {
+        let (_identifier, mut _attendee) = match Self::_get_attendee_from_caller(attendee.principal)
+        {
+            // If there is no record on this canister, the record is stored as is
+            None => return Self::_add_attendee(attendee),
+            Some(_existing) => _existing,
+        };
+
+        for (event_identifier, join) in attendee.joined {
+            if !_attendee.joined.contains_key(&event_identifier) {
+                _attendee.invites.remove(&event_identifier);
+                _attendee.joined.insert(event_identifier, join);
+            }
+        }
+
+        for (event_identifier, invite) in attendee.invites {
+            if !_attendee.joined.contains_key(&event_identifier) {
+                _attendee.invites.entry(event_identifier).or_insert(invite);
+            }
+        }
+
+        Self::_update_attendee(_identifier, _attendee)
+    }
+
+    // Method to remove the attendee record of a principal from this canister
+    pub fn remove_attendee(principal: Principal) -> Result<(), ApiError> {
+        match Self::_get_attendee_from_caller(principal) {
+            None => Err(Self::_attendee_not_found_error("remove_attendee", None)),
+            Some((_identifier, _attendee)) => {
+                ENTRIES.with(|entries| Data::remove_entry(entries, &_identifier));
+                Self::sync_attendee_indexes(&_identifier, Some(&_attendee), None);
+                Ok(())
+            }
+        }
+    }
+
+    // Method to get the principals of all the attendees on this canister
+    pub fn get_attendee_principals() -> Vec<Principal> {
+        ENTRIES.with(|entries| {
+            entries
+                .borrow()
+                .iter()
+                .map(|(_, attendee)| attendee.principal)
+                .collect()
+        })
+    }
+
+    // Method to apply the changes between the previous and the current attendee record to the indexes
+    // all writes to the attendees go through here so the indexes and counts stay in sync with the records
+    pub fn sync_attendee_indexes(
+        identifier: &Principal,
+        previous: Option<&Attendee>,
+        current: Option<&Attendee>,
+    ) {
+        let empty = Attendee::default();
+        let _previous = previous.unwrap_or(&empty);
+        let _current = current.unwrap_or(&empty);
+        let principal = match current.or(previous) {
+            None => return,
+            Some(_attendee) => _attendee.principal,
+        };
+
+        Self::_increase_event_data_versions(_previous, _current);
+        TransactionLog::record_changes(principal, _previous, _current);
+        Audit::record_changes(principal, _previous, _current);
+
+        JOIN_INDEX.with(|index| {
+            let mut index = index.borrow_mut();
+            // Joins that are added to the attendee
+            for event_identifier in _current.joined.keys() {
+                if !_previous.joined.contains_key(event_identifier) {
+                    index.insert(
+                        Self::_event_index_key(event_identifier, &principal),
+                        identifier.to_string(),
+                    );
+                    Self::update_attendee_count(*event_identifier, 1);
+                }
+            }
+            // Joins that are removed from the attendee
+            for event_identifier in _previous.joined.keys() {
+                if !_current.joined.contains_key(event_identifier) {
+                    index.remove(&Self::_event_index_key(event_identifier, &principal));
+                    Self::update_attendee_count(*event_identifier, -1);
+                }
+            }
+        });
+
+        INVITE_INDEX.with(|index| {
+            let mut index = index.borrow_mut();
+            // Invites that are added to the attendee
+            for event_identifier in _current.invites.keys() {
+                if !_previous.invites.contains_key(event_identifier) {
+                    index.insert(
+                        Self::_event_index_key(event_identifier, &principal),
+                        identifier.to_string(),
+                    );
+                }
+            }
+            // Invites that are removed from the attendee
+            for event_identifier in _previous.invites.keys() {
+                if !_current.invites.contains_key(event_identifier) {
+                    index.remove(&Self::_event_index_key(event_identifier, &principal));
+                }
+            }
+        });
+
+        GROUP_INDEX.with(|index| {
+            let mut index = index.borrow_mut();
+            let previous_keys = Self::_group_index_keys(_previous, &principal);
+            let current_keys = Self::_group_index_keys(_current, &principal);
+            // Joins and invites that are removed from the attendee or moved to an other group
+            for key in previous_keys.difference(&current_keys) {
+                index.remove(key);
+            }
+            // Joins and invites that are added to the attendee
+            for key in current_keys.difference(&previous_keys) {
+                index.insert(key.clone(), identifier.to_string());
+            }
+        });
+
+        Certification::record_changes(principal, _previous, _current);
+
+        // Fetch the details of new events right away, so the group queries and history stats have them
+        let has_new_events =
+            _current
+                .joined
+                .keys()
+                .chain(_current.invites.keys())
+                .any(|event_identifier| {
+                    EVENT_CACHE
+                        .with(|cache| !cache.borrow().contains_key(&event_identifier.to_string()))
+                });
+        if has_new_events {
+            Calendar::schedule_refresh();
+        }
+    }
+
+    // Keys of the group index for the joins and invites of an attendee
+    fn _group_index_keys(attendee: &Attendee, principal: &Principal) -> HashSet<String> {
+        let joins = attendee.joined.iter().map(|(_event, _join)| {
+            format!(
+                "{}:{}:{}:{}",
+                _join.group_identifier, GROUP_INDEX_JOINED, _event, principal
+            )
+        });
+        let invites = attendee.invites.iter().map(|(_event, _invite)| {
+            format!(
+                "{}:{}:{}:{}",
+                _invite.group_identifier, GROUP_INDEX_INVITED, _event, principal
+            )
+        });
+        joins.chain(invites).collect()
+    }
+
+    // Method to add an attendee that is stored without going through the store (ex; moved by the parent) to the indexes
+    pub fn index_attendee(principal: Principal) {
+        if let Some((_identifier, _attendee)) = Self::_get_attendee_from_caller(principal) {
+            Self::sync_attendee_indexes(&_identifier, None, Some(&_attendee));
+        }
+    }
+
+    fn _increase_data_version() -> u64 {
+        DATA_VERSION.with(|version| {
+            let mut version = version.borrow_mut();
+            let next = version.get() + 1;
+            let _ = version.set(next);
+            next
+        })
+    }
+
+    // Method to get the version of the joins and invites of an event
+    pub fn get_event_data_version(event_identifier: &Principal) -> u64 {
+        EVENT_DATA_VERSIONS.with(|versions| {
+            versions
+                .borrow()
+                .get(&event_identifier.to_string())
+                .unwrap_or_default()
+        })
+    }
+
+    // Method to increase the data version and set it as the version of the events of which the join or invite changed
+    fn _increase_event_data_versions(previous: &Attendee, current: &Attendee) {
+        let version = Self::_increase_data_version();
+
+        let changed_joins = previous
+            .joined
+            .keys()
+            .chain(current.joined.keys())
+            .filter(|_event| previous.joined.get(_event) != current.joined.get(_event));
+        let changed_invites = previous
+            .invites
+            .keys()
+            .chain(current.invites.keys())
+            .filter(|_event| previous.invites.get(_event) != current.invites.get(_event));
+
+        EVENT_DATA_VERSIONS.with(|versions| {
+            let mut versions = versions.borrow_mut();
+            changed_joins.chain(changed_invites).for_each(|_event| {
+                versions.insert(_event.to_string(), version);
+            });
+        });
+    }
+
+    // Key of the event indexes, the principal is part of the key so the entries per event are ordered by principal
+    fn _event_index_key(event_identifier: &Principal, principal: &Principal) -> String {
+        format!("{}:{}", event_identifier, principal)
+    }
+
+    // Method to rebuild the join and invite indexes from the stored attendees
+    // the version of every event is increased, so the snapshots of the old indexes expire
+    pub fn rebuild_event_indexes() {
+        let version = Self::_increase_data_version();
+
+        JOIN_INDEX.with(|n| {
+            n.replace(StableBTreeMap::new(
+                MEMORY_MANAGER.with(|m| m.borrow().get(JOIN_INDEX_MEMORY_ID)),
+            ))
+        });
+        INVITE_INDEX.with(|n| {
+            n.replace(StableBTreeMap::new(
+                MEMORY_MANAGER.with(|m| m.borrow().get(INVITE_INDEX_MEMORY_ID)),
+            ))
+        });
+        GROUP_INDEX.with(|n| {
+            n.replace(StableBTreeMap::new(
+                MEMORY_MANAGER.with(|m| m.borrow().get(GROUP_INDEX_MEMORY_ID)),
+            ))
+        });
+
+        ENTRIES.with(|entries| {
+            entries.borrow().iter().for_each(|(identifier, attendee)| {
+                attendee.joined.keys().for_each(|event_identifier| {
+                    JOIN_INDEX.with(|index| {
+                        index.borrow_mut().insert(
+                            Self::_event_index_key(event_identifier, &attendee.principal),
+                            identifier.clone(),
+                        )
+                    });
+                });
+                attendee.invites.keys().for_each(|event_identifier| {
+                    INVITE_INDEX.with(|index| {
+                        index.borrow_mut().insert(
+                            Self::_event_index_key(event_identifier, &attendee.principal),
+                            identifier.clone(),
+                        )
+                    });
+                });
+                GROUP_INDEX.with(|index| {
+                    let mut index = index.borrow_mut();
+                    Self::_group_index_keys(&attendee, &attendee.principal)
+                        .into_iter()
+                        .for_each(|key| {
+                            index.insert(key, identifier.clone());
+                        });
+                });
+                EVENT_DATA_VERSIONS.with(|versions| {
+                    let mut versions = versions.borrow_mut();
+                    attendee
+                        .joined
+                        .keys()
+                        .chain(attendee.invites.keys())
+                        .for_each(|event_identifier| {
+                            versions.insert(event_identifier.to_string(), version);
+                        });
+                });
+            })
+        });
+
+        Self::set_event_index_version();
+        Certification::rebuild();
+    }
+
+    // Method to rebuild the event indexes once when they are stored by an older version
+    pub fn migrate_event_indexes() {
+        let version = EVENT_INDEX_VERSION.with(|version| *version.borrow().get());
+        if version >= EVENT_INDEX_VERSION_CURRENT {
+            return;
+        }
+
+        match ENTRIES.with(|entries| entries.borrow().is_empty()) {
+            true => Self::set_event_index_version(),
+            false => Self::rebuild_event_indexes(),
+        }
+    }
+
+    // Method to mark the event indexes as up to date, used after a rebuild and on the install of a new canister
+    pub fn set_event_index_version() {
+        EVENT_INDEX_VERSION.with(|version| {
+            let _ = version.borrow_mut().set(EVENT_INDEX_VERSION_CURRENT);
+        });
+    }
+
+    // Method to get a page of the attendees of an event ordered by principal
+    // the cursor is the principal of the last attendee of the previous page
+    pub fn get_event_attendees_page(
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> CursorPagedResponse<JoinedAttendeeResponse> {
+        JOIN_INDEX.with(|index| {
+            Self::_get_event_page(
+                &index.borrow(),
+                event_identifier,
+                limit,
+                cursor,
+                |_identifier, _attendee| {
+                    Some(Self::map_attendee_to_joined_attendee_response(
+                        _identifier,
+                        _attendee,
+                        event_identifier,
+                    ))
+                },
+            )
+        })
+    }
+
+    // Method to get a page of the attendees of an event with the join details ordered by principal
+    pub fn get_event_attendees_page_v2(
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> CursorPagedResponse<JoinedAttendeeResponseV2> {
+        JOIN_INDEX.with(|index| {
+            Self::_get_event_page(
+                &index.borrow(),
+                event_identifier,
+                limit,
+                cursor,
+                |_identifier, _attendee| {
+                    Self::map_attendee_to_joined_attendee_response_v2(
+                        _identifier,
+                        _attendee,
+                        event_identifier,
+                    )
+                },
+            )
+        })
+    }
+
+    // Method to get a page of the invites of an event ordered by principal
+    // the cursor is the principal of the last invite of the previous page
+    pub fn get_event_invites_page(
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> CursorPagedResponse<InviteAttendeeResponse> {
+        INVITE_INDEX.with(|index| {
+            Self::_get_event_page(
+                &index.borrow(),
+                event_identifier,
+                limit,
+                cursor,
+                |_identifier, _attendee| {
+                    Some(Self::map_attendee_to_invite_attendee_response(
+                        _identifier,
+                        _attendee,
+                        event_identifier,
+                    ))
+                },
+            )
+        })
+    }
+
+    // Method to get a page of the invites of an event with the invite details ordered by principal
+    pub fn get_event_invites_page_v2(
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> CursorPagedResponse<InviteAttendeeResponseV2> {
+        INVITE_INDEX.with(|index| {
+            Self::_get_event_page(
+                &index.borrow(),
+                event_identifier,
+                limit,
+                cursor,
+                |_identifier, _attendee| {
+                    Self::map_attendee_to_invite_attendee_response_v2(
+                        _identifier,
+                        _attendee,
+                        event_identifier,
+                    )
+                },
+            )
+        })
+    }
+
+    // Method to get a page of the joins and invites of an event with the check-ins, used for the exports of the parent
+    // the joins are returned first and the invites after that, both ordered by principal
+    // the cursor is prefixed with the index it belongs to (ex; `joined:{principal}`)
+    pub fn get_event_export_page(
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> CursorPagedResponse<EventExportRow> {
+        let (is_invites, cursor) = match cursor.as_deref().and_then(|c| c.split_once(':')) {
+            Some(("invited", _cursor)) => (true, Some(_cursor.to_string())),
+            Some((_, _cursor)) => (false, Some(_cursor.to_string())),
+            None => (false, None),
+        };
+        // An empty cursor starts at the beginning of the index
+        let cursor = cursor.filter(|_cursor| !_cursor.is_empty());
+
+        let page = match is_invites {
+            false => JOIN_INDEX.with(|index| {
+                Self::_get_event_page(
+                    &index.borrow(),
+                    event_identifier,
+                    limit,
+                    cursor,
+                    |_identifier, _attendee| {
+                        let join = _attendee.joined.get(&event_identifier)?;
+                        Some(EventExportRow {
+                            principal: _attendee.principal,
+                            attendee_identifier: *_identifier,
+                            group_identifier: join.group_identifier,
+                            status: AttendeeStatus::Joined,
+                            invite_type: None,
+                            created_at: join.created_at,
+                            updated_at: join.updated_at,
+                            checked_in_at: History::get_last_check_in(
+                                _attendee.principal,
+                                event_identifier,
+                            ),
+                        })
+                    },
+                )
+            }),
+            true => INVITE_INDEX.with(|index| {
+                Self::_get_event_page(
+                    &index.borrow(),
+                    event_identifier,
+                    limit,
+                    cursor,
+                    |_identifier, _attendee| {
+                        let invite = _attendee.invites.get(&event_identifier)?;
+                        Some(EventExportRow {
+                            principal: _attendee.principal,
+                            attendee_identifier: *_identifier,
+                            group_identifier: invite.group_identifier,
+                            status: AttendeeStatus::from(&invite.invite_type),
+                            invite_type: Some(invite.invite_type.clone()),
+                            created_at: invite.created_at,
+                            updated_at: invite.updated_at,
+                            checked_in_at: None,
+                        })
+                    },
+                )
+            }),
+        };
+
+        let next_cursor = match (is_invites, page.next_cursor) {
+            (false, Some(_cursor)) => Some(format!("joined:{}", _cursor)),
+            // After the last page of the joins the invites are returned
+            (false, None) => Some("invited:".to_string()),
+            (true, Some(_cursor)) => Some(format!("invited:{}", _cursor)),
+            (true, None) => None,
+        };
+
+        CursorPagedResponse {
+            data: page.data,
+            next_cursor,
+        }
+    }
+
+    // Method to get a page from an event index and map the attendees of the page to a response
+    fn _get_event_page<T>(
+        index: &StableBTreeMap<String, String, Memory>,
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+        map: impl Fn(&Principal, &Attendee) -> Option<T>,
+    ) -> CursorPagedResponse<T> {
+        let (identifiers, next_cursor) =
+            Self::_get_index_page(index, &event_identifier, limit, cursor);
+
+        let data = ENTRIES.with(|entries| {
+            let entries = entries.borrow();
+            identifiers
+                .iter()
+                .filter_map(|identifier| {
+                    let attendee = entries.get(identifier)?;
+                    let identifier = Principal::from_text(identifier).ok()?;
+                    map(&identifier, &attendee)
+                })
+                .collect()
+        });
+
+        CursorPagedResponse { data, next_cursor }
+    }
+
+    // Method to get all the attendees of an event from an event index
+    fn _get_event_index_attendees(
+        index: &StableBTreeMap<String, String, Memory>,
+        event_identifier: &Principal,
+    ) -> Vec<(Principal, Attendee)> {
+        let prefix = format!("{}:", event_identifier);
+        let identifiers: Vec<String> = index
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .map(|(_, identifier)| identifier)
+            .collect();
+
+        ENTRIES.with(|entries| {
+            let entries = entries.borrow();
+            identifiers
+                .into_iter()
+                .filter_map(|identifier| {
+                    let attendee = entries.get(&identifier)?;
+                    Some((Principal::from_text(identifier).ok()?, attendee))
+                })
+                .collect()
+        })
+    }
+
+    // Method to get the attendee identifiers of a page from an event index, starting after the cursor
+    // returns the cursor for the next page if there are more entries for the event
+    fn _get_index_page(
+        index: &StableBTreeMap<String, String, Memory>,
+        event_identifier: &Principal,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> (Vec<String>, Option<String>) {
+        let limit = limit.min(MAX_PAGE_LIMIT);
+        let prefix = format!("{}:", event_identifier);
+        let start = match cursor.as_deref().map(decode_cursor) {
+            None => Bound::Included(prefix.clone()),
+            Some(Some(_principal)) => Bound::Excluded(format!("{}{}", prefix, _principal)),
+            // an invalid cursor results in an empty page, so a caller doesnt start over from the first page
+            Some(None) => return (vec![], None),
+        };
+
+        // Fetch one more entry than the limit to see if there is a next page
+        let mut page: Vec<(String, String)> = index
+            .range((start, Bound::Unbounded))
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .take(limit + 1)
+            .collect();
+
+        let next_cursor = if page.len() > limit {
+            page.truncate(limit);
+            page.last()
+                .and_then(|(key, _)| Principal::from_text(&key[prefix.len()..]).ok())
+                .map(|principal| encode_cursor(&principal))
+        } else {
+            None
+        };
+
+        (
+            page.into_iter().map(|(_, identifier)| identifier).collect(),
+            next_cursor,
+        )
+    }
+
+    // Method to keep the attendee count index in sync and queue the change for the event canister
+    // the count is clamped at 0, only the part of the delta that changed the count is queued
+    fn update_attendee_count(event_identifier: Principal, delta: i64) {
+        let key = event_identifier.to_string();
+        let applied_delta = ATTENDEE_COUNTS.with(|counts| {
+            let mut counts = counts.borrow_mut();
+            let previous = counts.get(&key).unwrap_or(0);
+            let count = (previous as i64 + delta).max(0) as u64;
+
+            if count == 0 {
+                counts.remove(&key);
+            } else {
+                counts.insert(key.clone(), count);
+            }
+            count as i64 - previous as i64
+        });
+
+        // An event without attendees is no longer in the count index, so it is remembered for the reconciliation
+        ZEROED_COUNTS.with(|zeroed| {
+            let has_attendees = ATTENDEE_COUNTS.with(|counts| counts.borrow().contains_key(&key));
+            match has_attendees {
+                true => zeroed.borrow_mut().remove(&key),
+                false => zeroed.borrow_mut().insert(key, time()),
+            }
+        });
+
+        if applied_delta != 0 {
+            Outbox::enqueue(event_identifier, applied_delta);
+        }
+    }
+
+    // Method to rebuild the attendee count index from the stored attendees
+    pub fn rebuild_attendee_counts() {
+        let mut counts: HashMap<String, u64> = HashMap::new();
+        ENTRIES.with(|entries| {
+            entries.borrow().iter().for_each(|(_, attendee)| {
+                attendee.joined.keys().for_each(|event_identifier| {
+                    *counts.entry(event_identifier.to_string()).or_insert(0) += 1;
+                })
+            })
+        });
+
+        ATTENDEE_COUNTS.with(|n| {
+            n.replace(StableBTreeMap::new(
+                MEMORY_MANAGER.with(|m| m.borrow().get(ATTENDEE_COUNTS_MEMORY_ID)),
+            ));
+            let mut index = n.borrow_mut();
+            counts.into_iter().for_each(|(key, count)| {
+                index.insert(key, count);
+            });
+        });
+
+        Certification::rebuild();
+    }
+
+    // Method to get the next sequence number for the attendee count updates
+    pub fn next_count_sequence() -> u64 {
+        COUNT_SEQUENCE.with(|sequence| {
+            let next = sequence.borrow().get() + 1;
+            let _ = sequence.borrow_mut().set(next);
+            next
+        })
+    }
+
+    // Method to register attendee principals in the directory of the parent (inter-canister call)
+    pub async fn register_attendee_canisters(principals: Vec<Principal>) -> Result<(), String> {
+        let parent = STABLE_DATA.with(|data| data.borrow().get().parent);
+        let result: Result<(Result<(), ApiError>,), _> =
+            call::call(parent, "register_attendee_canisters", (principals,)).await;
+
+        match result {
+            Err(err) => {
+                record_call_failure("register_attendee_canisters");
+                Err(format!("{:?} - {}", err.0, err.1))
+            }
+            Ok((Err(err),)) => Err(format!("{:?}", err)),
+            Ok((Ok(_),)) => Ok(()),
+        }
+    }
+
+    // Method to send an attendee count delta to the event canister (inter-canister call)
+    pub async fn send_attendee_count_delta(delta: AttendeeCountDelta) -> Result<(), String> {
+        let (_, event_canister, _) = Identifier::decode(&delta.event_identifier);
+        call::call::<(AttendeeCountDelta,), ()>(
+            event_canister,
+            "apply_attendee_count_delta",
+            (delta,),
+        )
+        .await
+        .map_err(|err| {
+            record_call_failure("apply_attendee_count_delta");
+            format!("{:?} - {}", err.0, err.1)
+        })
+    }
+
+    // Method to send the absolute attendee counts of this child to the event canisters
+    // the counts are read from the count index, that is kept in sync on every join and leave,
+    // so the attendees don't have to be scanned; events with undelivered deltas are skipped, they are picked up by the next run
+    pub async fn reconcile_attendee_counts() {
+        // Group the counts per event canister so a single call is made per canister
+        let mut reconciliations: HashMap<Principal, Vec<AttendeeCountReconciliation>> =
+            HashMap::new();
+        // Events whose last attendee left are send with a count of 0
+        let mut counts: Vec<(String, u64)> =
+            ATTENDEE_COUNTS.with(|counts| counts.borrow().iter().collect());
+        let zeroed: Vec<String> = ZEROED_COUNTS.with(|zeroed| {
+            zeroed
+                .borrow()
+                .iter()
+                .map(|(event_identifier, _)| event_identifier)
+                .filter(|event_identifier| {
+                    ATTENDEE_COUNTS.with(|counts| !counts.borrow().contains_key(event_identifier))
+                })
+                .collect()
+        });
+        counts.extend(
+            zeroed
+                .iter()
+                .map(|event_identifier| (event_identifier.clone(), 0)),
+        );
+
+        for (event_identifier, count) in counts {
+            let event_identifier = match Principal::from_text(&event_identifier) {
+                Ok(_event_identifier) => _event_identifier,
+                Err(_) => continue,
+            };
+
+            if Outbox::has_entry(&event_identifier) {
+                continue;
+            }
+
+            let (_, event_canister, _) = Identifier::decode(&event_identifier);
+            reconciliations
+                .entry(event_canister)
+                .or_default()
+                .push(AttendeeCountReconciliation {
+                    event_identifier,
+                    child_identifier: id(),
+                    sequence: Self::next_count_sequence(),
+                    count,
+                });
+        }
+
+        for (event_canister, _reconciliations) in reconciliations {
+            let delivered: Vec<String> = _reconciliations
+                .iter()
+                .filter(|reconciliation| reconciliation.count == 0)
+                .map(|reconciliation| reconciliation.event_identifier.to_string())
+                .collect();
+
+            let result = call::call::<(Vec<AttendeeCountReconciliation>,), ()>(
+                event_canister,
+                "reconcile_attendee_counts",
+                (_reconciliations,),
+            )
+            .await;
+
+            match result {
+                Err(_) => record_call_failure("reconcile_attendee_counts"),
+                // The 0 counts are only send once, an event that gets attendees again is removed on the join
+                Ok(_) => ZEROED_COUNTS.with(|zeroed| {
+                    let mut zeroed = zeroed.borrow_mut();
+                    delivered.iter().for_each(|event_identifier| {
+                        zeroed.remove(event_identifier);
+                    });
+                }),
+            }
+        }
+    }
+
+    // This method is used for role / permission based access control
+    pub async fn can_write(
+        caller: Principal,
+        group_identifier: Principal,
+        member_identifier: Principal,
+    ) -> Result<Principal, ApiError> {
+        Self::check_permission(
+            caller,
+            group_identifier,
+            member_identifier,
+            PermissionActionType::Write,
+            PermissionType::Attendee(None),
+        )
+        .await
+    }
+
+    // This method is used for role / permission based access control
+    pub async fn can_read(
+        caller: Principal,
+        group_identifier: Principal,
+        member_identifier: Principal,
+    ) -> Result<Principal, ApiError> {
+        Self::check_permission(
+            caller,
+            group_identifier,
+            member_identifier,
+            PermissionActionType::Read,
+            PermissionType::Attendee(None),
+        )
+        .await
     }
 
     // This method is used for role / permission based access control
@@ -959,122 +2627,210 @@ impl Store {
     // Used for composite_query calls from the parent canister
     //
     // Method to get filtered attendees serialized and chunked
+    // the first chunk is requested without a snapshot id, the snapshot id of that chunk is passed for the other chunks
     pub fn get_chunked_join_data(
-        event_identifier: &Principal,
+        request: &AttendeeListRequest,
         chunk: usize,
         max_bytes_per_chunk: usize,
-    ) -> (Vec<u8>, (usize, usize)) {
-        let attendees = ENTRIES.with(|entries| Data::get_entries(entries));
-        // Get attendees for filtering
-        let mapped_attendees: Vec<JoinedAttendeeResponse> = attendees
-            .iter()
-            // Filter attendees that have joined the group
-            .filter(|(_identifier, _attendee_data)| {
-                _attendee_data
-                    .joined
-                    .iter()
-                    .any(|(_event_identifier, _)| _event_identifier == event_identifier)
-            })
-            // Map attendee to joined attendee response
-            .map(|(_identifier, _attendee_data)| {
-                Self::map_attendee_to_joined_attendee_response(
-                    &Principal::from_text(_identifier).expect("failed"),
-                    _attendee_data,
-                    event_identifier.clone(),
-                )
-            })
-            .collect();
-
-        if let Ok(bytes) = serialize(&mapped_attendees) {
-            // Check if the bytes of the serialized groups are greater than the max bytes per chunk specified as an argument
-            if bytes.len() >= max_bytes_per_chunk {
-                // Get the start and end index of the bytes to be returned
-                let start = chunk * max_bytes_per_chunk;
-                let end = (chunk + 1) * (max_bytes_per_chunk);
+        snapshot_id: Option<u64>,
+    ) -> Result<ChunkedData, ApiError> {
+        Self::_get_snapshot_chunk(
+            "join",
+            request,
+            chunk,
+            max_bytes_per_chunk,
+            snapshot_id,
+            || serialize(&Self::_get_filtered_join_data(request)),
+        )
+    }
 
-                // Get the bytes to be returned, if the end index is greater than the length of the bytes, return the remaining bytes
-                let response = if end >= bytes.len() {
-                    bytes[start..].to_vec()
-                } else {
-                    bytes[start..end].to_vec()
-                };
+    // Method to store the filtered attendees serialized in the snapshot cache, returns the snapshot id
+    pub fn create_join_data_snapshot(request: &AttendeeListRequest) -> Result<u64, ApiError> {
+        Self::_create_snapshot("join", request, || {
+            serialize(&Self::_get_filtered_join_data(request))
+        })
+    }
 
-                // Determine the max number of chunks that can be returned, a float is used because the number of chunks can be a decimal in this step
-                let mut max_chunks: f64 = 0.00;
-                if max_bytes_per_chunk < bytes.len() {
-                    max_chunks = (bytes.len() / max_bytes_per_chunk) as f64;
-                }
+    // Method to get the attendees of an event filtered and sorted with the request
+    fn _get_filtered_join_data(
+        request: &AttendeeListRequest,
+    ) -> Vec<(Join, JoinedAttendeeResponse)> {
+        let event_identifier = request.event_identifier;
+        // Get the attendees that have joined the event from the join index
+        let attendees = JOIN_INDEX
+            .with(|index| Self::_get_event_index_attendees(&index.borrow(), &event_identifier));
 
-                // return the response and start and end chunk index, the end chunk index is calculated by rounding up the max chunks
-                return (response, (chunk, max_chunks.ceil() as usize));
-            }
+        let mut mapped_attendees: Vec<(Join, JoinedAttendeeResponse)> = attendees
+            .iter()
+            // Map attendee to the join and joined attendee response
+            .filter_map(|(_identifier, _attendee_data)| {
+                let join = _attendee_data.joined.get(&event_identifier)?.clone();
+                Some((
+                    join,
+                    Self::map_attendee_to_joined_attendee_response(
+                        _identifier,
+                        _attendee_data,
+                        event_identifier,
+                    ),
+                ))
+            })
+            // Filter attendees on the filters of the request
+            .filter(|entry| request.is_match(entry))
+            .collect();
 
-            // if the bytes of the serialized groups are less than the max bytes per chunk specified as an argument, return the bytes and start and end chunk index as 0
-            return (bytes, (0, 0));
-        } else {
-            // if the groups cant be serialized return an empty vec and start and end chunk index as 0
-            return (vec![], (0, 0));
-        }
+        // Sort the attendees, the parent canister merges the sorted data of the child canisters
+        mapped_attendees.sort_by(|a, b| request.sort.compare(a, b));
+        mapped_attendees
     }
 
     // Used for composite_query calls from the parent canister
     //
-    // Method to get filtered attendees serialized and chunked
+    // Method to get filtered invites serialized and chunked
+    // the first chunk is requested without a snapshot id, the snapshot id of that chunk is passed for the other chunks
     pub fn get_chunked_invite_data(
-        event_identifier: &Principal,
+        request: &AttendeeListRequest,
         chunk: usize,
         max_bytes_per_chunk: usize,
-    ) -> (Vec<u8>, (usize, usize)) {
-        let attendees = ENTRIES.with(|entries| Data::get_entries(entries));
-        // Get attendees for filtering
-        let mapped_attendees: Vec<InviteAttendeeResponse> = attendees
+        snapshot_id: Option<u64>,
+    ) -> Result<ChunkedData, ApiError> {
+        Self::_get_snapshot_chunk(
+            "invite",
+            request,
+            chunk,
+            max_bytes_per_chunk,
+            snapshot_id,
+            || serialize(&Self::_get_filtered_invite_data(request)),
+        )
+    }
+
+    // Method to store the filtered invites serialized in the snapshot cache, returns the snapshot id
+    pub fn create_invite_data_snapshot(request: &AttendeeListRequest) -> Result<u64, ApiError> {
+        Self::_create_snapshot("invite", request, || {
+            serialize(&Self::_get_filtered_invite_data(request))
+        })
+    }
+
+    // Method to get the invites of an event filtered and sorted with the request
+    fn _get_filtered_invite_data(
+        request: &AttendeeListRequest,
+    ) -> Vec<(Invite, InviteAttendeeResponse)> {
+        let event_identifier = request.event_identifier;
+        // Get the attendees that are invited to the event from the invite index
+        let attendees = INVITE_INDEX
+            .with(|index| Self::_get_event_index_attendees(&index.borrow(), &event_identifier));
+
+        let mut mapped_attendees: Vec<(Invite, InviteAttendeeResponse)> = attendees
             .iter()
-            // Filter attendees that have joined the group
-            .filter(|(_identifier, _attendee_data)| {
-                _attendee_data
-                    .invites
-                    .iter()
-                    .any(|(_event_identifier, _)| _event_identifier == event_identifier)
-            })
-            // Map member to joined member response
-            .map(|(_identifier, _event_data)| {
-                Self::map_attendee_to_invite_attendee_response(
-                    &Principal::from_text(_identifier).expect("failed"),
-                    _event_data,
-                    event_identifier.clone(),
-                )
+            // Map attendee to the invite and invite attendee response
+            .filter_map(|(_identifier, _event_data)| {
+                let invite = _event_data.invites.get(&event_identifier)?.clone();
+                Some((
+                    invite,
+                    Self::map_attendee_to_invite_attendee_response(
+                        _identifier,
+                        _event_data,
+                        event_identifier,
+                    ),
+                ))
             })
+            // Filter invites on the filters of the request
+            .filter(|entry| request.is_match(entry))
             .collect();
 
-        if let Ok(bytes) = serialize(&mapped_attendees) {
-            // Check if the bytes of the serialized groups are greater than the max bytes per chunk specified as an argument
-            if bytes.len() >= max_bytes_per_chunk {
-                // Get the start and end index of the bytes to be returned
-                let start = chunk * max_bytes_per_chunk;
-                let end = (chunk + 1) * (max_bytes_per_chunk);
+        // Sort the invites, the parent canister merges the sorted data of the child canisters
+        mapped_attendees.sort_by(|a, b| request.sort.compare(a, b));
+        mapped_attendees
+    }
 
-                // Get the bytes to be returned, if the end index is greater than the length of the bytes, return the remaining bytes
-                let response = if end >= bytes.len() {
-                    bytes[start..].to_vec()
-                } else {
-                    bytes[start..end].to_vec()
-                };
+    // Method to get a single chunk of the serialized data of a snapshot
+    // a snapshot is served from the cache, or serialized again if the data didnt change since the snapshot was taken
+    fn _get_snapshot_chunk<E: ToString>(
+        kind: &str,
+        request: &AttendeeListRequest,
+        chunk: usize,
+        max_bytes_per_chunk: usize,
+        snapshot_id: Option<u64>,
+        serialize_data: impl FnOnce() -> Result<Vec<u8>, E>,
+    ) -> Result<ChunkedData, ApiError> {
+        let chunk_error = |tag: &str, message: &str| {
+            api_error(
+                ApiErrorType::BadRequest,
+                tag,
+                message,
+                STABLE_DATA
+                    .with(|data| Data::get_name(data.borrow().get()))
+                    .as_str(),
+                "get_chunk",
+                Some(vec![
+                    format!("chunk - {}", chunk),
+                    format!("max_bytes_per_chunk - {}", max_bytes_per_chunk),
+                    format!("snapshot_id - {:?}", snapshot_id),
+                ]),
+            )
+        };
+
+        let data_version = Self::get_event_data_version(&request.event_identifier);
+        let cached = snapshot_id.and_then(|_snapshot_id| {
+            Snapshots::get(_snapshot_id, &Self::_snapshot_key(kind, request))
+                .map(|_bytes| (_snapshot_id, _bytes))
+        });
 
-                // Determine the max number of chunks that can be returned, a float is used because the number of chunks can be a decimal in this step
-                let mut max_chunks: f64 = 0.00;
-                if max_bytes_per_chunk < bytes.len() {
-                    max_chunks = (bytes.len() / max_bytes_per_chunk) as f64;
+        let (snapshot_id, bytes) = match cached {
+            Some(_cached) => _cached,
+            None => {
+                if let Some(_snapshot_id) = snapshot_id {
+                    if _snapshot_id != data_version {
+                        return Err(chunk_error(
+                            "SNAPSHOT_EXPIRED",
+                            "The data changed since the snapshot was taken, request the first chunk again",
+                        ));
+                    }
                 }
 
-                // return the response and start and end chunk index, the end chunk index is calculated by rounding up the max chunks
-                return (response, (chunk, max_chunks.ceil() as usize));
+                match serialize_data() {
+                    Err(err) => return Err(chunk_error("INVALID_CHUNK", &err.to_string())),
+                    Ok(_bytes) => (data_version, _bytes),
+                }
             }
+        };
 
-            // if the bytes of the serialized groups are less than the max bytes per chunk specified as an argument, return the bytes and start and end chunk index as 0
-            return (bytes, (0, 0));
-        } else {
-            // if the groups cant be serialized return an empty vec and start and end chunk index as 0
-            return (vec![], (0, 0));
+        get_chunk(&bytes, chunk, max_bytes_per_chunk, snapshot_id)
+            .map_err(|err| chunk_error("INVALID_CHUNK", err.as_str()))
+    }
+
+    // Method to serialize the data and store it in the snapshot cache
+    fn _create_snapshot<E: ToString>(
+        kind: &str,
+        request: &AttendeeListRequest,
+        serialize_data: impl FnOnce() -> Result<Vec<u8>, E>,
+    ) -> Result<u64, ApiError> {
+        let snapshot_error = |message: &str| {
+            api_error(
+                ApiErrorType::BadRequest,
+                "INVALID_SNAPSHOT",
+                message,
+                STABLE_DATA
+                    .with(|data| Data::get_name(data.borrow().get()))
+                    .as_str(),
+                "create_snapshot",
+                None,
+            )
+        };
+
+        let snapshot_id = Self::get_event_data_version(&request.event_identifier);
+        match serialize_data() {
+            Err(err) => Err(snapshot_error(&err.to_string())),
+            Ok(bytes) => {
+                match Snapshots::insert(snapshot_id, Self::_snapshot_key(kind, request), bytes) {
+                    true => Ok(snapshot_id),
+                    false => Err(snapshot_error("The data is too large to cache")),
+                }
+            }
         }
     }
+
+    // Key of the snapshot cache, the same snapshot id is shared by all requests on the same event data version
+    fn _snapshot_key(kind: &str, request: &AttendeeListRequest) -> Vec<u8> {
+        serialize(&(kind, request)).unwrap_or_default()
+    }
 }
@@ -0,0 +1,258 @@
+use std::time::Duration;
+
+use candid::Principal;
+use ic_cdk::api::{call, time};
+use ic_cdk_timers::set_timer_interval;
+use ic_scalable_canister::ic_scalable_misc::{
+    enums::api_error_type::{ApiError, ApiErrorType},
+    helpers::error_helper::api_error,
+};
+
+use shared::{
+    metrics_helper::record_call_failure,
+    subscription_model::{Subscription, SubscriptionRequest, SubscriptionStatus},
+};
+
+use crate::{store::SUBSCRIPTIONS, transaction_log::TransactionLog};
+
+// Interval on which the changes are dispatched to the subscribers
+const DISPATCH_INTERVAL_SECONDS: u64 = 10;
+// Max number of subscriptions on a single child canister
+const MAX_SUBSCRIPTIONS: u64 = 100;
+// Max number of events a single subscription can filter on
+const MAX_EVENT_FILTER: usize = 100;
+// Max number of changes that are delivered in a single notification
+pub const MAX_CHANGES_PER_BATCH: usize = 100;
+// Max number of subscriptions that are delivered to per dispatch
+const MAX_DELIVERIES_PER_DISPATCH: usize = 20;
+// After this number of failed notifications a subscription is marked as failed
+const MAX_ATTEMPTS: u32 = 10;
+// Backoff bounds in nanoseconds
+const BASE_BACKOFF_NANOS: u64 = 10 * 1_000_000_000;
+const MAX_BACKOFF_NANOS: u64 = 60 * 60 * 1_000_000_000;
+
+pub struct Subscriptions;
+
+impl Subscriptions {
+    // Method to start the timer that dispatches the changes to the subscribers,
+    // needs to be called on init and post_upgrade
+    pub fn start_timer() {
+        set_timer_interval(
+            Duration::from_secs(DISPATCH_INTERVAL_SECONDS),
+            Self::dispatch,
+        );
+    }
+
+    // Method to add or update the subscription of a canister
+    // the changes are delivered from the current end of the log, an existing subscription keeps its position
+    // new subscriptions are only created by a monitor, so the subscriptions can't be exhausted by unknown canisters
+    pub fn subscribe(
+        subscriber: Principal,
+        request: SubscriptionRequest,
+        can_create: bool,
+    ) -> Result<Subscription, ApiError> {
+        // Only canisters can receive the changes
+        if subscriber.as_slice().last() != Some(&1) {
+            return Err(api_error(
+                ApiErrorType::Unauthorized,
+                "ONLY_CANISTERS",
+                "Only canisters can subscribe on the attendee changes",
+                "Subscriptions",
+                "subscribe",
+                None,
+            ));
+        }
+
+        if request.method.is_empty() {
+            return Err(api_error(
+                ApiErrorType::BadRequest,
+                "INVALID_METHOD",
+                "The callback method can not be empty",
+                "Subscriptions",
+                "subscribe",
+                None,
+            ));
+        }
+
+        if request.event_identifiers.len() > MAX_EVENT_FILTER {
+            return Err(api_error(
+                ApiErrorType::BadRequest,
+                "TOO_MANY_EVENTS",
+                "The subscription filters on too many events",
+                "Subscriptions",
+                "subscribe",
+                None,
+            ));
+        }
+
+        let now = time();
+        SUBSCRIPTIONS.with(|subscriptions| {
+            let mut subscriptions = subscriptions.borrow_mut();
+            let existing = subscriptions.get(&subscriber.to_string());
+
+            if existing.is_none() && !can_create {
+                return Err(api_error(
+                    ApiErrorType::Unauthorized,
+                    "SUBSCRIPTION_NOT_FOUND",
+                    "The canister has no subscription, subscriptions are created by a monitor",
+                    "Subscriptions",
+                    "subscribe",
+                    None,
+                ));
+            }
+
+            if existing.is_none() && subscriptions.len() >= MAX_SUBSCRIPTIONS {
+                return Err(api_error(
+                    ApiErrorType::BadRequest,
+                    "MAX_SUBSCRIPTIONS_REACHED",
+                    "The max number of subscriptions is reached",
+                    "Subscriptions",
+                    "subscribe",
+                    None,
+                ));
+            }
+
+            let subscription = Subscription {
+                subscriber,
+                event_identifiers: request.event_identifiers,
+                method: request.method,
+                status: SubscriptionStatus::Active,
+                next_sequence: match &existing {
+                    None => TransactionLog::get_log_length(),
+                    Some(_existing) => _existing.next_sequence,
+                },
+                attempts: 0,
+                next_attempt_at: now,
+                last_error: None,
+                updated_at: now,
+                created_at: match &existing {
+                    None => now,
+                    Some(_existing) => _existing.created_at,
+                },
+            };
+
+            subscriptions.insert(subscriber.to_string(), subscription.clone());
+            Ok(subscription)
+        })
+    }
+
+    // Method to remove the subscription of a canister
+    pub fn unsubscribe(subscriber: Principal) -> Result<(), ApiError> {
+        match SUBSCRIPTIONS
+            .with(|subscriptions| subscriptions.borrow_mut().remove(&subscriber.to_string()))
+        {
+            None => Err(api_error(
+                ApiErrorType::NotFound,
+                "SUBSCRIPTION_NOT_FOUND",
+                "Subscription not found",
+                "Subscriptions",
+                "unsubscribe",
+                None,
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+
+    pub fn get_subscription(subscriber: Principal) -> Option<Subscription> {
+        SUBSCRIPTIONS.with(|subscriptions| subscriptions.borrow().get(&subscriber.to_string()))
+    }
+
+    // Method to get the subscriptions, optionally filtered on status
+    pub fn get_subscriptions(status: Option<SubscriptionStatus>) -> Vec<Subscription> {
+        SUBSCRIPTIONS.with(|subscriptions| {
+            subscriptions
+                .borrow()
+                .iter()
+                .map(|(_, subscription)| subscription)
+                .filter(|subscription| match &status {
+                    None => true,
+                    Some(_status) => &subscription.status == _status,
+                })
+                .collect()
+        })
+    }
+
+    // Method to deliver the undelivered changes to the subscriptions that are due
+    // the changes are sent as one-way notifications, so a subscriber can't hold up the dispatcher
+    pub fn dispatch() {
+        let now = time();
+        let log_length = TransactionLog::get_log_length();
+        let due_subscriptions: Vec<Subscription> = SUBSCRIPTIONS.with(|subscriptions| {
+            subscriptions
+                .borrow()
+                .iter()
+                .map(|(_, subscription)| subscription)
+                .filter(|subscription| subscription.status == SubscriptionStatus::Active)
+                .filter(|subscription| subscription.next_attempt_at <= now)
+                .filter(|subscription| subscription.next_sequence < log_length)
+                .take(MAX_DELIVERIES_PER_DISPATCH)
+                .collect()
+        });
+
+        for subscription in due_subscriptions {
+            let batch = TransactionLog::get_changes(
+                subscription.next_sequence,
+                MAX_CHANGES_PER_BATCH,
+                &subscription.event_identifiers,
+            );
+
+            // None of the changes match the filter, only the position is moved
+            if batch.changes.is_empty() {
+                Self::complete(&subscription, batch.next_sequence, Ok(()));
+                continue;
+            }
+
+            // A notification only fails if it can't be enqueued, changes that are lost after that
+            // are fetched by the subscriber with get_attendee_changes
+            let next_sequence = batch.next_sequence;
+            let result = call::notify(subscription.subscriber, &subscription.method, (batch,))
+                // The subscriber methods are chosen by the subscribers, so they are counted under a single label
+                .map_err(|code| {
+                    record_call_failure("subscription_callback");
+                    format!("{:?}", code)
+                });
+            Self::complete(&subscription, next_sequence, result);
+        }
+    }
+
+    // Method to process the result of a delivery
+    fn complete(delivered: &Subscription, next_sequence: u64, result: Result<(), String>) {
+        let key = delivered.subscriber.to_string();
+        SUBSCRIPTIONS.with(|subscriptions| {
+            let mut subscriptions = subscriptions.borrow_mut();
+            let mut subscription = match subscriptions.get(&key) {
+                None => return,
+                Some(_subscription) => _subscription,
+            };
+
+            match result {
+                Ok(_) => {
+                    subscription.next_sequence = next_sequence;
+                    subscription.attempts = 0;
+                    subscription.last_error = None;
+                    subscription.next_attempt_at = time();
+                }
+                Err(err) => {
+                    subscription.attempts += 1;
+                    subscription.last_error = Some(err);
+
+                    if subscription.attempts >= MAX_ATTEMPTS {
+                        subscription.status = SubscriptionStatus::Failed;
+                    } else {
+                        subscription.next_attempt_at =
+                            time() + Self::backoff(subscription.attempts);
+                    }
+                }
+            }
+            subscription.updated_at = time();
+            subscriptions.insert(key, subscription);
+        });
+    }
+
+    // Exponential backoff based on the number of attempts
+    fn backoff(attempts: u32) -> u64 {
+        BASE_BACKOFF_NANOS
+            .saturating_mul(2_u64.saturating_pow(attempts.saturating_sub(1)))
+            .min(MAX_BACKOFF_NANOS)
+    }
+}
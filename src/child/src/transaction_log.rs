@@ -0,0 +1,396 @@
+use std::{cell::Cell, collections::BTreeMap, convert::TryFrom};
+
+use candid::{Nat, Principal};
+use ic_cdk::{api::time, id};
+use serde_bytes::ByteBuf;
+
+use shared::{
+    attendee_model::{Attendee, InviteType},
+    icrc3_helper::hash_value,
+    icrc3_model::{
+        BlockTypeDocumentation, BlockWithId, GetBlocksArgs, GetBlocksResult, StoredBlock,
+        SupportedBlockType, Value, BLOCK_TYPE_ACCEPT, BLOCK_TYPE_INVITE, BLOCK_TYPE_JOIN,
+        BLOCK_TYPE_LEAVE, BLOCK_TYPE_REMOVE, BLOCK_TYPE_REMOVE_INVITE,
+    },
+    subscription_model::{AttendeeChange, AttendeeChangeBatch, AttendeeChangeType},
+};
+
+use crate::{audit::Audit, certification::Certification, store::BLOCKS};
+
+// Max number of blocks that are returned by a single icrc3_get_blocks call
+const MAX_BLOCKS_PER_REQUEST: u64 = 1000;
+// Max number of blocks that are scanned for a single batch of changes
+const MAX_SCANNED_PER_BATCH: u64 = 10_000;
+// Path of the documentation of the block types, served by the canister (see http.rs)
+const BLOCK_TYPES_PATH: &str = "icrc3/block-types";
+// Reason that is added to the blocks of records that are moved by the deduplication of the parent
+const REASON_DEDUPLICATION: &str = "deduplication";
+
+thread_local! {
+    // Set while the parent merges or removes a duplicate attendee record
+    static IS_DEDUPLICATING: Cell<bool> = const { Cell::new(false) };
+}
+
+pub struct TransactionLog;
+
+impl TransactionLog {
+    // Method to apply a merge or removal of a duplicate record, the blocks that are appended while applying it are marked
+    // with the deduplication reason, the joins and invites only moved between the canisters so they are no changes for the subscribers
+    pub fn with_deduplication<T>(apply: impl FnOnce() -> T) -> T {
+        IS_DEDUPLICATING.with(|deduplicating| deduplicating.set(true));
+        let result = apply();
+        IS_DEDUPLICATING.with(|deduplicating| deduplicating.set(false));
+        result
+    }
+
+    // Method to append a block for every change between the previous and current attendee record
+    // called from the central write path of the store, so every change of the joins and invites is logged
+    pub fn record_changes(principal: Principal, previous: &Attendee, current: &Attendee) {
+        for (event_identifier, join) in current.joined.iter() {
+            if !previous.joined.contains_key(event_identifier) {
+                // A join that replaces an invite is an accepted invite
+                let block_type = match previous.invites.contains_key(event_identifier) {
+                    true => BLOCK_TYPE_ACCEPT,
+                    false => BLOCK_TYPE_JOIN,
+                };
+                Self::append(
+                    block_type,
+                    principal,
+                    *event_identifier,
+                    join.group_identifier,
+                    vec![],
+                );
+            }
+        }
+
+        for (event_identifier, join) in previous.joined.iter() {
+            if !current.joined.contains_key(event_identifier) {
+                // The attendee left the event if the attendee made the write, otherwise the attendee is removed
+                let block_type = match Audit::get_actor() == principal {
+                    true => BLOCK_TYPE_LEAVE,
+                    false => BLOCK_TYPE_REMOVE,
+                };
+                Self::append(
+                    block_type,
+                    principal,
+                    *event_identifier,
+                    join.group_identifier,
+                    vec![],
+                );
+            }
+        }
+
+        for (event_identifier, invite) in current.invites.iter() {
+            if !previous.invites.contains_key(event_identifier) {
+                let invite_type = match invite.invite_type {
+                    InviteType::None => "none",
+                    InviteType::OwnerRequest => "owner_request",
+                    InviteType::UserRequest => "user_request",
+                };
+                Self::append(
+                    BLOCK_TYPE_INVITE,
+                    principal,
+                    *event_identifier,
+                    invite.group_identifier,
+                    vec![(
+                        "invite_type".to_string(),
+                        Value::Text(invite_type.to_string()),
+                    )],
+                );
+            }
+        }
+
+        for (event_identifier, invite) in previous.invites.iter() {
+            // Invites that are accepted are already logged as accepted
+            if !current.invites.contains_key(event_identifier)
+                && !current.joined.contains_key(event_identifier)
+            {
+                Self::append(
+                    BLOCK_TYPE_REMOVE_INVITE,
+                    principal,
+                    *event_identifier,
+                    invite.group_identifier,
+                    vec![],
+                );
+            }
+        }
+    }
+
+    // Method to append a block to the log, chained to the previous block by its hash
+    fn append(
+        block_type: &str,
+        principal: Principal,
+        event_identifier: Principal,
+        group_identifier: Principal,
+        mut fields: Vec<(String, Value)>,
+    ) {
+        BLOCKS.with(|blocks| {
+            let mut blocks = blocks.borrow_mut();
+            let index = blocks.len();
+
+            fields.extend(vec![
+                ("principal".to_string(), Self::principal_value(principal)),
+                ("event".to_string(), Self::principal_value(event_identifier)),
+                ("group".to_string(), Self::principal_value(group_identifier)),
+                (
+                    "caller".to_string(),
+                    Self::principal_value(Audit::get_actor()),
+                ),
+            ]);
+            if IS_DEDUPLICATING.with(|deduplicating| deduplicating.get()) {
+                fields.push((
+                    "reason".to_string(),
+                    Value::Text(REASON_DEDUPLICATION.to_string()),
+                ));
+            }
+
+            let mut block = vec![
+                ("btype".to_string(), Value::Text(block_type.to_string())),
+                ("ts".to_string(), Value::Nat(Nat::from(time()))),
+                ("tx".to_string(), Value::Map(fields)),
+            ];
+            // The first block has no parent hash
+            if let Some((_, _previous)) = blocks.last_key_value() {
+                block.push(("phash".to_string(), Value::Blob(_previous.hash)));
+            }
+
+            let block = Value::Map(block);
+            let hash = ByteBuf::from(hash_value(&block).to_vec());
+            blocks.insert(
+                index,
+                StoredBlock {
+                    block,
+                    hash: hash.clone(),
+                },
+            );
+            // The last block is part of the certified data, so the chain can be verified from a certified tip
+            Certification::set_tip(index, hash.into_vec());
+        });
+    }
+
+    // Method to get the blocks of the requested ranges, the blocks are never archived
+    pub fn get_blocks(args: Vec<GetBlocksArgs>) -> GetBlocksResult {
+        BLOCKS.with(|blocks| {
+            let blocks = blocks.borrow();
+            let log_length = blocks.len();
+            let mut remaining = MAX_BLOCKS_PER_REQUEST;
+            let mut result: Vec<BlockWithId> = vec![];
+
+            for arg in args {
+                let start = Self::nat_to_u64(&arg.start);
+                let length = Self::nat_to_u64(&arg.length).min(remaining);
+                let end = start.saturating_add(length).min(log_length);
+
+                for (id, stored) in blocks.range(start..end) {
+                    result.push(BlockWithId {
+                        id: Nat::from(id),
+                        block: stored.block,
+                    });
+                }
+                remaining -= end.saturating_sub(start).min(remaining);
+            }
+
+            GetBlocksResult {
+                log_length: Nat::from(log_length),
+                blocks: result,
+                archived_blocks: vec![],
+            }
+        })
+    }
+
+    // Method to get the changes starting from a sequence, optionally filtered on events
+    // the next_sequence of the batch is the sequence to continue from, also when none of the scanned changes matched
+    pub fn get_changes(
+        from_sequence: u64,
+        limit: usize,
+        event_identifiers: &[Principal],
+    ) -> AttendeeChangeBatch {
+        BLOCKS.with(|blocks| {
+            let blocks = blocks.borrow();
+            let log_length = blocks.len();
+            let end = from_sequence
+                .saturating_add(MAX_SCANNED_PER_BATCH)
+                .min(log_length);
+            let mut next_sequence = from_sequence.max(end);
+            let mut changes: Vec<AttendeeChange> = vec![];
+
+            for (sequence, stored) in blocks.range(from_sequence..end) {
+                if changes.len() == limit {
+                    next_sequence = sequence;
+                    break;
+                }
+
+                if let Some(_change) = Self::block_to_change(sequence, &stored.block) {
+                    if event_identifiers.is_empty()
+                        || event_identifiers.contains(&_change.event_identifier)
+                    {
+                        changes.push(_change);
+                    }
+                }
+            }
+
+            AttendeeChangeBatch {
+                canister_identifier: id(),
+                changes,
+                next_sequence,
+                log_length,
+            }
+        })
+    }
+
+    // Method to count the blocks per block type that are appended since the given time
+    // the log is scanned from the newest block, so only the recent blocks are read
+    pub fn count_changes_since(since: u64) -> Vec<(String, u64)> {
+        BLOCKS.with(|blocks| {
+            let blocks = blocks.borrow();
+            let end = blocks.len();
+            let start = end.saturating_sub(MAX_SCANNED_PER_BATCH);
+            let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+
+            for index in (start..end).rev() {
+                let stored = match blocks.get(&index) {
+                    Some(_stored) => _stored,
+                    None => continue,
+                };
+                let fields = match Self::map_fields(&stored.block) {
+                    Some(_fields) => _fields,
+                    None => continue,
+                };
+                match Self::field(fields, "ts") {
+                    Some(Value::Nat(_ts)) if Self::nat_to_u64(_ts) >= since => {}
+                    _ => break,
+                }
+                if Self::is_deduplication(fields) {
+                    continue;
+                }
+                if let Some(Value::Text(_block_type)) = Self::field(fields, "btype") {
+                    *counts.entry(_block_type.clone()).or_default() += 1;
+                }
+            }
+
+            counts.into_iter().collect()
+        })
+    }
+
+    // Method to read the change back from a block that is appended by this log
+    fn block_to_change(sequence: u64, block: &Value) -> Option<AttendeeChange> {
+        let fields = Self::map_fields(block)?;
+        let change_type = match Self::field(fields, "btype")? {
+            Value::Text(_block_type) => match _block_type.as_str() {
+                BLOCK_TYPE_JOIN => AttendeeChangeType::Join,
+                BLOCK_TYPE_ACCEPT => AttendeeChangeType::Accept,
+                BLOCK_TYPE_LEAVE => AttendeeChangeType::Leave,
+                BLOCK_TYPE_REMOVE => AttendeeChangeType::Remove,
+                BLOCK_TYPE_INVITE => AttendeeChangeType::Invite,
+                BLOCK_TYPE_REMOVE_INVITE => AttendeeChangeType::RemoveInvite,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let created_at = match Self::field(fields, "ts")? {
+            Value::Nat(_ts) => Self::nat_to_u64(_ts),
+            _ => return None,
+        };
+        if Self::is_deduplication(fields) {
+            return None;
+        }
+        let tx = Self::map_fields(Self::field(fields, "tx")?)?;
+
+        Some(AttendeeChange {
+            sequence,
+            change_type,
+            principal: Self::principal_field(tx, "principal")?,
+            event_identifier: Self::principal_field(tx, "event")?,
+            group_identifier: Self::principal_field(tx, "group")?,
+            created_at,
+        })
+    }
+
+    // Blocks of records that are moved by the deduplication are no changes of the attendance
+    fn is_deduplication(fields: &[(String, Value)]) -> bool {
+        Self::field(fields, "tx")
+            .and_then(Self::map_fields)
+            .and_then(|tx| Self::field(tx, "reason"))
+            .map(|reason| matches!(reason, Value::Text(_reason) if _reason == REASON_DEDUPLICATION))
+            .unwrap_or(false)
+    }
+
+    fn map_fields(value: &Value) -> Option<&Vec<(String, Value)>> {
+        match value {
+            Value::Map(_fields) => Some(_fields),
+            _ => None,
+        }
+    }
+
+    fn field<'a>(fields: &'a [(String, Value)], key: &str) -> Option<&'a Value> {
+        fields
+            .iter()
+            .find(|(_key, _)| _key == key)
+            .map(|(_, value)| value)
+    }
+
+    fn principal_field(fields: &[(String, Value)], key: &str) -> Option<Principal> {
+        match Self::field(fields, key)? {
+            Value::Blob(_bytes) => Principal::try_from_slice(_bytes).ok(),
+            _ => None,
+        }
+    }
+
+    // Method to get the number of blocks in the log
+    pub fn get_log_length() -> u64 {
+        BLOCKS.with(|blocks| blocks.borrow().len())
+    }
+
+    // The url of a block type points at its documentation, which is served by this canister
+    pub fn get_supported_block_types() -> Vec<SupportedBlockType> {
+        Self::get_block_type_documentation()
+            .into_iter()
+            .map(|documentation| SupportedBlockType {
+                url: format!(
+                    "https://{}.raw.icp0.io/{}#{}",
+                    id(),
+                    BLOCK_TYPES_PATH,
+                    documentation.block_type
+                ),
+                block_type: documentation.block_type,
+            })
+            .collect()
+    }
+
+    pub fn get_block_type_documentation() -> Vec<BlockTypeDocumentation> {
+        vec![
+            (BLOCK_TYPE_JOIN, "The attendee joined the event"),
+            (
+                BLOCK_TYPE_ACCEPT,
+                "An invite or join request of the attendee is accepted",
+            ),
+            (BLOCK_TYPE_LEAVE, "The attendee left the event"),
+            (BLOCK_TYPE_REMOVE, "The attendee is removed from the event"),
+            (
+                BLOCK_TYPE_INVITE,
+                "An invite or join request is added, the tx contains the invite_type",
+            ),
+            (
+                BLOCK_TYPE_REMOVE_INVITE,
+                "An invite or join request is removed",
+            ),
+        ]
+        .into_iter()
+        .map(|(block_type, description)| BlockTypeDocumentation {
+            block_type: block_type.to_string(),
+            description: format!(
+                "{}. The tx contains the principal of the attendee, the event, the group and the caller as blobs",
+                description
+            ),
+        })
+        .collect()
+    }
+
+    fn principal_value(principal: Principal) -> Value {
+        Value::Blob(ByteBuf::from(principal.as_slice().to_vec()))
+    }
+
+    fn nat_to_u64(nat: &Nat) -> u64 {
+        u64::try_from(&nat.0).unwrap_or(u64::MAX)
+    }
+}
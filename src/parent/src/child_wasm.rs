@@ -0,0 +1,275 @@
+use std::time::Duration;
+
+use candid::Principal;
+use ic_cdk::api::time;
+use ic_cdk_timers::set_timer;
+use ic_scalable_misc::{
+    enums::{
+        api_error_type::{ApiError, ApiErrorType},
+        canister_type::CanisterType,
+        wasm_version_type::WasmVersion,
+    },
+    helpers::{error_helper::api_error, logger_helper::add_log},
+    models::{
+        logger_models::{LogType, PostLog},
+        wasm_models::WasmDetails,
+    },
+};
+
+use shared::{
+    chunk_helper::hash_bytes,
+    hex_helper::to_hex,
+    wasm_helper::{
+        get_upload_progress, new_upload, stage_upload, validate_chunk, validate_upload_request,
+        verify_upload,
+    },
+    wasm_model::{
+        ChildWasmMetadata, ChildWasmStatus, ChildWasmUploadProgress, ChildWasmUploadRequest,
+        StagedChildWasm, MAX_CHILD_WASM_HISTORY,
+    },
+};
+
+use crate::{
+    rollout::Rollouts,
+    store::{ScalableData, CHILD_WASM, DATA},
+};
+
+// Upload of the child wasm in chunks, replaces the child wasm that was included in the parent wasm
+// an upload is started with the hash and size, verified when it is finalized and rolled out as a separate step
+// the validation methods return the text that is shown on an SNS proposal
+pub struct ChildWasm;
+
+impl ChildWasm {
+    pub fn get_status() -> ChildWasmStatus {
+        CHILD_WASM.with(|store| {
+            let store = store.borrow();
+            ChildWasmStatus {
+                current: store.history.last().cloned(),
+                staged: store.staged.as_ref().map(|staged| staged.metadata.clone()),
+                upload: store.upload.as_ref().map(get_upload_progress),
+                history: store.history.clone(),
+            }
+        })
+    }
+
+    pub fn validate_start_upload(request: &ChildWasmUploadRequest) -> Result<String, String> {
+        validate_upload_request(request, &Self::get_used_labels())?;
+        Ok(format!(
+            "Upload child wasm {} ({} bytes, sha256 {})",
+            request.label.trim(),
+            request.total_bytes,
+            to_hex(&request.sha256)
+        ))
+    }
+
+    // Method to start a new upload, an upload that is in progress is replaced
+    pub fn start_upload(
+        caller: Principal,
+        request: ChildWasmUploadRequest,
+    ) -> Result<ChildWasmUploadProgress, ApiError> {
+        Self::validate_start_upload(&request)
+            .map_err(|err| Self::error("INVALID_UPLOAD", &err, "start_upload"))?;
+
+        let upload = new_upload(request, caller, time());
+        let progress = get_upload_progress(&upload);
+        CHILD_WASM.with(|store| store.borrow_mut().upload = Some(upload));
+        Ok(progress)
+    }
+
+    pub fn validate_upload_chunk(chunk: u64, bytes: &[u8]) -> Result<String, String> {
+        CHILD_WASM.with(|store| match &store.borrow().upload {
+            None => Err("There is no upload in progress".to_string()),
+            Some(_upload) => {
+                validate_chunk(_upload, chunk, bytes)?;
+                Ok(format!(
+                    "Upload chunk {} ({} bytes) of child wasm {}",
+                    chunk,
+                    bytes.len(),
+                    _upload.request.label
+                ))
+            }
+        })
+    }
+
+    pub fn upload_chunk(chunk: u64, bytes: Vec<u8>) -> Result<ChildWasmUploadProgress, ApiError> {
+        Self::validate_upload_chunk(chunk, &bytes)
+            .map_err(|err| Self::error("INVALID_CHUNK", &err, "upload_chunk"))?;
+
+        CHILD_WASM.with(|store| {
+            let mut store = store.borrow_mut();
+            let upload = store.upload.as_mut().expect("validated upload");
+            upload.bytes.extend(bytes);
+            upload.received_chunks += 1;
+            Ok(get_upload_progress(upload))
+        })
+    }
+
+    pub fn validate_finalize_upload() -> Result<String, String> {
+        CHILD_WASM.with(|store| match &store.borrow().upload {
+            None => Err("There is no upload in progress".to_string()),
+            Some(_upload) => {
+                verify_upload(_upload)?;
+                Ok(format!(
+                    "Finalize the upload of child wasm {} (sha256 {})",
+                    _upload.request.label,
+                    to_hex(&_upload.request.sha256)
+                ))
+            }
+        })
+    }
+
+    // Method to verify the uploaded wasm and store it with the next version, a staged wasm that isnt rolled out is replaced
+    pub fn finalize_upload() -> Result<ChildWasmMetadata, ApiError> {
+        Self::validate_finalize_upload()
+            .map_err(|err| Self::error("INVALID_WASM", &err, "finalize_upload"))?;
+
+        let version = Self::get_next_version();
+        CHILD_WASM.with(|store| {
+            let mut store = store.borrow_mut();
+            let upload = store.upload.take().expect("validated upload");
+            let staged = stage_upload(upload, version);
+            let metadata = staged.metadata.clone();
+            store.staged = Some(staged);
+            Ok(metadata)
+        })
+    }
+
+    // The version is passed so a proposal can only roll out the wasm it was created for
+    pub fn validate_rollout(version: u64) -> Result<String, String> {
+        let metadata = CHILD_WASM.with(|store| {
+            store
+                .borrow()
+                .staged
+                .as_ref()
+                .map(|staged| staged.metadata.clone())
+        });
+
+        match metadata {
+            Some(_metadata) if _metadata.version == version => {
+                if Rollouts::is_running() {
+                    return Err("A rollout is in progress, halt it first".to_string());
+                }
+
+                let current_bytes = DATA.with(|data| data.borrow().child_wasm_data.bytes.clone());
+                if !current_bytes.is_empty() && hash_bytes(&current_bytes) == _metadata.sha256 {
+                    return Err("The child wasm is the same as the current child wasm".to_string());
+                }
+
+                Ok(format!(
+                    "Roll out child wasm {} (version {}, sha256 {}) to the {} child canisters",
+                    _metadata.label,
+                    _metadata.version,
+                    to_hex(&_metadata.sha256),
+                    DATA.with(|data| data.borrow().canisters.len())
+                ))
+            }
+            _ => Err(format!(
+                "There is no uploaded child wasm with version {}",
+                version
+            )),
+        }
+    }
+
+    // Method to make the staged wasm the current child wasm and start a rollout to the child canisters
+    // the first child canister is spawned when there are no child canisters yet
+    // all the checks are done before the state is changed, so a failed rollout leaves the staged wasm in place
+    pub fn rollout(version: u64) -> Result<ChildWasmMetadata, ApiError> {
+        // The validation checks that no rollout is running, and the staged wasm has a new version
+        // so every child canister is outdated once it is the current wasm
+        Self::validate_rollout(version)
+            .map_err(|err| Self::error("INVALID_ROLLOUT", &err, "rollout"))?;
+
+        let metadata = CHILD_WASM.with(|store| {
+            let mut store = store.borrow_mut();
+            let StagedChildWasm {
+                mut metadata,
+                bytes,
+            } = store.staged.take().expect("validated staged wasm");
+            metadata.rolled_out_at = Some(time());
+
+            DATA.with(|data| {
+                let mut data = data.borrow_mut();
+                data.child_wasm_data = WasmDetails {
+                    label: metadata.label.clone(),
+                    bytes,
+                    wasm_type: CanisterType::ScalableChild,
+                    wasm_version: WasmVersion::Version(metadata.version),
+                    updated_at: time(),
+                    created_at: match data.child_wasm_data.bytes.is_empty() {
+                        true => time(),
+                        false => data.child_wasm_data.created_at,
+                    },
+                };
+            });
+
+            store.history.push(metadata.clone());
+            if store.history.len() > MAX_CHILD_WASM_HISTORY {
+                store.history.remove(0);
+            }
+            metadata
+        });
+
+        add_log(PostLog {
+            log_type: LogType::Info,
+            description: "Child wasm rolled out".to_string(),
+            source: "rollout".to_string(),
+            data: format!("{} - version {}", metadata.label, metadata.version),
+        });
+
+        // The first child canister is installed with the wasm, the other child canisters are upgraded in a staged rollout
+        match DATA.with(|data| data.borrow().canisters.is_empty()) {
+            true => {
+                set_timer(Duration::from_secs(0), || {
+                    ic_cdk::spawn(ScalableData::initialize_first_child_canister());
+                });
+            }
+            false => {
+                Rollouts::begin();
+            }
+        }
+
+        Ok(metadata)
+    }
+
+    // The version increases over the current child wasm (also when it was included in an older parent wasm)
+    fn get_next_version() -> u64 {
+        let current = match DATA.with(|data| data.borrow().child_wasm_data.wasm_version.clone()) {
+            WasmVersion::Version(_version) => _version,
+            _ => 0,
+        };
+
+        CHILD_WASM.with(|store| {
+            let store = store.borrow();
+            store
+                .history
+                .iter()
+                .chain(store.staged.as_ref().map(|staged| &staged.metadata))
+                .map(|metadata| metadata.version)
+                .fold(current, u64::max)
+                + 1
+        })
+    }
+
+    fn get_used_labels() -> Vec<String> {
+        CHILD_WASM.with(|store| {
+            let store = store.borrow();
+            store
+                .history
+                .iter()
+                .chain(store.staged.as_ref().map(|staged| &staged.metadata))
+                .map(|metadata| metadata.label.clone())
+                .collect()
+        })
+    }
+
+    fn error(tag: &str, message: &str, method_name: &str) -> ApiError {
+        api_error(
+            ApiErrorType::BadRequest,
+            tag,
+            message,
+            "ChildWasm",
+            method_name,
+            None,
+        )
+    }
+}
@@ -1,83 +1,126 @@
-use std::time::Duration;
+use std::collections::HashMap;
 
-use ic_cdk::{caller, init, post_upgrade, pre_upgrade, query, storage};
-use ic_cdk_timers::set_timer;
-use ic_scalable_misc::{
-    helpers::logger_helper::add_log,
-    models::logger_models::{LogType, PostLog},
+use candid::Principal;
+use ic_cdk::{api::stable, caller, init, post_upgrade, pre_upgrade, query, storage};
+use ic_stable_structures::{writer::Writer, Memory};
+
+use shared::{
+    capacity_model::CapacitySettings, operator_model::OperatorRoles, rollout_model::RolloutStore,
+    wasm_model::ChildWasmStore,
+};
+
+use super::{
+    roles::Roles,
+    rollout::Rollouts,
+    store::{
+        ScalableData, ATTENDEE_DIRECTORY, CAPACITY_SETTINGS, CHILD_WASM, DATA, MEMORY_MANAGER,
+        OPERATOR_ROLES, ROLLOUT, UPGRADE_STATE_MEMORY_ID,
+    },
 };
 
-use super::store::{ScalableData, DATA};
+// Data that is kept in stable storage during an upgrade
+// the attendee directory is only read from versions that stored it on the heap, it is kept in a stable map now
+// the operator roles, capacity settings and child wasm uploads and rollouts are optional because they arent stored by older versions
+type StableState = (
+    ScalableData,
+    Option<HashMap<Principal, Principal>>,
+    Option<OperatorRoles>,
+    Option<CapacitySettings>,
+    Option<ChildWasmStore>,
+    Option<RolloutStore>,
+);
 
 // Stores the data in stable storage before upgrading the canister.
+// the heap data is written to the upgrade memory, so it doesnt overwrite the stable structures
 #[pre_upgrade]
 pub fn pre_upgrade() {
-    DATA.with(|data| storage::stable_save((&*data.borrow(),)))
+    let bytes = DATA
+        .with(|data| {
+            OPERATOR_ROLES.with(|roles| {
+                CAPACITY_SETTINGS.with(|capacity| {
+                    CHILD_WASM.with(|child_wasm| {
+                        ROLLOUT.with(|rollout| {
+                            candid::encode_args((
+                                &*data.borrow(),
+                                None::<HashMap<Principal, Principal>>,
+                                &*roles.borrow(),
+                                &*capacity.borrow(),
+                                &*child_wasm.borrow(),
+                                &*rollout.borrow(),
+                            ))
+                        })
+                    })
+                })
+            })
+        })
+        .expect("Something went wrong while upgrading");
+
+    let mut memory = MEMORY_MANAGER.with(|m| m.borrow().get(UPGRADE_STATE_MEMORY_ID));
+    let mut writer = Writer::new(&mut memory, 0);
+    writer
+        .write(&(bytes.len() as u64).to_le_bytes())
+        .and_then(|_| writer.write(&bytes))
         .expect("Something went wrong while upgrading");
 }
 
 #[post_upgrade]
 // Restores the data from stable- to heap storage after upgrading the canister.
-pub fn post_upgrade() {
-    let (mut old_store,): (ScalableData,) = storage::stable_restore().unwrap();
-
-    use ic_scalable_misc::enums::wasm_version_type::WasmVersion::*;
-    let version = match old_store.child_wasm_data.wasm_version {
-        Version(_version) => _version + 1,
-        _ => 0,
-    };
-    // Get the child wasm data from the old store
-    let child_wasm_data = ScalableData::get_child_wasm_data(&old_store, version);
-    match child_wasm_data {
-        // If the child wasm data is found, update the data in the new store
-        Ok(_child_wasm_data) => {
-            DATA.with(|d| {
-                old_store.child_wasm_data = _child_wasm_data;
-                *d.borrow_mut() = old_store;
-            });
-
-            add_log(PostLog {
-                log_type: LogType::Info,
-                description: "canister children upgrading".to_string(),
-                source: "post_upgrade".to_string(),
-                data: "".to_string(),
-            });
+// the operator roles of the upgrade arguments replace the stored roles
+// the child canisters are no longer upgraded with the parent, the child wasm is uploaded and rolled out through the child wasm methods
+pub fn post_upgrade(operator_roles: Option<OperatorRoles>) {
+    let (old_store, directory, stored_roles, capacity, child_wasm, rollout) = restore_state();
+    DATA.with(|d| *d.borrow_mut() = old_store);
+    CAPACITY_SETTINGS.with(|c| *c.borrow_mut() = capacity.unwrap_or_default());
+    CHILD_WASM.with(|c| *c.borrow_mut() = child_wasm.unwrap_or_default());
+    ROLLOUT.with(|r| *r.borrow_mut() = rollout.unwrap_or_default());
+    Rollouts::interrupt();
+    if let Some(_operator_roles) = operator_roles {
+        Roles::seed(_operator_roles);
+    } else {
+        OPERATOR_ROLES.with(|r| *r.borrow_mut() = stored_roles.unwrap_or_default());
+    }
 
-            // Use a timer to trigger the upgrade_children method to upgrade the child WASMs
-            set_timer(Duration::from_secs(0), || {
-                ic_cdk::spawn(ScalableData::upgrade_children());
-            });
-        }
-        // If the child wasm data is not found, continue restoring the old store
-        Err(err) => {
-            DATA.with(|d| {
-                *d.borrow_mut() = old_store;
+    // Move the heap directory of an older version to the stable map
+    if let Some(_directory) = directory {
+        ATTENDEE_DIRECTORY.with(|d| {
+            let mut d = d.borrow_mut();
+            _directory.into_iter().for_each(|(principal, canister)| {
+                d.insert(principal.to_string(), canister.to_string());
             });
+        });
+    }
+}
 
-            add_log(PostLog {
-                log_type: LogType::Info,
-                description: "No child upgrade needed".to_string(),
-                source: "post_upgrade".to_string(),
-                data: format!("Error: {}", err),
-            });
-        }
+// Method to read the upgrade state, versions before the stable directory saved it at the start of the stable memory
+// that has to be read before the memory manager is initialized, because the memory manager overwrites it
+fn restore_state() -> StableState {
+    let mut magic = [0u8; 3];
+    if stable::stable64_size() > 0 {
+        stable::stable64_read(0, &mut magic);
+    }
+    if &magic != b"MGR" {
+        return storage::stable_restore().expect("Something went wrong while restoring");
     }
+
+    let memory = MEMORY_MANAGER.with(|m| m.borrow().get(UPGRADE_STATE_MEMORY_ID));
+    let mut length = [0u8; 8];
+    memory.read(0, &mut length);
+    let mut bytes = vec![0u8; u64::from_le_bytes(length) as usize];
+    memory.read(8, &mut bytes);
+    candid::decode_args(&bytes).expect("Something went wrong while restoring")
 }
 
 // Init methods thats get triggered when the canister is installed
+// The operator roles are passed to the child canisters on install and upgrade
+// the first child canister is spawned when the first child wasm is rolled out
 #[init]
-fn init() {
+fn init(operator_roles: Option<OperatorRoles>) {
+    Roles::seed(operator_roles.unwrap_or_default());
+
     DATA.with(|v| {
         let mut data = v.borrow_mut();
         data.name = "event_attendees_parent".to_string();
         data.parent = caller();
-        // Set the child WASM data on first deploy from the file system
-        data.child_wasm_data = ScalableData::get_child_wasm_data(&data, 0_0_1).unwrap();
-    });
-
-    // Spawn the first child canister
-    set_timer(Duration::from_secs(0), || {
-        ic_cdk::spawn(ScalableData::initialize_first_child_canister());
     });
 }
 
@@ -94,6 +137,13 @@ pub fn __export_did_tmp_() -> String {
     use ic_scalable_misc::models::http_models::HttpRequest;
     use ic_scalable_misc::models::paged_response_models::PagedResponse;
     use shared::attendee_model::*;
+    use shared::capacity_model::*;
+    use shared::chunk_model::*;
+    use shared::export_model::*;
+    use shared::icrc3_model::*;
+    use shared::operator_model::*;
+    use shared::rollout_model::*;
+    use shared::wasm_model::*;
     export_service!();
     __export_service()
 }
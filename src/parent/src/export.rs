@@ -0,0 +1,265 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
+
+use candid::Principal;
+use ic_cdk::api::{management_canister::main::raw_rand, time};
+use ic_scalable_misc::{
+    enums::api_error_type::{ApiError, ApiErrorType},
+    helpers::error_helper::api_error,
+};
+
+use shared::{
+    chunk_helper::{get_chunk, hash_bytes},
+    chunk_model::ChunkedData,
+    export_helper::{export_rows, sign_download_token, verify_download_token},
+    export_model::{EventExport, ExportFormat, ExportRequest},
+};
+
+use crate::store::ScalableData;
+
+// Exports can be read and downloaded for 15 minutes, in nanoseconds
+const EXPORT_TTL_NANOS: u64 = 15 * 60 * 1_000_000_000;
+// Max size of a chunk, keeps the http responses below the response limit
+const MAX_BYTES_PER_CHUNK: usize = 1_500_000;
+// Max number of exports that are kept at the same time, the oldest export is removed first
+const MAX_STORED_EXPORTS: usize = 20;
+// Max size of all the exports that are kept at the same time, the oldest export is removed first
+const MAX_STORED_EXPORT_BYTES: usize = 100_000_000;
+// Max number of exports that are kept per principal, the oldest export of the principal is removed first
+const MAX_EXPORTS_PER_OWNER: usize = 3;
+
+thread_local! {
+    // Secret used to sign the download tokens, created on the first export
+    // kept on the heap so the tokens (and the exports) are invalidated by an upgrade
+    static EXPORT_SECRET: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+    static EXPORTS: RefCell<HashMap<u64, StoredExport>> = RefCell::new(HashMap::new());
+    static NEXT_EXPORT_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+struct StoredExport {
+    owner: Principal,
+    export: EventExport,
+    bytes: Vec<u8>,
+}
+
+pub struct Exports;
+
+impl Exports {
+    // Method to export the joins and invites of an event with the check-ins from all the child canisters
+    // only the joins and invites of the requested group are exported, so the permission check covers the exported data
+    pub async fn create_export(
+        caller: Principal,
+        request: ExportRequest,
+    ) -> Result<EventExport, ApiError> {
+        ScalableData::can_read(caller, request.group_identifier, request.member_identifier).await?;
+        let secret = Self::get_secret().await?;
+
+        let (mut rows, failed_canisters) =
+            ScalableData::get_event_export_rows(request.event_identifier).await;
+        rows.retain(|row| row.group_identifier == request.group_identifier);
+
+        let bytes = export_rows(&rows, request.format).map_err(|err| {
+            api_error(
+                ApiErrorType::BadRequest,
+                "EXPORT_FAILED",
+                &err,
+                "Exports",
+                "create_export",
+                None,
+            )
+        })?;
+
+        if bytes.len() > MAX_STORED_EXPORT_BYTES {
+            return Err(api_error(
+                ApiErrorType::BadRequest,
+                "EXPORT_TOO_LARGE",
+                &format!(
+                    "The export is {} bytes, the max is {} bytes",
+                    bytes.len(),
+                    MAX_STORED_EXPORT_BYTES
+                ),
+                "Exports",
+                "create_export",
+                None,
+            ));
+        }
+
+        let now = time();
+        let export_id = NEXT_EXPORT_ID.with(|id| id.replace(id.get() + 1));
+        let expires_at = now + EXPORT_TTL_NANOS;
+        let export = EventExport {
+            export_id,
+            event_identifier: request.event_identifier,
+            format: request.format,
+            total_rows: rows.len(),
+            total_bytes: bytes.len(),
+            total_chunks: bytes.len().div_ceil(MAX_BYTES_PER_CHUNK).max(1),
+            hash: hash_bytes(&bytes),
+            download_token: sign_download_token(&secret, export_id, expires_at),
+            expires_at,
+            failed_canisters,
+        };
+
+        EXPORTS.with(|exports| {
+            let mut exports = exports.borrow_mut();
+            exports.retain(|_, stored| stored.export.expires_at > now);
+
+            // The export ids are increasing, so the lowest id is the oldest export
+            while exports
+                .values()
+                .filter(|stored| stored.owner == caller)
+                .count()
+                >= MAX_EXPORTS_PER_OWNER
+            {
+                match exports
+                    .iter()
+                    .filter(|(_, stored)| stored.owner == caller)
+                    .map(|(id, _)| *id)
+                    .min()
+                {
+                    None => break,
+                    Some(_oldest) => exports.remove(&_oldest),
+                };
+            }
+
+            while exports.len() >= MAX_STORED_EXPORTS
+                || exports
+                    .values()
+                    .map(|stored| stored.bytes.len())
+                    .sum::<usize>()
+                    + bytes.len()
+                    > MAX_STORED_EXPORT_BYTES
+            {
+                match exports.keys().min().copied() {
+                    None => break,
+                    Some(_oldest) => exports.remove(&_oldest),
+                };
+            }
+            exports.insert(
+                export_id,
+                StoredExport {
+                    owner: caller,
+                    export: export.clone(),
+                    bytes,
+                },
+            );
+        });
+
+        Ok(export)
+    }
+
+    // Method to get a chunk of an export, can only be read by the principal that created the export
+    pub fn get_export_chunk(
+        caller: Principal,
+        export_id: u64,
+        chunk: usize,
+    ) -> Result<ChunkedData, ApiError> {
+        Self::with_export(export_id, "get_export_chunk", |stored| {
+            if stored.owner != caller {
+                return Err(api_error(
+                    ApiErrorType::Unauthorized,
+                    "NOT_EXPORT_OWNER",
+                    "The export is created by another principal",
+                    "Exports",
+                    "get_export_chunk",
+                    None,
+                ));
+            }
+            Self::chunk(stored, chunk, "get_export_chunk")
+        })
+    }
+
+    // Method to get a chunk of an export with a download token, used by the http download route
+    pub fn download_export_chunk(
+        export_id: u64,
+        token: &str,
+        chunk: usize,
+    ) -> Result<(ExportFormat, ChunkedData), ApiError> {
+        let is_valid = EXPORT_SECRET.with(|secret| match secret.borrow().as_ref() {
+            None => false,
+            Some(_secret) => verify_download_token(_secret, export_id, token, time()),
+        });
+
+        if !is_valid {
+            return Err(api_error(
+                ApiErrorType::Unauthorized,
+                "INVALID_DOWNLOAD_TOKEN",
+                "The download token is invalid or expired",
+                "Exports",
+                "download_export_chunk",
+                None,
+            ));
+        }
+
+        Self::with_export(export_id, "download_export_chunk", |stored| {
+            Ok((
+                stored.export.format,
+                Self::chunk(stored, chunk, "download_export_chunk")?,
+            ))
+        })
+    }
+
+    fn with_export<T>(
+        export_id: u64,
+        method_name: &str,
+        f: impl FnOnce(&StoredExport) -> Result<T, ApiError>,
+    ) -> Result<T, ApiError> {
+        EXPORTS.with(|exports| match exports.borrow().get(&export_id) {
+            Some(_stored) if _stored.export.expires_at > time() => f(_stored),
+            _ => Err(api_error(
+                ApiErrorType::NotFound,
+                "EXPORT_NOT_FOUND",
+                "The export does not exist or is expired",
+                "Exports",
+                method_name,
+                None,
+            )),
+        })
+    }
+
+    fn chunk(
+        stored: &StoredExport,
+        chunk: usize,
+        method_name: &str,
+    ) -> Result<ChunkedData, ApiError> {
+        get_chunk(
+            &stored.bytes,
+            chunk,
+            MAX_BYTES_PER_CHUNK,
+            stored.export.export_id,
+        )
+        .map_err(|err| {
+            api_error(
+                ApiErrorType::BadRequest,
+                "INVALID_CHUNK",
+                &err,
+                "Exports",
+                method_name,
+                None,
+            )
+        })
+    }
+
+    async fn get_secret() -> Result<Vec<u8>, ApiError> {
+        if let Some(_secret) = EXPORT_SECRET.with(|secret| secret.borrow().clone()) {
+            return Ok(_secret);
+        }
+
+        match raw_rand().await {
+            Err(err) => Err(api_error(
+                ApiErrorType::BadRequest,
+                "RANDOMNESS_UNAVAILABLE",
+                &format!("{:?} - {}", err.0, err.1),
+                "Exports",
+                "get_secret",
+                None,
+            )),
+            // Another export could have set the secret while waiting for the randomness
+            Ok((_bytes,)) => {
+                Ok(EXPORT_SECRET.with(|secret| secret.borrow_mut().get_or_insert(_bytes).clone()))
+            }
+        }
+    }
+}
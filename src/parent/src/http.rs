@@ -0,0 +1,115 @@
+use ic_scalable_misc::{
+    helpers::paging_helper::get_paged_data,
+    models::http_models::{HeaderField, HttpRequest, HttpResponse},
+};
+
+use shared::{
+    export_helper::{content_type, file_extension},
+    http_helper::{
+        api_error_response, error_response, json_response, method_not_allowed_response, HttpRoute,
+    },
+};
+
+use crate::{export::Exports, store::ScalableData};
+
+pub struct Http;
+
+impl Http {
+    // Method to handle the read-only JSON routes, the data is aggregated over all the child canisters
+    // returns none for the paths that are not part of the api
+    // requires composite queries to be released to mainnet
+    pub async fn handle(req: &HttpRequest) -> Option<HttpResponse> {
+        let route = HttpRoute::parse(req);
+        if !matches!(
+            route.path.first().map(|segment| segment.as_str()),
+            Some("events") | Some("attendees") | Some("exports")
+        ) {
+            return None;
+        }
+
+        if route.method != "GET" {
+            return Some(method_not_allowed_response());
+        }
+
+        let response = if route.matches(&["events", ":event_identifier", "attendees"]) {
+            Self::get_event_attendees(&route).await
+        } else if route.matches(&["events", ":event_identifier", "count"]) {
+            Self::get_event_count(&route).await
+        } else if route.matches(&["attendees", ":principal"]) {
+            Self::get_attendee(&route).await
+        } else if route.matches(&["exports", ":export_id"]) {
+            Self::download_export(&route)
+        } else {
+            Err(error_response(404, "Route not found"))
+        };
+
+        Some(response.unwrap_or_else(|err| err))
+    }
+
+    // GET /events/{event_identifier}/attendees?limit=&cursor=
+    async fn get_event_attendees(route: &HttpRoute) -> Result<HttpResponse, HttpResponse> {
+        let event_identifier = route.principal(1)?;
+        let page = ScalableData::get_joined_child_canister_page_v2(
+            event_identifier,
+            route.limit()?,
+            route.cursor(),
+        )
+        .await;
+        Ok(json_response(200, &page))
+    }
+
+    // GET /events/{event_identifier}/count
+    async fn get_event_count(route: &HttpRoute) -> Result<HttpResponse, HttpResponse> {
+        let event_identifier = route.principal(1)?;
+        Ok(json_response(
+            200,
+            &ScalableData::get_event_count(event_identifier).await,
+        ))
+    }
+
+    // GET /attendees/{principal}?limit=&page=
+    async fn get_attendee(route: &HttpRoute) -> Result<HttpResponse, HttpResponse> {
+        let principal = route.principal(1)?;
+        let (limit, page) = (route.limit()?, route.page()?);
+        match ScalableData::get_attending_anywhere_v2(principal).await {
+            Ok(_joined) => Ok(json_response(200, &get_paged_data(_joined, limit, page))),
+            Err(err) => Err(api_error_response(&err)),
+        }
+    }
+
+    // GET /exports/{export_id}?token=&chunk=
+    fn download_export(route: &HttpRoute) -> Result<HttpResponse, HttpResponse> {
+        let export_id = route.path[1]
+            .parse::<u64>()
+            .map_err(|_| error_response(400, "Invalid export id in the path"))?;
+        let chunk = match route.query.get("chunk") {
+            None => 0,
+            Some(_chunk) => _chunk
+                .parse::<usize>()
+                .map_err(|_| error_response(400, "The chunk should be a number"))?,
+        };
+        let token = route.query.get("token").cloned().unwrap_or_default();
+
+        let (format, chunk) = Exports::download_export_chunk(export_id, &token, chunk)
+            .map_err(|err| api_error_response(&err))?;
+
+        Ok(HttpResponse {
+            status_code: 200,
+            headers: vec![
+                HeaderField("content-type".to_string(), content_type(format).to_string()),
+                HeaderField(
+                    "content-disposition".to_string(),
+                    format!(
+                        "attachment; filename=\"export-{}-{}.{}\"",
+                        export_id,
+                        chunk.chunk,
+                        file_extension(format)
+                    ),
+                ),
+                HeaderField("x-total-chunks".to_string(), chunk.total_chunks.to_string()),
+                HeaderField("cache-control".to_string(), "no-store".to_string()),
+            ],
+            body: chunk.bytes,
+        })
+    }
+}
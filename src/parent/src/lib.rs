@@ -1,4 +1,11 @@
+pub mod child_wasm;
 pub mod default;
+pub mod export;
+pub mod http;
 pub mod methods;
+pub mod roles;
+pub mod rollout;
 pub mod scalable_methods;
+pub mod sns;
+pub mod sns_methods;
 pub mod store;
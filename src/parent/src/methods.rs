@@ -1,12 +1,31 @@
 use candid::Principal;
-use ic_cdk::query;
-use ic_scalable_misc::models::paged_response_models::PagedResponse;
+use ic_cdk::{caller, query, update};
+use ic_scalable_misc::{
+    enums::{api_error_type::ApiError, filter_type::FilterType},
+    models::paged_response_models::PagedResponse,
+};
 
-use shared::attendee_model::{InviteAttendeeResponse, JoinedAttendeeResponse};
+use shared::attendee_model::{
+    Attendee, AttendeeListRequest, AttendeeSort, DeduplicationReport, GroupAttendee,
+    GroupAttendeeRequest, GroupEventAttendance, InviteAttendeeResponse, InviteAttendeeResponseV2,
+    JoinedAttendeeResponse, JoinedAttendeeResponseV2, MergedCursorPagedResponse,
+    MergedPagedResponse,
+};
+use shared::chunk_model::ChunkedData;
+use shared::export_model::{EventExport, ExportRequest};
+use shared::icrc3_model::AttendanceLog;
+use shared::operator_model::{OperatorRole, OperatorRoles, OperatorRolesUpdate};
+use shared::rollout_model::{Rollout, RolloutProgress, RolloutSettings};
+use shared::wasm_model::{
+    ChildWasmMetadata, ChildWasmStatus, ChildWasmUploadProgress, ChildWasmUploadRequest,
+};
 
-use super::store::ScalableData;
+use super::{
+    child_wasm::ChildWasm, export::Exports, roles::Roles, rollout::Rollouts, store::ScalableData,
+};
 
-// Method used to get all the members from the child canisters filtered, sorted and paged
+// Method used to get all the members of an event from the child canisters paged
+// kept for the existing clients, the child canisters that failed are left out, use get_members_filtered to get them
 // requires composite queries to be released to mainnet
 #[query(composite = true)]
 async fn get_members(
@@ -14,10 +33,13 @@ async fn get_members(
     limit: usize,
     page: usize,
 ) -> PagedResponse<JoinedAttendeeResponse> {
-    ScalableData::get_joined_child_canister_data(group_identifier, limit, page).await
+    ScalableData::get_joined_child_canister_data(unfiltered_request(group_identifier, limit, page))
+        .await
+        .page
 }
 
-// Method used to get all the members from the child canisters filtered, sorted and paged
+// Method used to get all the invites of an event from the child canisters paged
+// kept for the existing clients, the child canisters that failed are left out, use get_invites_filtered to get them
 // requires composite queries to be released to mainnet
 #[query(composite = true)]
 async fn get_invites(
@@ -25,5 +47,314 @@ async fn get_invites(
     limit: usize,
     page: usize,
 ) -> PagedResponse<InviteAttendeeResponse> {
-    ScalableData::get_invites_child_canister_data(group_identifier, limit, page).await
+    ScalableData::get_invites_child_canister_data(unfiltered_request(group_identifier, limit, page))
+        .await
+        .page
+}
+
+// Method used to get all the members from the child canisters filtered, sorted and paged
+// requires composite queries to be released to mainnet
+#[query(composite = true)]
+async fn get_members_filtered(
+    request: AttendeeListRequest,
+) -> MergedPagedResponse<JoinedAttendeeResponse> {
+    ScalableData::get_joined_child_canister_data(request).await
+}
+
+// Method used to get all the invites from the child canisters filtered, sorted and paged
+// requires composite queries to be released to mainnet
+#[query(composite = true)]
+async fn get_invites_filtered(
+    request: AttendeeListRequest,
+) -> MergedPagedResponse<InviteAttendeeResponse> {
+    ScalableData::get_invites_child_canister_data(request).await
+}
+
+// The identifier of the old methods is the event identifier
+fn unfiltered_request(
+    event_identifier: Principal,
+    limit: usize,
+    page: usize,
+) -> AttendeeListRequest {
+    AttendeeListRequest {
+        event_identifier,
+        filters: vec![],
+        filter_type: FilterType::default(),
+        sort: AttendeeSort::default(),
+        limit,
+        page,
+    }
+}
+
+// Method used to get a page of the members of an event from the child canisters, ordered by principal
+// pass the next_cursor of the previous response to get the next page
+// requires composite queries to be released to mainnet
+#[query(composite = true)]
+async fn get_members_page(
+    event_identifier: Principal,
+    limit: usize,
+    cursor: Option<String>,
+) -> MergedCursorPagedResponse<JoinedAttendeeResponse> {
+    ScalableData::get_joined_child_canister_page(event_identifier, limit, cursor).await
+}
+
+// Method used to get a page of the invites of an event from the child canisters, ordered by principal
+// pass the next_cursor of the previous response to get the next page
+// the caller needs read permission on the attendees of the group
+#[update]
+async fn get_invites_page(
+    event_identifier: Principal,
+    group_identifier: Principal,
+    member_identifier: Principal,
+    limit: usize,
+    cursor: Option<String>,
+) -> Result<MergedCursorPagedResponse<InviteAttendeeResponse>, ApiError> {
+    ScalableData::can_read(caller(), group_identifier, member_identifier).await?;
+    Ok(ScalableData::get_invites_child_canister_page(event_identifier, limit, cursor).await)
+}
+
+// Method used to get all the members from the child canisters filtered, sorted and paged
+// with the group, status and timestamps of the join
+// requires composite queries to be released to mainnet
+#[query(composite = true)]
+async fn get_members_v2(
+    request: AttendeeListRequest,
+) -> MergedPagedResponse<JoinedAttendeeResponseV2> {
+    ScalableData::get_joined_child_canister_data_v2(request).await
+}
+
+// Method used to get all the invites from the child canisters filtered, sorted and paged
+// with the group, status and timestamps of the invite
+// requires composite queries to be released to mainnet
+#[query(composite = true)]
+async fn get_invites_v2(
+    request: AttendeeListRequest,
+) -> MergedPagedResponse<InviteAttendeeResponseV2> {
+    ScalableData::get_invites_child_canister_data_v2(request).await
+}
+
+// Method used to get a page of the members of an event from the child canisters, ordered by principal
+// with the group, status and timestamps of the join
+// requires composite queries to be released to mainnet
+#[query(composite = true)]
+async fn get_members_page_v2(
+    event_identifier: Principal,
+    limit: usize,
+    cursor: Option<String>,
+) -> MergedCursorPagedResponse<JoinedAttendeeResponseV2> {
+    ScalableData::get_joined_child_canister_page_v2(event_identifier, limit, cursor).await
+}
+
+// Method used to get a page of the invites of an event from the child canisters, ordered by principal
+// with the group, status and timestamps of the invite
+// the caller needs read permission on the attendees of the group
+#[update]
+async fn get_invites_page_v2(
+    event_identifier: Principal,
+    group_identifier: Principal,
+    member_identifier: Principal,
+    limit: usize,
+    cursor: Option<String>,
+) -> Result<MergedCursorPagedResponse<InviteAttendeeResponseV2>, ApiError> {
+    ScalableData::can_read(caller(), group_identifier, member_identifier).await?;
+    Ok(ScalableData::get_invites_child_canister_page_v2(event_identifier, limit, cursor).await)
+}
+
+// Method used to get the participation of the attendees in all the events of a group, ordered by principal
+// the total of the page is the number of distinct attendees of the group
+// the caller needs read permission on the attendees of the group
+#[update]
+async fn get_group_attendees(
+    request: GroupAttendeeRequest,
+) -> Result<MergedPagedResponse<GroupAttendee>, ApiError> {
+    ScalableData::get_group_attendees(caller(), request).await
+}
+
+// Method used to get the number of attendees and invites per event of a group, ordered by event
+// the caller needs read permission on the attendees of the group
+#[update]
+async fn get_group_event_attendance(
+    request: GroupAttendeeRequest,
+) -> Result<MergedPagedResponse<GroupEventAttendance>, ApiError> {
+    ScalableData::get_group_event_attendance(caller(), request).await
+}
+
+// Method used to list the ICRC-3 attendance logs of all the child canisters with their length
+// requires composite queries to be released to mainnet
+#[query(composite = true)]
+async fn get_attendance_logs() -> Vec<AttendanceLog> {
+    ScalableData::get_attendance_logs().await
+}
+
+// Method called by the child canisters when new attendee records are created (inter-canister call)
+// can only be called by a child canister
+#[update]
+fn register_attendee_canisters(principals: Vec<Principal>) -> Result<(), ApiError> {
+    ScalableData::register_attendee_canisters(caller(), principals)
+}
+
+// Method called by a child canister before it creates a new attendee record (inter-canister call)
+// returns the child canister that holds (or will hold) the record of the principal
+// can only be called by a child canister
+#[update]
+fn claim_attendee_canister(principal: Principal) -> Result<Principal, ApiError> {
+    ScalableData::claim_attendee_canister(caller(), principal)
+}
+
+// Method to get the child canister that holds the attendee record of a principal
+#[query]
+fn get_attendee_canister(principal: Principal) -> Result<Principal, ApiError> {
+    ScalableData::get_attendee_canister(principal)
+}
+
+// Method to get the caller his joined events and invites from the child canister that holds them
+// requires composite queries to be released to mainnet
+#[query(composite = true)]
+async fn get_self_anywhere() -> Result<(Principal, Attendee), ApiError> {
+    ScalableData::get_attendee_anywhere(caller()).await
+}
+
+// One-time migration to remove attendee records that are duplicated across the child canisters
+// can only be called by a controller
+#[update(guard = "is_controller")]
+async fn deduplicate_attendees() -> DeduplicationReport {
+    ScalableData::deduplicate_attendees().await
+}
+
+// Method to export the attendees and invites of an event with the check-ins as CSV or JSON
+// the caller needs read permission on the attendees of the group, the export can be read for 15 minutes
+#[update(guard = "auth")]
+async fn export_event_attendees(request: ExportRequest) -> Result<EventExport, ApiError> {
+    Exports::create_export(caller(), request).await
+}
+
+// Method to get a chunk of an export created by the caller
+#[query(guard = "auth")]
+fn get_export_chunk(export_id: u64, chunk: usize) -> Result<ChunkedData, ApiError> {
+    Exports::get_export_chunk(caller(), export_id, chunk)
+}
+
+// Method to get the owner and monitor principals of the child canisters
+#[query]
+fn get_operator_roles() -> OperatorRoles {
+    Roles::get()
+}
+
+// Method to give a principal the owner or monitor role on all the child canisters
+#[update(guard = "is_role_manager")]
+async fn add_operator(
+    role: OperatorRole,
+    principal: Principal,
+) -> Result<OperatorRolesUpdate, ApiError> {
+    Roles::add_operator(role, principal).await
+}
+
+// Method to take the owner or monitor role of a principal on all the child canisters
+#[update(guard = "is_role_manager")]
+async fn remove_operator(
+    role: OperatorRole,
+    principal: Principal,
+) -> Result<OperatorRolesUpdate, ApiError> {
+    Roles::remove_operator(role, principal).await
+}
+
+// Method to set the SNS governance canister that can manage the roles next to the controllers
+#[update(guard = "is_role_manager")]
+async fn set_sns_governance(
+    sns_governance: Option<Principal>,
+) -> Result<OperatorRolesUpdate, ApiError> {
+    Roles::set_sns_governance(sns_governance).await
+}
+
+// Method to send the operator roles to all the child canisters again, used when the propagation failed for a child canister
+#[update(guard = "is_role_manager")]
+async fn propagate_operator_roles() -> OperatorRolesUpdate {
+    Roles::propagate().await
+}
+
+// Method to get the current, uploaded and rolled out versions of the child wasm
+#[query]
+fn get_child_wasm_status() -> ChildWasmStatus {
+    ChildWasm::get_status()
+}
+
+// Method to start a child wasm upload with the version label, SHA-256 hash and size of the (gzipped) wasm
+// replaces an upload that is in progress
+#[update(guard = "is_controller")]
+fn start_child_wasm_upload(
+    request: ChildWasmUploadRequest,
+) -> Result<ChildWasmUploadProgress, ApiError> {
+    ChildWasm::start_upload(caller(), request)
+}
+
+// Method to upload the next chunk of the child wasm, the chunks are uploaded in order starting at 0
+#[update(guard = "is_controller")]
+fn upload_child_wasm_chunk(
+    chunk: u64,
+    bytes: Vec<u8>,
+) -> Result<ChildWasmUploadProgress, ApiError> {
+    ChildWasm::upload_chunk(chunk, bytes)
+}
+
+// Method to verify the uploaded child wasm against the hash and store it with the next version
+#[update(guard = "is_controller")]
+fn finalize_child_wasm_upload() -> Result<ChildWasmMetadata, ApiError> {
+    ChildWasm::finalize_upload()
+}
+
+// Method to roll out the uploaded child wasm to the child canisters
+#[update(guard = "is_controller")]
+fn rollout_child_wasm(version: u64) -> Result<ChildWasmMetadata, ApiError> {
+    ChildWasm::rollout(version)
+}
+
+// Method to get the progress of the last rollout and the child wasm version of every child canister
+#[query]
+fn get_rollout_progress() -> RolloutProgress {
+    Rollouts::get_progress()
+}
+
+// Method to set the canary percentage, batch size and batch delay of the next rollout
+#[update(guard = "is_controller")]
+fn set_rollout_settings(settings: RolloutSettings) -> Result<RolloutSettings, ApiError> {
+    Rollouts::set_settings(settings)
+}
+
+// Method to start a staged rollout of the current child wasm to the child canisters that dont run it
+#[update(guard = "is_controller")]
+fn start_rollout() -> Result<Rollout, ApiError> {
+    Rollouts::start()
+}
+
+// Method to halt the running rollout, a batch that is being upgraded is finished first
+#[update(guard = "is_controller")]
+fn halt_rollout() -> Result<Rollout, ApiError> {
+    Rollouts::halt(format!("Halted by {}", caller()))
+}
+
+// Method to resume a halted rollout, the batch that failed is upgraded and checked again
+#[update(guard = "is_controller")]
+fn resume_rollout() -> Result<Rollout, ApiError> {
+    Rollouts::resume()
+}
+
+pub fn is_controller() -> Result<(), String> {
+    match ic_cdk::api::is_controller(&caller()) {
+        true => Ok(()),
+        false => Err("Unauthorized".to_string()),
+    }
+}
+
+pub fn is_role_manager() -> Result<(), String> {
+    match Roles::can_manage(&caller()) {
+        true => Ok(()),
+        false => Err("Unauthorized".to_string()),
+    }
+}
+
+pub fn auth() -> Result<(), String> {
+    match caller() == Principal::anonymous() {
+        true => Err("Unauthorized".to_string()),
+        false => Ok(()),
+    }
 }
@@ -0,0 +1,110 @@
+use candid::Principal;
+use ic_cdk::api::time;
+use ic_scalable_misc::{
+    enums::api_error_type::{ApiError, ApiErrorType},
+    helpers::error_helper::api_error,
+};
+
+use shared::{
+    operator_helper::{add_operator, is_controller_or_sns_governance, remove_operator},
+    operator_model::{OperatorRole, OperatorRoles, OperatorRolesUpdate},
+};
+
+use crate::store::{ScalableData, OPERATOR_ROLES};
+
+pub struct Roles;
+
+impl Roles {
+    pub fn get() -> OperatorRoles {
+        OPERATOR_ROLES.with(|roles| roles.borrow().clone())
+    }
+
+    // Method to seed the roles from the init or upgrade arguments, the new timestamp makes the children accept them
+    pub fn seed(mut roles: OperatorRoles) {
+        roles.updated_at = time();
+        OPERATOR_ROLES.with(|stored| *stored.borrow_mut() = roles);
+    }
+
+    // Method to check if the principal can manage the roles
+    pub fn can_manage(principal: &Principal) -> bool {
+        OPERATOR_ROLES.with(|roles| {
+            is_controller_or_sns_governance(
+                &roles.borrow(),
+                principal,
+                ic_cdk::api::is_controller(principal),
+            )
+        })
+    }
+
+    // Method to give a principal a role on all the child canisters
+    pub async fn add_operator(
+        role: OperatorRole,
+        principal: Principal,
+    ) -> Result<OperatorRolesUpdate, ApiError> {
+        Self::update(|roles| {
+            add_operator(roles, role, principal).map_err(|err| {
+                api_error(
+                    ApiErrorType::BadRequest,
+                    "INVALID_OPERATOR",
+                    &err,
+                    "Roles",
+                    "add_operator",
+                    None,
+                )
+            })
+        })
+        .await
+    }
+
+    // Method to take a role of a principal on all the child canisters
+    pub async fn remove_operator(
+        role: OperatorRole,
+        principal: Principal,
+    ) -> Result<OperatorRolesUpdate, ApiError> {
+        Self::update(|roles| match remove_operator(roles, role, &principal) {
+            true => Ok(()),
+            false => Err(api_error(
+                ApiErrorType::NotFound,
+                "OPERATOR_NOT_FOUND",
+                "The principal doesnt have this role",
+                "Roles",
+                "remove_operator",
+                None,
+            )),
+        })
+        .await
+    }
+
+    // Method to set the SNS governance canister that can manage the roles next to the controllers
+    pub async fn set_sns_governance(
+        sns_governance: Option<Principal>,
+    ) -> Result<OperatorRolesUpdate, ApiError> {
+        Self::update(|roles| {
+            roles.sns_governance = sns_governance;
+            Ok(())
+        })
+        .await
+    }
+
+    // Method to send the current roles to all the child canisters, used to retry the failed child canisters
+    pub async fn propagate() -> OperatorRolesUpdate {
+        let roles = Self::get();
+        OperatorRolesUpdate {
+            failed_canisters: ScalableData::propagate_operator_roles(roles.clone()).await,
+            roles,
+        }
+    }
+
+    // Method to change the roles and propagate them to the child canisters
+    // the changes are stored before the propagation, so a failed child canister doesnt undo the change
+    async fn update(
+        f: impl FnOnce(&mut OperatorRoles) -> Result<(), ApiError>,
+    ) -> Result<OperatorRolesUpdate, ApiError> {
+        let mut roles = Self::get();
+        f(&mut roles)?;
+        roles.updated_at = time();
+        OPERATOR_ROLES.with(|stored| *stored.borrow_mut() = roles);
+
+        Ok(Self::propagate().await)
+    }
+}
@@ -0,0 +1,464 @@
+use std::{cell::Cell, time::Duration};
+
+use candid::Principal;
+use futures::channel::oneshot;
+use ic_cdk::api::{call, time};
+use ic_cdk_timers::set_timer;
+use ic_scalable_misc::{
+    enums::api_error_type::{ApiError, ApiErrorType},
+    helpers::{error_helper::api_error, logger_helper::add_log},
+    models::logger_models::{LogType, PostLog},
+};
+
+use shared::{
+    lock_helper::LockGuard,
+    metrics_helper::record_call_failure,
+    operator_model::OperatorRoles,
+    rollout_helper::{plan_rollout, validate_rollout_settings},
+    rollout_model::{
+        ChildRolloutStatus, ChildWasmVersion, Rollout, RolloutProgress, RolloutSettings,
+        RolloutStatus,
+    },
+};
+
+use crate::store::{ScalableData, DATA, ROLLOUT};
+
+thread_local! {
+    // Set while a batch is being upgraded, so a halted rollout can't be resumed or replaced until the batch is finished
+    static BATCH_IN_FLIGHT: Cell<bool> = const { Cell::new(false) };
+}
+
+// Number of times the certification of an upgraded child canister is checked before the health check fails,
+// one check is added for every `ATTENDEES_PER_CERTIFICATION_CHECK` attendees of the child canister
+const MIN_CERTIFICATION_CHECKS: u64 = 5;
+// Matches the number of attendees the child canister certifies per rebuild slice
+const ATTENDEES_PER_CERTIFICATION_CHECK: u64 = 1_000;
+// Delay between the certification checks, the rebuild slices of the child canister run in between
+const CERTIFICATION_CHECK_DELAY_SECONDS: u64 = 2;
+
+// Staged rollout of the child wasm, the child canisters are upgraded in batches starting with a canary batch
+// every upgraded child canister is health checked and the rollout halts on the first failure
+pub struct Rollouts;
+
+impl Rollouts {
+    pub fn get_progress() -> RolloutProgress {
+        let (wasm_version, mut children) = DATA.with(|data| {
+            let data = data.borrow();
+            (
+                data.child_wasm_data.wasm_version.clone(),
+                data.canisters
+                    .values()
+                    .map(|canister| ChildWasmVersion {
+                        canister_principal: canister.principal,
+                        wasm_version: canister.wasm_version.clone(),
+                    })
+                    .collect::<Vec<ChildWasmVersion>>(),
+            )
+        });
+        children.sort_by_key(|child| child.canister_principal);
+
+        ROLLOUT.with(|store| {
+            let store = store.borrow();
+            RolloutProgress {
+                wasm_version,
+                settings: store.settings.clone(),
+                rollout: store.rollout.clone(),
+                children,
+            }
+        })
+    }
+
+    pub fn validate_settings(settings: &RolloutSettings) -> Result<String, String> {
+        validate_rollout_settings(settings)?;
+        Ok(format!(
+            "Roll out the child wasm to {}% of the child canisters first, then in batches of {} with {} seconds between the batches",
+            settings.canary_percentage, settings.batch_size, settings.batch_delay_seconds
+        ))
+    }
+
+    // The settings are used for the next rollout, a running rollout keeps its settings
+    pub fn set_settings(settings: RolloutSettings) -> Result<RolloutSettings, ApiError> {
+        Self::validate_settings(&settings)
+            .map_err(|err| Self::error("INVALID_SETTINGS", &err, "set_settings"))?;
+        ROLLOUT.with(|store| store.borrow_mut().settings = settings.clone());
+        Ok(settings)
+    }
+
+    pub fn validate_start() -> Result<String, String> {
+        Self::check_not_running()?;
+        let (wasm_version, outdated, total) = DATA.with(|data| {
+            let data = data.borrow();
+            (
+                data.child_wasm_data.wasm_version.clone(),
+                Self::get_outdated_canisters().len(),
+                data.canisters.len(),
+            )
+        });
+
+        match outdated {
+            0 => Err("All the child canisters are up to date".to_string()),
+            _ => Ok(format!(
+                "Upgrade {} of the {} child canisters to child wasm {:?}",
+                outdated, total, wasm_version
+            )),
+        }
+    }
+
+    // Method to start a rollout of the current child wasm to the child canisters that run an other version
+    // replaces the last rollout when it is halted or completed
+    pub fn start() -> Result<Rollout, ApiError> {
+        Self::validate_start().map_err(|err| Self::error("INVALID_ROLLOUT", &err, "start"))?;
+        Ok(Self::begin())
+    }
+
+    // Method to start a rollout without validation, the caller checks that no rollout is running
+    // and that there are outdated child canisters
+    pub fn begin() -> Rollout {
+        let settings = ROLLOUT.with(|store| store.borrow().settings.clone());
+        let (children, total_batches) =
+            plan_rollout(Self::get_outdated_canisters(), &settings, time());
+        let rollout = Rollout {
+            wasm_version: DATA.with(|data| data.borrow().child_wasm_data.wasm_version.clone()),
+            settings,
+            status: RolloutStatus::Running,
+            total_batches,
+            completed_batches: 0,
+            children,
+            started_at: time(),
+            updated_at: time(),
+        };
+
+        ROLLOUT.with(|store| store.borrow_mut().rollout = Some(rollout.clone()));
+        Self::log(LogType::Info, "Child wasm rollout started", &rollout);
+        Self::schedule_next_batch(0);
+        rollout
+    }
+
+    pub fn validate_halt() -> Result<String, String> {
+        match Self::get_rollout() {
+            Some(_rollout) if _rollout.status == RolloutStatus::Running => Ok(format!(
+                "Halt the rollout of child wasm {:?} after {} of the {} batches",
+                _rollout.wasm_version, _rollout.completed_batches, _rollout.total_batches
+            )),
+            _ => Err("There is no running rollout".to_string()),
+        }
+    }
+
+    // Method to halt the running rollout, a batch that is being upgraded is finished first
+    pub fn halt(reason: String) -> Result<Rollout, ApiError> {
+        Self::validate_halt().map_err(|err| Self::error("INVALID_HALT", &err, "halt"))?;
+        Ok(Self::set_status(RolloutStatus::Halted(reason)).expect("validated rollout"))
+    }
+
+    pub fn validate_resume() -> Result<String, String> {
+        if BATCH_IN_FLIGHT.with(|in_flight| in_flight.get()) {
+            return Err("A batch is still being upgraded".to_string());
+        }
+
+        match Self::get_rollout() {
+            Some(_rollout) if matches!(_rollout.status, RolloutStatus::Halted(_)) => {
+                let wasm_version =
+                    DATA.with(|data| data.borrow().child_wasm_data.wasm_version.clone());
+                if _rollout.wasm_version != wasm_version {
+                    return Err(
+                        "The child wasm changed since the rollout started, start a new rollout"
+                            .to_string(),
+                    );
+                }
+
+                Ok(format!(
+                    "Resume the rollout of child wasm {:?} at batch {} of {}",
+                    _rollout.wasm_version,
+                    _rollout.completed_batches + 1,
+                    _rollout.total_batches
+                ))
+            }
+            _ => Err("There is no halted rollout".to_string()),
+        }
+    }
+
+    // Method to resume a halted rollout, the batch that failed is upgraded and checked again
+    pub fn resume() -> Result<Rollout, ApiError> {
+        Self::validate_resume().map_err(|err| Self::error("INVALID_RESUME", &err, "resume"))?;
+
+        let rollout = Self::set_status(RolloutStatus::Running).expect("validated rollout");
+        Self::log(LogType::Info, "Child wasm rollout resumed", &rollout);
+        Self::schedule_next_batch(0);
+        Ok(rollout)
+    }
+
+    // Method to halt a running rollout when the parent is upgraded, the timer of the next batch doesnt survive the upgrade
+    pub fn interrupt() {
+        if Self::validate_halt().is_ok() {
+            Self::set_status(RolloutStatus::Halted(
+                "Interrupted by an upgrade of the parent".to_string(),
+            ));
+        }
+    }
+
+    pub fn is_running() -> bool {
+        Self::check_not_running().is_err()
+    }
+
+    fn check_not_running() -> Result<(), String> {
+        if BATCH_IN_FLIGHT.with(|in_flight| in_flight.get()) {
+            return Err("A batch is still being upgraded".to_string());
+        }
+
+        match Self::get_rollout() {
+            Some(_rollout) if _rollout.status == RolloutStatus::Running => {
+                Err("A rollout is in progress".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn schedule_next_batch(delay_seconds: u64) {
+        set_timer(Duration::from_secs(delay_seconds), || {
+            ic_cdk::spawn(Self::run_next_batch());
+        });
+    }
+
+    // Method to upgrade and health check the child canisters of the next batch
+    // the rollout halts when one of the child canisters fails, otherwise the next batch is scheduled after the batch delay
+    async fn run_next_batch() {
+        // The guard releases the lock at the end of the method, also when a callback traps
+        let _guard = match LockGuard::acquire(&BATCH_IN_FLIGHT) {
+            None => return,
+            Some(_guard) => _guard,
+        };
+
+        let rollout = match Self::get_rollout() {
+            Some(_rollout) if _rollout.status == RolloutStatus::Running => _rollout,
+            _ => return,
+        };
+
+        let wasm_version = DATA.with(|data| data.borrow().child_wasm_data.wasm_version.clone());
+        if rollout.wasm_version != wasm_version {
+            Self::set_status(RolloutStatus::Halted(
+                "The child wasm changed during the rollout".to_string(),
+            ));
+            return;
+        }
+
+        let canisters: Vec<Principal> = rollout
+            .children
+            .iter()
+            .filter(|child| child.batch == rollout.completed_batches)
+            .map(|child| child.canister_principal)
+            .collect();
+
+        let results = ScalableData::join_all_batched(
+            canisters
+                .iter()
+                .map(|canister_principal| Self::upgrade_and_check(*canister_principal))
+                .collect(),
+        )
+        .await;
+
+        let rollout = ROLLOUT.with(|store| {
+            let mut store = store.borrow_mut();
+            let rollout = store.rollout.as_mut().expect("running rollout");
+            let mut failure: Option<String> = None;
+
+            for (canister_principal, result) in canisters.iter().zip(results) {
+                let status = match result {
+                    Ok(_) => ChildRolloutStatus::Upgraded,
+                    Err(err) => {
+                        failure.get_or_insert(format!("{} failed: {}", canister_principal, err));
+                        ChildRolloutStatus::Failed(err)
+                    }
+                };
+
+                if let Some(_child) = rollout
+                    .children
+                    .iter_mut()
+                    .find(|child| &child.canister_principal == canister_principal)
+                {
+                    _child.status = status;
+                    _child.updated_at = time();
+                }
+            }
+
+            // An operator can halt the rollout while the batch is upgraded, a halted rollout keeps its status
+            // and resumes at this batch, the upgraded child canisters are then only checked
+            if rollout.status == RolloutStatus::Running {
+                match failure {
+                    Some(_failure) => rollout.status = RolloutStatus::Halted(_failure),
+                    None => {
+                        rollout.completed_batches += 1;
+                        if rollout.completed_batches >= rollout.total_batches {
+                            rollout.status = RolloutStatus::Completed;
+                        }
+                    }
+                }
+            }
+            rollout.updated_at = time();
+            rollout.clone()
+        });
+
+        match &rollout.status {
+            RolloutStatus::Running => {
+                Self::log(
+                    LogType::Info,
+                    "Child wasm rollout batch completed",
+                    &rollout,
+                );
+                Self::schedule_next_batch(rollout.settings.batch_delay_seconds);
+            }
+            RolloutStatus::Completed => {
+                Self::log(LogType::Info, "Child wasm rollout completed", &rollout)
+            }
+            RolloutStatus::Halted(_) => {
+                Self::log(LogType::Error, "Child wasm rollout halted", &rollout)
+            }
+        }
+    }
+
+    // A child canister that is already upgraded (when a batch is retried) is only checked
+    async fn upgrade_and_check(canister_principal: Principal) -> Result<(), String> {
+        let is_upgraded = DATA.with(|data| {
+            let data = data.borrow();
+            data.canisters
+                .get(&canister_principal)
+                .map(|canister| canister.wasm_version == data.child_wasm_data.wasm_version)
+                .unwrap_or(false)
+        });
+
+        if !is_upgraded {
+            ScalableData::upgrade_child_canister(canister_principal)
+                .await
+                .map_err(|err| format!("{:?}", err))?;
+        }
+
+        Self::health_check(canister_principal).await
+    }
+
+    // Health check of an upgraded child canister, `sanity_check` returns the name of the canister
+    // and `get_operator_roles` checks that the candid interface still decodes
+    async fn health_check(canister_principal: Principal) -> Result<(), String> {
+        let sanity_check: Result<(String,), _> =
+            call::call(canister_principal, "sanity_check", ()).await;
+        let name = match sanity_check {
+            Err(err) => {
+                record_call_failure("sanity_check");
+                return Err(format!("sanity_check: {:?} - {}", err.0, err.1));
+            }
+            Ok((_name,)) => _name,
+        };
+
+        let expected_name = DATA.with(|data| data.borrow().name.clone());
+        if name != expected_name {
+            return Err(format!(
+                "sanity_check: expected {}, received {}",
+                expected_name, name
+            ));
+        }
+
+        let smoke_query: Result<(OperatorRoles,), _> =
+            call::call(canister_principal, "get_operator_roles", ()).await;
+        if let Err(err) = smoke_query {
+            record_call_failure("get_operator_roles");
+            return Err(format!("get_operator_roles: {:?} - {}", err.0, err.1));
+        }
+
+        Self::certification_check(canister_principal).await
+    }
+
+    // The child canister rebuilds its certified data on timers after the upgrade, so the check is repeated
+    // with a delay in between, larger child canisters need more rebuild slices and get more checks
+    async fn certification_check(canister_principal: Principal) -> Result<(), String> {
+        let attendees = match ScalableData::get_child_attendee_metrics(canister_principal).await {
+            Err(err) => {
+                record_call_failure("get_attendee_metrics");
+                return Err(format!("get_attendee_metrics: {}", err));
+            }
+            Ok(_metrics) => _metrics.attendees,
+        };
+        let max_checks = MIN_CERTIFICATION_CHECKS + attendees / ATTENDEES_PER_CERTIFICATION_CHECK;
+
+        for check in 0..max_checks {
+            if check > 0 {
+                Self::wait(CERTIFICATION_CHECK_DELAY_SECONDS).await;
+            }
+
+            let is_ready: Result<(bool,), _> =
+                call::call(canister_principal, "is_certification_ready", ()).await;
+            match is_ready {
+                Err(err) => {
+                    record_call_failure("is_certification_ready");
+                    return Err(format!("is_certification_ready: {:?} - {}", err.0, err.1));
+                }
+                Ok((true,)) => return Ok(()),
+                Ok((false,)) => continue,
+            }
+        }
+        Err(format!(
+            "is_certification_ready: the certified data is not rebuilt after {} checks",
+            max_checks
+        ))
+    }
+
+    // Resolves after the delay, the timer runs in its own message so other messages are handled while waiting
+    async fn wait(delay_seconds: u64) {
+        let (sender, receiver) = oneshot::channel::<()>();
+        set_timer(Duration::from_secs(delay_seconds), move || {
+            let _ = sender.send(());
+        });
+        let _ = receiver.await;
+    }
+
+    fn get_outdated_canisters() -> Vec<Principal> {
+        DATA.with(|data| {
+            let data = data.borrow();
+            let mut canisters: Vec<Principal> = data
+                .canisters
+                .values()
+                .filter(|canister| canister.wasm_version != data.child_wasm_data.wasm_version)
+                .map(|canister| canister.principal)
+                .collect();
+            canisters.sort();
+            canisters
+        })
+    }
+
+    fn get_rollout() -> Option<Rollout> {
+        ROLLOUT.with(|store| store.borrow().rollout.clone())
+    }
+
+    fn set_status(status: RolloutStatus) -> Option<Rollout> {
+        ROLLOUT.with(|store| {
+            let mut store = store.borrow_mut();
+            let rollout = store.rollout.as_mut()?;
+            rollout.status = status;
+            rollout.updated_at = time();
+            Some(rollout.clone())
+        })
+    }
+
+    fn log(log_type: LogType, description: &str, rollout: &Rollout) {
+        add_log(PostLog {
+            log_type,
+            description: description.to_string(),
+            source: "rollout".to_string(),
+            data: format!(
+                "{:?} - batch {} of {} - {:?}",
+                rollout.wasm_version,
+                rollout.completed_batches,
+                rollout.total_batches,
+                rollout.status
+            ),
+        });
+    }
+
+    fn error(tag: &str, message: &str, method_name: &str) -> ApiError {
+        api_error(
+            ApiErrorType::BadRequest,
+            tag,
+            message,
+            "Rollouts",
+            method_name,
+            None,
+        )
+    }
+}
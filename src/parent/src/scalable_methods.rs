@@ -4,7 +4,7 @@ use ic_scalable_misc::{
     enums::{api_error_type::ApiError, wasm_version_type::WasmVersion},
     helpers::{
         canister_helper::Canister,
-        metrics_helper::{http_request as _http_request, metrics, PathEntry},
+        metrics_helper::{http_request as _http_request, metrics, MetricsEntry, PathEntry},
     },
     models::{
         canister_models::ScalableCanisterDetails,
@@ -12,7 +12,12 @@ use ic_scalable_misc::{
     },
 };
 
-use super::store::{ScalableData, DATA};
+use shared::metrics_helper::{is_metrics_request, render_attendee_metrics};
+
+use super::{
+    http::Http,
+    store::{ScalableData, ATTENDEE_DIRECTORY, DATA},
+};
 
 // Method to retrieve an available canister to write updated to
 #[query]
@@ -42,10 +47,44 @@ fn get_latest_wasm_version() -> WasmVersion {
     DATA.with(|v| v.borrow().child_wasm_data.wasm_version.clone())
 }
 
-// HTTP request handler
-// canister metrics are added to the response
-#[query]
-fn http_request(req: HttpRequest) -> HttpResponse {
+// HTTP request handler, serves the read-only JSON routes and the canister metrics
+// requires composite queries to be released to mainnet
+#[query(composite = true)]
+async fn http_request(req: HttpRequest) -> HttpResponse {
+    if let Some(_response) = Http::handle(&req).await {
+        return _response;
+    }
+
+    // The child canisters are only called for the metrics path
+    if !is_metrics_request(&req.method, &req.url) {
+        return _http_request(req, vec![]);
+    }
+
+    let (attendee_metrics, failed_canisters) = ScalableData::get_attendee_metrics().await;
+    let mut body = metrics(vec![
+        MetricsEntry {
+            helper_text: "child_canisters Number of child canisters".to_string(),
+            label: "child_canisters".to_string(),
+            value: DATA.with(|data| data.borrow().canisters.len()).to_string(),
+        },
+        MetricsEntry {
+            helper_text: "attendee_directory Number of principals in the attendee directory"
+                .to_string(),
+            label: "attendee_directory".to_string(),
+            value: ATTENDEE_DIRECTORY
+                .with(|directory| directory.borrow().len())
+                .to_string(),
+        },
+        MetricsEntry {
+            helper_text:
+                "failed_child_metrics Number of child canisters that failed to return their metrics"
+                    .to_string(),
+            label: "failed_child_metrics".to_string(),
+            value: failed_canisters.len().to_string(),
+        },
+    ]);
+    body.push_str(&render_attendee_metrics(&attendee_metrics));
+
     let path_entries = vec![PathEntry {
         match_path: vec!["metrics".to_string()],
         response: HttpResponse {
@@ -54,7 +93,7 @@ fn http_request(req: HttpRequest) -> HttpResponse {
                 "content-type".to_string(),
                 "text/plain".to_string(),
             )],
-            body: metrics(vec![]).as_bytes().to_vec(),
+            body: body.into_bytes(),
         },
     }];
 
@@ -0,0 +1,220 @@
+use candid::Principal;
+use ic_scalable_misc::{
+    enums::api_error_type::ApiError,
+    helpers::logger_helper::add_log,
+    models::logger_models::{LogType, PostLog},
+};
+
+use shared::{
+    capacity_model::CapacitySettings,
+    operator_helper::{add_operator, has_operator_role, remove_operator},
+    operator_model::{OperatorRole, OperatorRolesUpdate},
+    rollout_model::RolloutSettings,
+    wasm_model::ChildWasmUploadRequest,
+};
+
+use crate::{
+    child_wasm::ChildWasm,
+    roles::Roles,
+    rollout::Rollouts,
+    store::{ScalableData, CAPACITY_SETTINGS, DATA},
+};
+
+// Bounds of the cycles a new child canister is created with
+const MIN_CHILD_CANISTER_CYCLES: u64 = 1_000_000_000_000;
+const MAX_CHILD_CANISTER_CYCLES: u64 = 50_000_000_000_000;
+
+// Logic of the SNS generic functions, the validation methods return the text that is shown on the proposal
+// the validation runs again when the proposal is executed, because the state can change while the proposal is open
+pub struct Sns;
+
+impl Sns {
+    // Method to check if the principal is the SNS governance canister set with `set_sns_governance`
+    pub fn is_governance(principal: &Principal) -> bool {
+        Roles::get().sns_governance.as_ref() == Some(principal)
+    }
+
+    // The child canisters are upgraded in a staged rollout, the proposal succeeds when the rollout is started
+    pub fn validate_upgrade_children() -> Result<String, String> {
+        if DATA.with(|data| data.borrow().child_wasm_data.bytes.is_empty()) {
+            return Err("There is no child wasm uploaded".to_string());
+        }
+        Rollouts::validate_start()
+    }
+
+    pub fn upgrade_children() -> Result<(), String> {
+        Self::validate_upgrade_children()?;
+        Self::check_api_result(Rollouts::start())
+    }
+
+    pub fn set_rollout_settings(settings: RolloutSettings) -> Result<(), String> {
+        Self::check_api_result(Rollouts::set_settings(settings))
+    }
+
+    pub fn halt_rollout() -> Result<(), String> {
+        Self::check_api_result(Rollouts::halt("Halted by an SNS proposal".to_string()))
+    }
+
+    pub fn resume_rollout() -> Result<(), String> {
+        Self::check_api_result(Rollouts::resume())
+    }
+
+    pub fn validate_add_operator(
+        role: OperatorRole,
+        principal: Principal,
+    ) -> Result<String, String> {
+        let mut roles = Roles::get();
+        if has_operator_role(&roles, role, &principal) {
+            return Err(format!("{} already has the {:?} role", principal, role));
+        }
+        add_operator(&mut roles, role, principal)?;
+        Ok(format!(
+            "Add {} as {:?} of the child canisters",
+            principal, role
+        ))
+    }
+
+    pub async fn add_operator(role: OperatorRole, principal: Principal) -> Result<(), String> {
+        Self::validate_add_operator(role, principal)?;
+        Self::check_propagation(Roles::add_operator(role, principal).await)
+    }
+
+    pub fn validate_remove_operator(
+        role: OperatorRole,
+        principal: Principal,
+    ) -> Result<String, String> {
+        let mut roles = Roles::get();
+        match remove_operator(&mut roles, role, &principal) {
+            true => Ok(format!(
+                "Remove {} as {:?} of the child canisters",
+                principal, role
+            )),
+            false => Err(format!("{} doesnt have the {:?} role", principal, role)),
+        }
+    }
+
+    pub async fn remove_operator(role: OperatorRole, principal: Principal) -> Result<(), String> {
+        Self::validate_remove_operator(role, principal)?;
+        Self::check_propagation(Roles::remove_operator(role, principal).await)
+    }
+
+    // Restores need a backup that is uploaded to the child canister by an owner
+    pub fn validate_restore_child_backup(canister_principal: Principal) -> Result<String, String> {
+        Self::validate_child_canister(canister_principal)?;
+        Ok(format!(
+            "Replace the data of child canister {} with its uploaded backup",
+            canister_principal
+        ))
+    }
+
+    pub async fn restore_child_backup(canister_principal: Principal) -> Result<(), String> {
+        Self::validate_restore_child_backup(canister_principal)?;
+        ScalableData::call_child_backup_method(canister_principal, "restore_data").await
+    }
+
+    pub fn validate_clear_child_backup(canister_principal: Principal) -> Result<String, String> {
+        Self::validate_child_canister(canister_principal)?;
+        Ok(format!(
+            "Remove the uploaded backup of child canister {}",
+            canister_principal
+        ))
+    }
+
+    pub async fn clear_child_backup(canister_principal: Principal) -> Result<(), String> {
+        Self::validate_clear_child_backup(canister_principal)?;
+        ScalableData::call_child_backup_method(canister_principal, "clear_backup").await
+    }
+
+    pub fn validate_capacity_settings(settings: &CapacitySettings) -> Result<String, String> {
+        if !(MIN_CHILD_CANISTER_CYCLES..=MAX_CHILD_CANISTER_CYCLES)
+            .contains(&settings.child_canister_cycles)
+        {
+            return Err(format!(
+                "The child canister cycles should be between {} and {}",
+                MIN_CHILD_CANISTER_CYCLES, MAX_CHILD_CANISTER_CYCLES
+            ));
+        }
+
+        let child_canisters = DATA.with(|data| data.borrow().canisters.len() as u64);
+        if let Some(_max_child_canisters) = settings.max_child_canisters {
+            if _max_child_canisters < child_canisters.max(1) {
+                return Err(format!(
+                    "The max number of child canisters can't be lower than the {} existing child canisters",
+                    child_canisters
+                ));
+            }
+        }
+
+        Ok(format!(
+            "Create new child canisters with {} cycles, max child canisters: {}",
+            settings.child_canister_cycles,
+            settings
+                .max_child_canisters
+                .map(|max| max.to_string())
+                .unwrap_or_else(|| "no limit".to_string())
+        ))
+    }
+
+    pub fn set_capacity_settings(settings: CapacitySettings) -> Result<(), String> {
+        Self::validate_capacity_settings(&settings)?;
+        CAPACITY_SETTINGS.with(|stored| *stored.borrow_mut() = settings);
+        Ok(())
+    }
+
+    pub fn get_capacity_settings() -> CapacitySettings {
+        CAPACITY_SETTINGS.with(|settings| settings.borrow().clone())
+    }
+
+    // The child wasm is uploaded in chunks, every chunk is a separate proposal
+    pub fn start_child_wasm_upload(
+        caller: Principal,
+        request: ChildWasmUploadRequest,
+    ) -> Result<(), String> {
+        Self::check_api_result(ChildWasm::start_upload(caller, request))
+    }
+
+    pub fn upload_child_wasm_chunk(chunk: u64, bytes: Vec<u8>) -> Result<(), String> {
+        Self::check_api_result(ChildWasm::upload_chunk(chunk, bytes))
+    }
+
+    pub fn finalize_child_wasm_upload() -> Result<(), String> {
+        Self::check_api_result(ChildWasm::finalize_upload())
+    }
+
+    pub fn rollout_child_wasm(version: u64) -> Result<(), String> {
+        Self::check_api_result(ChildWasm::rollout(version))
+    }
+
+    fn validate_child_canister(canister_principal: Principal) -> Result<(), String> {
+        match ScalableData::is_child_canister(&canister_principal) {
+            true => Ok(()),
+            false => Err(format!("{} is not a child canister", canister_principal)),
+        }
+    }
+
+    fn check_api_result<T>(result: Result<T, ApiError>) -> Result<(), String> {
+        result.map(|_| ()).map_err(|err| format!("{:?}", err))
+    }
+
+    // The role change is stored on the parent before it is sent to the child canisters, so the proposal succeeds once it is stored
+    // child canisters that couldn't be reached are logged, `propagate_operator_roles` sends the roles to them again
+    fn check_propagation(result: Result<OperatorRolesUpdate, ApiError>) -> Result<(), String> {
+        let update = result.map_err(|err| format!("{:?}", err))?;
+        if !update.failed_canisters.is_empty() {
+            add_log(PostLog {
+                log_type: LogType::Error,
+                description: "The operator roles are changed but not propagated to all the child canisters, retry with `propagate_operator_roles`".to_string(),
+                source: "sns".to_string(),
+                data: format!(
+                    "{:?}",
+                    update
+                        .failed_canisters
+                        .iter()
+                        .map(|failed| failed.canister_identifier)
+                        .collect::<Vec<Principal>>()
+                ),
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use candid::Principal;
+use ic_cdk::{caller, query, update};
+
+use shared::{
+    capacity_model::CapacitySettings, operator_model::OperatorRole, rollout_model::RolloutSettings,
+    wasm_model::ChildWasmUploadRequest,
+};
+
+use super::{child_wasm::ChildWasm, rollout::Rollouts, sns::Sns};
+
+// SNS generic functions, every target method has a validation method with the `validate_` prefix
+// the target methods trap when the operation fails, so the proposal is marked as failed
+
+// Method to start a staged rollout of the current child wasm to the child canisters that dont run it
+#[update(guard = "is_sns_governance")]
+fn sns_upgrade_child_canisters() {
+    or_trap(Sns::upgrade_children())
+}
+
+#[query]
+fn validate_sns_upgrade_child_canisters() -> Result<String, String> {
+    Sns::validate_upgrade_children()
+}
+
+// Method to give a principal the owner or monitor role on all the child canisters
+#[update(guard = "is_sns_governance")]
+async fn sns_add_operator(role: OperatorRole, principal: Principal) {
+    or_trap(Sns::add_operator(role, principal).await)
+}
+
+#[query]
+fn validate_sns_add_operator(role: OperatorRole, principal: Principal) -> Result<String, String> {
+    Sns::validate_add_operator(role, principal)
+}
+
+// Method to take the owner or monitor role of a principal on all the child canisters
+#[update(guard = "is_sns_governance")]
+async fn sns_remove_operator(role: OperatorRole, principal: Principal) {
+    or_trap(Sns::remove_operator(role, principal).await)
+}
+
+#[query]
+fn validate_sns_remove_operator(
+    role: OperatorRole,
+    principal: Principal,
+) -> Result<String, String> {
+    Sns::validate_remove_operator(role, principal)
+}
+
+// Method to restore the data of a child canister from the backup that is uploaded by an owner
+#[update(guard = "is_sns_governance")]
+async fn sns_restore_child_backup(canister_principal: Principal) {
+    or_trap(Sns::restore_child_backup(canister_principal).await)
+}
+
+#[query]
+fn validate_sns_restore_child_backup(canister_principal: Principal) -> Result<String, String> {
+    Sns::validate_restore_child_backup(canister_principal)
+}
+
+// Method to remove the uploaded backup of a child canister
+#[update(guard = "is_sns_governance")]
+async fn sns_clear_child_backup(canister_principal: Principal) {
+    or_trap(Sns::clear_child_backup(canister_principal).await)
+}
+
+#[query]
+fn validate_sns_clear_child_backup(canister_principal: Principal) -> Result<String, String> {
+    Sns::validate_clear_child_backup(canister_principal)
+}
+
+// Method to set the cycles and the max number of child canisters used when spawning a child canister
+#[update(guard = "is_sns_governance")]
+fn sns_set_capacity_settings(settings: CapacitySettings) {
+    or_trap(Sns::set_capacity_settings(settings))
+}
+
+#[query]
+fn validate_sns_set_capacity_settings(settings: CapacitySettings) -> Result<String, String> {
+    Sns::validate_capacity_settings(&settings)
+}
+
+// Method to start a child wasm upload, replaces an upload that is in progress
+#[update(guard = "is_sns_governance")]
+fn sns_start_child_wasm_upload(request: ChildWasmUploadRequest) {
+    or_trap(Sns::start_child_wasm_upload(caller(), request))
+}
+
+#[query]
+fn validate_sns_start_child_wasm_upload(request: ChildWasmUploadRequest) -> Result<String, String> {
+    ChildWasm::validate_start_upload(&request)
+}
+
+// Method to upload the next chunk of the child wasm
+#[update(guard = "is_sns_governance")]
+fn sns_upload_child_wasm_chunk(chunk: u64, bytes: Vec<u8>) {
+    or_trap(Sns::upload_child_wasm_chunk(chunk, bytes))
+}
+
+#[query]
+fn validate_sns_upload_child_wasm_chunk(chunk: u64, bytes: Vec<u8>) -> Result<String, String> {
+    ChildWasm::validate_upload_chunk(chunk, &bytes)
+}
+
+// Method to verify the uploaded child wasm against the hash and store it with the next version
+#[update(guard = "is_sns_governance")]
+fn sns_finalize_child_wasm_upload() {
+    or_trap(Sns::finalize_child_wasm_upload())
+}
+
+#[query]
+fn validate_sns_finalize_child_wasm_upload() -> Result<String, String> {
+    ChildWasm::validate_finalize_upload()
+}
+
+// Method to roll out the uploaded child wasm to the child canisters
+#[update(guard = "is_sns_governance")]
+fn sns_rollout_child_wasm(version: u64) {
+    or_trap(Sns::rollout_child_wasm(version))
+}
+
+#[query]
+fn validate_sns_rollout_child_wasm(version: u64) -> Result<String, String> {
+    ChildWasm::validate_rollout(version)
+}
+
+// Method to set the canary percentage, batch size and batch delay of the next rollout
+#[update(guard = "is_sns_governance")]
+fn sns_set_rollout_settings(settings: RolloutSettings) {
+    or_trap(Sns::set_rollout_settings(settings))
+}
+
+#[query]
+fn validate_sns_set_rollout_settings(settings: RolloutSettings) -> Result<String, String> {
+    Rollouts::validate_settings(&settings)
+}
+
+// Method to halt the running rollout
+#[update(guard = "is_sns_governance")]
+fn sns_halt_rollout() {
+    or_trap(Sns::halt_rollout())
+}
+
+#[query]
+fn validate_sns_halt_rollout() -> Result<String, String> {
+    Rollouts::validate_halt()
+}
+
+// Method to resume a halted rollout, the batch that failed is upgraded and checked again
+#[update(guard = "is_sns_governance")]
+fn sns_resume_rollout() {
+    or_trap(Sns::resume_rollout())
+}
+
+#[query]
+fn validate_sns_resume_rollout() -> Result<String, String> {
+    Rollouts::validate_resume()
+}
+
+// Method to get the cycles and the max number of child canisters used when spawning a child canister
+#[query]
+fn get_capacity_settings() -> CapacitySettings {
+    Sns::get_capacity_settings()
+}
+
+fn or_trap(result: Result<(), String>) {
+    if let Err(err) = result {
+        ic_cdk::trap(&err);
+    }
+}
+
+pub fn is_sns_governance() -> Result<(), String> {
+    match Sns::is_governance(&caller()) {
+        true => Ok(()),
+        false => Err("Unauthorized".to_string()),
+    }
+}
@@ -1,10 +1,18 @@
-use std::{cell::RefCell, collections::HashMap};
+use std::{
+    cell::RefCell, cmp::Ordering, collections::HashMap, future::Future, iter::Peekable,
+    vec::IntoIter,
+};
 
-use candid::{CandidType, Deserialize, Principal};
+use candid::{CandidType, Deserialize, Nat, Principal};
+use futures::future::join_all;
 use ic_cdk::{
     api::{call, time},
     id,
 };
+use ic_stable_structures::{
+    memory_manager::{MemoryId, MemoryManager, VirtualMemory},
+    DefaultMemoryImpl, StableBTreeMap,
+};
 
 use ic_scalable_misc::{
     enums::{
@@ -15,18 +23,38 @@ use ic_scalable_misc::{
     helpers::{
         canister_helper::{Canister, CanisterID, CanisterSettings, InstallCodeMode},
         error_helper::api_error,
-        logger_helper::add_log,
         paging_helper::get_paged_data,
+        role_helper::{default_roles, get_group_roles, get_member_roles, has_permission},
         serialize_helper::deserialize,
     },
     models::{
         canister_models::ScalableCanisterDetails,
-        logger_models::{LogType, PostLog},
         paged_response_models::PagedResponse,
+        permissions_models::{PermissionActionType, PermissionType},
         wasm_models::WasmDetails,
     },
 };
-use shared::attendee_model::{InviteAttendeeResponse, JoinedAttendeeResponse};
+use shared::{
+    attendee_model::{
+        Attendee, AttendeeListRequest, AttendeeStatus, ChildCanisterError, CursorPagedResponse,
+        DeduplicationReport, EventCount, GroupAttendee, GroupAttendeeRequest, GroupEventAttendance,
+        Invite, InviteAttendeeResponse, InviteAttendeeResponseV2, Join, JoinedAttendeeResponse,
+        JoinedAttendeeResponseV2, MergedCursorPagedResponse, MergedEventCount, MergedPagedResponse,
+        MAX_PAGE_LIMIT,
+    },
+    capacity_model::CapacitySettings,
+    certification_model::CertifiedEventCounts,
+    chunk_helper::assemble_chunks,
+    chunk_model::ChunkedData,
+    cursor_helper::encode_cursor,
+    export_model::EventExportRow,
+    icrc3_model::{AttendanceLog, GetBlocksArgs, GetBlocksResult},
+    metrics_helper::{get_call_failures, merge_metrics, record_call_failure},
+    metrics_model::AttendeeMetrics,
+    operator_model::OperatorRoles,
+    rollout_model::RolloutStore,
+    wasm_model::ChildWasmStore,
+};
 
 #[derive(CandidType, Clone, Deserialize)]
 pub struct ScalableMetaData {
@@ -70,8 +98,40 @@ impl Default for ScalableData {
     }
 }
 
+type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+// The data that is kept on the heap is written to its own memory on upgrade, the stable structures use the others
+pub static UPGRADE_STATE_MEMORY_ID: MemoryId = MemoryId::new(0);
+pub static ATTENDEE_DIRECTORY_MEMORY_ID: MemoryId = MemoryId::new(1);
+
+// Max number of inter-canister calls that are awaited at the same time during a fan-out to the child canisters
+// keeps the composite queries within the limit of outstanding calls
+const MAX_CONCURRENT_CALLS: usize = 20;
+// Number of times the chunks of a child canister are read when the data changes while they are read
+const MAX_SNAPSHOT_ATTEMPTS: usize = 3;
+// Error tag of a child canister when the data changed since the first chunk was read
+const SNAPSHOT_EXPIRED: &str = "SNAPSHOT_EXPIRED";
+
 thread_local! {
+    pub static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+        RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+
     pub static DATA: RefCell<ScalableData> = RefCell::new(ScalableData::default());
+    // Directory of the attendee principals and the child canister that holds their attendee record, keyed by principal
+    // kept in stable memory so it doesnt need to be serialized on upgrade
+    pub static ATTENDEE_DIRECTORY: RefCell<StableBTreeMap<String, String, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(ATTENDEE_DIRECTORY_MEMORY_ID)),
+        )
+    );
+    // Owner and monitor principals of the child canisters, passed on install and upgrade and propagated on every change
+    pub static OPERATOR_ROLES: RefCell<OperatorRoles> = RefCell::new(OperatorRoles::default());
+    // Cycles and limit used when spawning a new child canister
+    pub static CAPACITY_SETTINGS: RefCell<CapacitySettings> = RefCell::new(CapacitySettings::default());
+    // Child wasm uploads and the metadata of the rolled out versions
+    pub static CHILD_WASM: RefCell<ChildWasmStore> = RefCell::new(ChildWasmStore::default());
+    // Settings of the staged child wasm rollouts and the last rollout
+    pub static ROLLOUT: RefCell<RolloutStore> = RefCell::new(RolloutStore::default());
 }
 impl ScalableData {
     // Method to retrieve an available canister to write updates to
@@ -202,14 +262,17 @@ impl ScalableData {
                                 .await;
 
                                 match call_result {
-                                    Err(err) => Err(api_error(
-                                        ApiErrorType::BadRequest,
-                                        "FAILED_TO_STORE_DATA",
-                                        err.1.as_str(),
-                                        &Self::get_name(),
-                                        "close_child_canister_and_spawn_sibling",
-                                        inputs,
-                                    )),
+                                    Err(err) => {
+                                        record_call_failure("add_entry_by_parent");
+                                        Err(api_error(
+                                            ApiErrorType::BadRequest,
+                                            "FAILED_TO_STORE_DATA",
+                                            err.1.as_str(),
+                                            &Self::get_name(),
+                                            "close_child_canister_and_spawn_sibling",
+                                            inputs,
+                                        ))
+                                    }
                                     Ok(_) => Ok(new_installed_canister_principal),
                                 }
                             }
@@ -229,8 +292,15 @@ impl ScalableData {
             &canister_principal.to_string()
         )]);
 
-        let data = DATA.with(|v| v.borrow().clone());
-        match data.canisters.get(&canister_principal).cloned() {
+        // Only the needed fields are read, the wasm bytes are cloned once for the install
+        let (child_canister, wasm_version) = DATA.with(|v| {
+            let data = v.borrow();
+            (
+                data.canisters.get(&canister_principal).cloned(),
+                data.child_wasm_data.wasm_version.clone(),
+            )
+        });
+        match child_canister {
             None => Err(api_error(
                 ApiErrorType::NotFound,
                 "NO_CHILDREN",
@@ -241,7 +311,7 @@ impl ScalableData {
             )),
             Some(mut _child_canister) => {
                 // check if the version of the wasm is different then the new version
-                if &data.child_wasm_data.wasm_version == &_child_canister.wasm_version {
+                if wasm_version == _child_canister.wasm_version {
                     return Err(api_error(
                         ApiErrorType::BadRequest,
                         "CANISTER_UP_TO_DATE",
@@ -257,22 +327,25 @@ impl ScalableData {
                 let upgrade_result = canister
                     .install_code(
                         InstallCodeMode::Upgrade,
-                        data.child_wasm_data.bytes.clone(),
-                        (),
+                        DATA.with(|v| v.borrow().child_wasm_data.bytes.clone()),
+                        (Some(OPERATOR_ROLES.with(|roles| roles.borrow().clone())),),
                     )
                     .await;
                 match upgrade_result {
-                    Err(err) => Err(api_error(
-                        ApiErrorType::BadRequest,
-                        "UPGRADE_FAILED",
-                        &err.1.as_str(),
-                        &Self::get_name(),
-                        "upgrade_scalable_canister",
-                        inputs,
-                    )),
+                    Err(err) => {
+                        record_call_failure("install_code");
+                        Err(api_error(
+                            ApiErrorType::BadRequest,
+                            "UPGRADE_FAILED",
+                            &err.1.as_str(),
+                            &Self::get_name(),
+                            "upgrade_scalable_canister",
+                            inputs,
+                        ))
+                    }
                     Ok(_) => {
                         // update child wasm version
-                        _child_canister.wasm_version = data.child_wasm_data.wasm_version;
+                        _child_canister.wasm_version = wasm_version;
 
                         DATA.with(|v| {
                             v.borrow_mut()
@@ -288,6 +361,20 @@ impl ScalableData {
 
     // Method used to spawn an empty canister (not installed)
     async fn spawn_empty_canister() -> Result<Principal, ApiError> {
+        let capacity = CAPACITY_SETTINGS.with(|settings| settings.borrow().clone());
+        if let Some(_max_child_canisters) = capacity.max_child_canisters {
+            if DATA.with(|data| data.borrow().canisters.len() as u64) >= _max_child_canisters {
+                return Err(api_error(
+                    ApiErrorType::BadRequest,
+                    "MAX_CHILD_CANISTERS_REACHED",
+                    "The max number of child canisters is reached",
+                    &Self::get_name(),
+                    "_spawn_empty_canister",
+                    None,
+                ));
+            }
+        }
+
         // Set canister settings
         let canister_settings = CanisterSettings {
             controllers: Some(vec![id()]),
@@ -296,17 +383,21 @@ impl ScalableData {
             freezing_threshold: None,
         };
 
-        // Create canister with predefined amount of cycles
-        let new_canister = Canister::create(Some(canister_settings), 2_000_000_000_000).await;
+        // Create canister with the configured amount of cycles
+        let new_canister =
+            Canister::create(Some(canister_settings), capacity.child_canister_cycles).await;
         match new_canister {
-            Err(err) => Err(api_error(
-                ApiErrorType::BadRequest,
-                "CANISTER_NOT_CREATED",
-                err.1.as_str(),
-                &Self::get_name(),
-                "_spawn_empty_canister",
-                None,
-            )),
+            Err(err) => {
+                record_call_failure("create_canister");
+                Err(api_error(
+                    ApiErrorType::BadRequest,
+                    "CANISTER_NOT_CREATED",
+                    err.1.as_str(),
+                    &Self::get_name(),
+                    "_spawn_empty_canister",
+                    None,
+                ))
+            }
             Ok(_canister) => {
                 let new_canister_principal = CanisterID::from(_canister);
                 let canister_data = ScalableCanisterDetails {
@@ -352,19 +443,27 @@ impl ScalableData {
             .install_code(
                 install_code_mode,
                 data.child_wasm_data.bytes,
-                (id(), name, data.canisters.iter().len()),
+                (
+                    id(),
+                    name,
+                    data.canisters.iter().len(),
+                    Some(OPERATOR_ROLES.with(|roles| roles.borrow().clone())),
+                ),
             )
             .await;
 
         match install_canister {
-            Err(err) => Err(api_error(
-                ApiErrorType::NotFound,
-                "CANISTER_INSTALL_FAILED",
-                err.1.as_str(),
-                &Self::get_name(),
-                "_install_child_canister",
-                inputs,
-            )),
+            Err(err) => {
+                record_call_failure("install_code");
+                Err(api_error(
+                    ApiErrorType::NotFound,
+                    "CANISTER_INSTALL_FAILED",
+                    err.1.as_str(),
+                    &Self::get_name(),
+                    "_install_child_canister",
+                    inputs,
+                ))
+            }
             Ok(_) => {
                 let new_child_details = ScalableCanisterDetails {
                     principal: canister_principal,
@@ -384,55 +483,6 @@ impl ScalableData {
         }
     }
 
-    // Method used to upgrade all the child canister
-    pub async fn upgrade_children() {
-        let data = DATA.with(|data| data.borrow().clone());
-        for child in data.canisters {
-            if child.1.wasm_version != data.child_wasm_data.wasm_version {
-                match ScalableData::upgrade_child_canister(child.0.clone()).await {
-                    Ok(_details) => add_log(PostLog {
-                        log_type: LogType::Info,
-                        description: "Event attendee child canister successfully upgraded"
-                            .to_string(),
-                        source: "upgrade_children".to_string(),
-                        data: format!("{:?}", _details),
-                    }),
-                    Err(err) => add_log(PostLog {
-                        log_type: LogType::Error,
-                        description: "Event attendee child canister not upgraded".to_string(),
-                        source: "upgrade_children".to_string(),
-                        data: format!("{:?}", err),
-                    }),
-                };
-            }
-        }
-    }
-    pub fn get_child_wasm_data(
-        old_store: &ScalableData,
-        version: u64,
-    ) -> Result<WasmDetails, String> {
-        let bytes = include_bytes!("../../../wasm/child.wasm.gz").to_vec();
-
-        if bytes.is_empty() {
-            return Err("No WASM found, skipping child WASM update".to_string());
-        }
-
-        if old_store.child_wasm_data.bytes == bytes {
-            return Err("WASM is the same, skipping child WASM update".to_string());
-        }
-
-        let details = WasmDetails {
-            label: "child_event_attendee_canister".to_string(),
-            bytes,
-            wasm_type: CanisterType::ScalableChild,
-            wasm_version: WasmVersion::Version(version),
-            updated_at: time(),
-            created_at: old_store.child_wasm_data.created_at,
-        };
-
-        Ok(details)
-    }
-
     fn get_name() -> String {
         DATA.with(|v| v.borrow().name.clone())
     }
@@ -441,87 +491,97 @@ impl ScalableData {
     // MEMBERS
     //
     // Method used to get all the members from the child canisters paged
+    // the child canisters are called in parallel, failed child canisters are reported in the response
     // requires composite queries to be released to mainnet
     pub async fn get_joined_child_canister_data(
-        group_identifier: Principal,
-        limit: usize,
-        page: usize,
-    ) -> PagedResponse<JoinedAttendeeResponse> {
-        let canisters: Vec<Principal> = DATA.with(|data| {
-            data.borrow()
-                .canisters
-                .clone()
-                .into_iter()
-                .map(|c| c.1.principal.clone())
-                .collect()
-        });
+        request: AttendeeListRequest,
+    ) -> MergedPagedResponse<JoinedAttendeeResponse> {
+        Self::get_merged_joined_child_data(request, |(_, response)| response).await
+    }
 
-        let mut joined: Vec<JoinedAttendeeResponse> = vec![];
-        for canister in canisters {
-            let mut canister_data =
-                Self::get_filtered_joined_child_data(canister, group_identifier).await;
-            joined.append(&mut canister_data);
-        }
+    // Method used to get all the members from the child canisters paged with the group, status and timestamps of the join
+    // requires composite queries to be released to mainnet
+    pub async fn get_joined_child_canister_data_v2(
+        request: AttendeeListRequest,
+    ) -> MergedPagedResponse<JoinedAttendeeResponseV2> {
+        Self::get_merged_joined_child_data(request, |(join, response)| {
+            JoinedAttendeeResponseV2::new(&join, response)
+        })
+        .await
+    }
+
+    // Method to merge the filtered and sorted members of the child canisters and map them to the requested response
+    async fn get_merged_joined_child_data<T: Clone>(
+        request: AttendeeListRequest,
+        map: fn((Join, JoinedAttendeeResponse)) -> T,
+    ) -> MergedPagedResponse<T> {
+        let canisters = Self::get_child_principals();
+
+        let results = Self::join_all_batched(
+            canisters
+                .iter()
+                .map(|canister| Self::get_filtered_joined_child_data(*canister, request.clone()))
+                .collect(),
+        )
+        .await;
 
-        get_paged_data(joined, limit, page)
+        // The data of the child canisters is already filtered and sorted, so it only needs to be merged
+        let (joined, failed_canisters) =
+            Self::merge_child_results(canisters, results, |a, b| request.sort.compare(a, b));
+        let joined: Vec<T> = joined.into_iter().map(map).collect();
+
+        MergedPagedResponse {
+            page: get_paged_data(joined, request.limit, request.page),
+            failed_canisters,
+        }
     }
 
     // Method to get the data from a single child canister in chunks
     // requires composite queries to be released to mainnet
     async fn get_filtered_joined_child_data(
         canister_principal: Principal,
-        group_identifier: Principal,
-    ) -> Vec<JoinedAttendeeResponse> {
-        // Do initial fetch of the first chunk and determine the number of chunks
-        let (mut bytes, (_, last)) =
-            Self::get_chunked_joined_child_data(canister_principal, group_identifier, 0, None)
-                .await;
-
-        // If there are more chunks, fetch them
-        if last > 1 {
-            for i in 1..last + 1 {
-                let (mut _bytes, _) = Self::get_chunked_joined_child_data(
-                    canister_principal,
-                    group_identifier,
-                    i,
-                    None,
-                )
-                .await;
-                // Append the bytes to the first chunk
-                bytes.append(&mut _bytes);
-            }
-        }
+        request: AttendeeListRequest,
+    ) -> Result<Vec<(Join, JoinedAttendeeResponse)>, String> {
+        let bytes = Self::read_child_chunks(|chunk, snapshot_id| {
+            Self::get_chunked_joined_child_data(
+                canister_principal,
+                request.clone(),
+                chunk,
+                None,
+                snapshot_id,
+            )
+        })
+        .await?;
 
         // Deserialize the bytes to the correct data type
-        match deserialize::<Vec<JoinedAttendeeResponse>>(bytes.clone()) {
-            Ok(_res) => _res,
-            Err(_err) => {
-                ic_cdk::println!("Error: {}", _err);
-                vec![]
-            }
-        }
+        deserialize::<Vec<(Join, JoinedAttendeeResponse)>>(bytes).map_err(|err| err.to_string())
     }
 
     // Inter canister call to fetch the chunked data from the child canister
     async fn get_chunked_joined_child_data(
         canister_principal: Principal,
-        group_identifier: Principal,
+        request: AttendeeListRequest,
         chunk: usize,
         max_bytes_per_chunk: Option<usize>,
-    ) -> (Vec<u8>, (usize, usize)) {
+        snapshot_id: Option<u64>,
+    ) -> Result<ChunkedData, String> {
         // If the max bytes per chunk is not provided, use the default of 2_000_000 (2mb)
         let _max_bytes_per_chunk = max_bytes_per_chunk.unwrap_or(2_000_000);
-        let result: Result<(Vec<u8>, (usize, usize)), _> = call::call(
+        let result: Result<(Result<ChunkedData, ApiError>,), _> = call::call(
             canister_principal,
             "get_chunked_join_data",
-            (group_identifier, chunk, _max_bytes_per_chunk),
+            (request, chunk, _max_bytes_per_chunk, snapshot_id),
         )
         .await;
 
-        // return the bytes and the chunk info, if there is an error, return an empty vec and chunk info
+        // return the chunk, if there is an error, return the rejection message or the error of the child canister
         match result {
-            Ok(_res) => _res,
-            _ => (vec![], (0, 0)),
+            Err(err) => Err(err.1),
+            Ok((Err(ApiError::BadRequest(_error)),)) if _error.tag == SNAPSHOT_EXPIRED => {
+                Err(SNAPSHOT_EXPIRED.to_string())
+            }
+            Ok((Err(err),)) => Err(format!("{:?}", err)),
+            Ok((Ok(_chunk),)) => Ok(_chunk),
         }
     }
 
@@ -529,87 +589,1015 @@ impl ScalableData {
     // INVITES
     //
     // Method used to get all the invites from the child canisters paged
+    // the child canisters are called in parallel, failed child canisters are reported in the response
     // requires composite queries to be released to mainnet
     pub async fn get_invites_child_canister_data(
-        group_identifier: Principal,
-        limit: usize,
-        page: usize,
-    ) -> PagedResponse<InviteAttendeeResponse> {
-        let canisters: Vec<Principal> = DATA.with(|data| {
-            data.borrow()
-                .canisters
-                .clone()
-                .into_iter()
-                .map(|c| c.1.principal.clone())
-                .collect()
-        });
+        request: AttendeeListRequest,
+    ) -> MergedPagedResponse<InviteAttendeeResponse> {
+        Self::get_merged_invites_child_data(request, |(_, response)| response).await
+    }
 
-        let mut joined: Vec<InviteAttendeeResponse> = vec![];
-        for canister in canisters {
-            let mut canister_data =
-                Self::get_filtered_invites_child_data(canister, group_identifier).await;
-            joined.append(&mut canister_data);
-        }
+    // Method used to get all the invites from the child canisters paged with the group, status and timestamps of the invite
+    // requires composite queries to be released to mainnet
+    pub async fn get_invites_child_canister_data_v2(
+        request: AttendeeListRequest,
+    ) -> MergedPagedResponse<InviteAttendeeResponseV2> {
+        Self::get_merged_invites_child_data(request, |(invite, response)| {
+            InviteAttendeeResponseV2::new(&invite, response)
+        })
+        .await
+    }
+
+    // Method to merge the filtered and sorted invites of the child canisters and map them to the requested response
+    async fn get_merged_invites_child_data<T: Clone>(
+        request: AttendeeListRequest,
+        map: fn((Invite, InviteAttendeeResponse)) -> T,
+    ) -> MergedPagedResponse<T> {
+        let canisters = Self::get_child_principals();
+
+        let results = Self::join_all_batched(
+            canisters
+                .iter()
+                .map(|canister| Self::get_filtered_invites_child_data(*canister, request.clone()))
+                .collect(),
+        )
+        .await;
 
-        get_paged_data(joined, limit, page)
+        // The data of the child canisters is already filtered and sorted, so it only needs to be merged
+        let (invites, failed_canisters) =
+            Self::merge_child_results(canisters, results, |a, b| request.sort.compare(a, b));
+        let invites: Vec<T> = invites.into_iter().map(map).collect();
+
+        MergedPagedResponse {
+            page: get_paged_data(invites, request.limit, request.page),
+            failed_canisters,
+        }
     }
 
     // Method to get the data from a single child canister in chunks
     // requires composite queries to be released to mainnet
     async fn get_filtered_invites_child_data(
         canister_principal: Principal,
-        group_identifier: Principal,
-    ) -> Vec<InviteAttendeeResponse> {
-        // Do initial fetch of the first chunk and determine the number of chunks
-        let (mut bytes, (_, last)) =
-            Self::get_chunked_invites_child_data(canister_principal, group_identifier, 0, None)
-                .await;
-
-        // If there are more chunks, fetch them
-        if last > 1 {
-            for i in 1..last + 1 {
-                let (mut _bytes, _) = Self::get_chunked_invites_child_data(
-                    canister_principal,
-                    group_identifier,
-                    i,
-                    None,
-                )
-                .await;
-                // Append the bytes to the first chunk
-                bytes.append(&mut _bytes);
-            }
-        }
+        request: AttendeeListRequest,
+    ) -> Result<Vec<(Invite, InviteAttendeeResponse)>, String> {
+        let bytes = Self::read_child_chunks(|chunk, snapshot_id| {
+            Self::get_chunked_invites_child_data(
+                canister_principal,
+                request.clone(),
+                chunk,
+                None,
+                snapshot_id,
+            )
+        })
+        .await?;
 
         // Deserialize the bytes to the correct data type
-        match deserialize::<Vec<InviteAttendeeResponse>>(bytes.clone()) {
-            Ok(_res) => _res,
-            Err(_err) => {
-                ic_cdk::println!("Error: {}", _err);
-                vec![]
-            }
-        }
+        deserialize::<Vec<(Invite, InviteAttendeeResponse)>>(bytes).map_err(|err| err.to_string())
     }
 
     // Inter canister call to fetch the chunked data from the child canister
     async fn get_chunked_invites_child_data(
         canister_principal: Principal,
-        group_identifier: Principal,
+        request: AttendeeListRequest,
         chunk: usize,
         max_bytes_per_chunk: Option<usize>,
-    ) -> (Vec<u8>, (usize, usize)) {
+        snapshot_id: Option<u64>,
+    ) -> Result<ChunkedData, String> {
         // If the max bytes per chunk is not provided, use the default of 2_000_000 (2mb)
         let _max_bytes_per_chunk = max_bytes_per_chunk.unwrap_or(2_000_000);
-        let result: Result<(Vec<u8>, (usize, usize)), _> = call::call(
+        let result: Result<(Result<ChunkedData, ApiError>,), _> = call::call(
             canister_principal,
             "get_chunked_invite_data",
-            (group_identifier, chunk, _max_bytes_per_chunk),
+            (request, chunk, _max_bytes_per_chunk, snapshot_id),
         )
         .await;
 
-        // return the bytes and the chunk info, if there is an error, return an empty vec and chunk info
+        // return the chunk, if there is an error, return the rejection message or the error of the child canister
         match result {
-            Ok(_res) => _res,
-            _ => (vec![], (0, 0)),
+            Err(err) => Err(err.1),
+            Ok((Err(ApiError::BadRequest(_error)),)) if _error.tag == SNAPSHOT_EXPIRED => {
+                Err(SNAPSHOT_EXPIRED.to_string())
+            }
+            Ok((Err(err),)) => Err(format!("{:?}", err)),
+            Ok((Ok(_chunk),)) => Ok(_chunk),
+        }
+    }
+
+    //
+    // GROUPS
+    //
+    // Method used to get the participation of the attendees in the events of a group from the child canisters paged
+    // the participation of an attendee that is stored on multiple child canisters is added up
+    // the caller needs read permission on the attendees of the group
+    pub async fn get_group_attendees(
+        caller: Principal,
+        request: GroupAttendeeRequest,
+    ) -> Result<MergedPagedResponse<GroupAttendee>, ApiError> {
+        Self::can_read(caller, request.group_identifier, request.member_identifier).await?;
+
+        let (attendees, failed_canisters) = Self::get_merged_group_data(
+            "get_group_attendees",
+            &request,
+            |attendee: &GroupAttendee| attendee.principal.to_string(),
+            GroupAttendee::merge,
+        )
+        .await;
+
+        Ok(MergedPagedResponse {
+            page: get_paged_data(attendees, request.limit, request.page),
+            failed_canisters,
+        })
+    }
+
+    // Method used to get the number of attendees and invites per event of a group from the child canisters paged
+    // the caller needs read permission on the attendees of the group
+    pub async fn get_group_event_attendance(
+        caller: Principal,
+        request: GroupAttendeeRequest,
+    ) -> Result<MergedPagedResponse<GroupEventAttendance>, ApiError> {
+        Self::can_read(caller, request.group_identifier, request.member_identifier).await?;
+
+        let (attendance, failed_canisters) = Self::get_merged_group_data(
+            "get_group_event_attendance",
+            &request,
+            |attendance: &GroupEventAttendance| attendance.event_identifier.to_string(),
+            GroupEventAttendance::merge,
+        )
+        .await;
+
+        Ok(MergedPagedResponse {
+            page: get_paged_data(attendance, request.limit, request.page),
+            failed_canisters,
+        })
+    }
+
+    // Method to fetch the group data from all the child canisters and combine the items with the same key
+    // the child canisters return the data sorted on the same key
+    async fn get_merged_group_data<T: CandidType + for<'de> Deserialize<'de>>(
+        method: &str,
+        request: &GroupAttendeeRequest,
+        key: fn(&T) -> String,
+        merge: fn(&mut T, &T),
+    ) -> (Vec<T>, Vec<ChildCanisterError>) {
+        let canisters = Self::get_child_principals();
+
+        let results = Self::join_all_batched(
+            canisters
+                .iter()
+                .map(|canister| Self::get_child_group_data::<T>(*canister, method, request.clone()))
+                .collect(),
+        )
+        .await;
+
+        let (sorted, failed_canisters) =
+            Self::merge_child_results(canisters, results, |a, b| key(a).cmp(&key(b)));
+
+        let mut merged: Vec<T> = vec![];
+        for item in sorted {
+            match merged.last_mut() {
+                Some(_last) if key(_last) == key(&item) => merge(_last, &item),
+                _ => merged.push(item),
+            }
+        }
+
+        (merged, failed_canisters)
+    }
+
+    // Inter canister call to fetch the group data from the child canister
+    async fn get_child_group_data<T: CandidType + for<'de> Deserialize<'de>>(
+        canister_principal: Principal,
+        method: &str,
+        request: GroupAttendeeRequest,
+    ) -> Result<Vec<T>, String> {
+        // Request all the data of the child canister in a single page, the merged data is paged by the parent
+        let request = GroupAttendeeRequest {
+            limit: usize::MAX,
+            page: 1,
+            ..request
+        };
+        let result: Result<(PagedResponse<T>,), _> =
+            call::call(canister_principal, method, (request,)).await;
+
+        result.map(|(_page,)| _page.data).map_err(|err| err.1)
+    }
+
+    //
+    // ATTENDANCE LOGS
+    //
+    // Method used to list the ICRC-3 attendance logs of all the child canisters with their length
+    // an indexer can follow the whole system by following the log of every listed child canister
+    // requires composite queries to be released to mainnet
+    pub async fn get_attendance_logs() -> Vec<AttendanceLog> {
+        let canisters = Self::get_child_principals();
+
+        let results = Self::join_all_batched(
+            canisters
+                .iter()
+                .map(|canister| Self::get_child_log_length(*canister))
+                .collect(),
+        )
+        .await;
+
+        canisters
+            .into_iter()
+            .zip(results)
+            .map(|(canister_identifier, result)| match result {
+                Ok(_log_length) => AttendanceLog {
+                    canister_identifier,
+                    log_length: Some(_log_length),
+                    error: None,
+                },
+                Err(err) => AttendanceLog {
+                    canister_identifier,
+                    log_length: None,
+                    error: Some(err),
+                },
+            })
+            .collect()
+    }
+
+    // Method to get the number of attendees and invites of an event summed over all the child canisters
+    // requires composite queries to be released to mainnet
+    pub async fn get_event_count(event_identifier: Principal) -> MergedEventCount {
+        let canisters = Self::get_child_principals();
+
+        let results = Self::join_all_batched(
+            canisters
+                .iter()
+                .map(|canister| Self::get_child_event_count(*canister, event_identifier))
+                .collect(),
+        )
+        .await;
+
+        let mut count = EventCount {
+            event_identifier,
+            attendees: 0,
+            invites: 0,
+        };
+        let mut failed_canisters: Vec<ChildCanisterError> = vec![];
+
+        for (canister_identifier, result) in canisters.into_iter().zip(results) {
+            match result {
+                Ok(_count) => {
+                    count.attendees += _count.attendees;
+                    count.invites += _count.invites;
+                }
+                Err(error) => failed_canisters.push(ChildCanisterError {
+                    canister_identifier,
+                    error,
+                }),
+            }
+        }
+
+        MergedEventCount {
+            count,
+            failed_canisters,
+        }
+    }
+
+    // Method to get the joins and invites of an event with the check-ins from all the child canisters,
+    // the joins are ordered before the invites and both are ordered by principal
+    pub async fn get_event_export_rows(
+        event_identifier: Principal,
+    ) -> (Vec<EventExportRow>, Vec<ChildCanisterError>) {
+        let canisters = Self::get_child_principals();
+
+        let results = Self::join_all_batched(
+            canisters
+                .iter()
+                .map(|canister| Self::get_child_event_export_rows(*canister, event_identifier))
+                .collect(),
+        )
+        .await;
+
+        let mut rows: Vec<EventExportRow> = vec![];
+        let mut failed_canisters: Vec<ChildCanisterError> = vec![];
+
+        for (canister_identifier, result) in canisters.into_iter().zip(results) {
+            match result {
+                Ok(mut _rows) => rows.append(&mut _rows),
+                Err(error) => failed_canisters.push(ChildCanisterError {
+                    canister_identifier,
+                    error,
+                }),
+            }
+        }
+
+        rows.sort_by(|a, b| {
+            (a.status != AttendeeStatus::Joined, a.principal)
+                .cmp(&(b.status != AttendeeStatus::Joined, b.principal))
+        });
+        (rows, failed_canisters)
+    }
+
+    // Method to get the attendee metrics of all the child canisters merged into a single set of metrics
+    // the inter-canister call failures of this canister are added to the failures of the child canisters
+    pub async fn get_attendee_metrics() -> (AttendeeMetrics, Vec<ChildCanisterError>) {
+        let canisters = Self::get_child_principals();
+
+        let results = Self::join_all_batched(
+            canisters
+                .iter()
+                .map(|canister| Self::get_child_attendee_metrics(*canister))
+                .collect(),
+        )
+        .await;
+
+        let mut metrics: Vec<AttendeeMetrics> = vec![AttendeeMetrics {
+            call_failures: get_call_failures(),
+            ..Default::default()
+        }];
+        let mut failed_canisters: Vec<ChildCanisterError> = vec![];
+
+        for (canister_identifier, result) in canisters.into_iter().zip(results) {
+            match result {
+                Ok(_metrics) => metrics.push(_metrics),
+                Err(error) => failed_canisters.push(ChildCanisterError {
+                    canister_identifier,
+                    error,
+                }),
+            }
+        }
+
+        (merge_metrics(metrics), failed_canisters)
+    }
+
+    // Inter canister call to get the attendee metrics of the child canister
+    pub async fn get_child_attendee_metrics(
+        canister_principal: Principal,
+    ) -> Result<AttendeeMetrics, String> {
+        let result: Result<(AttendeeMetrics,), _> =
+            call::call(canister_principal, "get_attendee_metrics", ()).await;
+
+        result.map(|(_metrics,)| _metrics).map_err(|err| err.1)
+    }
+
+    // Method to send the operator roles to all the child canisters
+    pub async fn propagate_operator_roles(roles: OperatorRoles) -> Vec<ChildCanisterError> {
+        let canisters = Self::get_child_principals();
+
+        let results = Self::join_all_batched(
+            canisters
+                .iter()
+                .map(|canister| Self::set_child_operator_roles(*canister, roles.clone()))
+                .collect(),
+        )
+        .await;
+
+        canisters
+            .into_iter()
+            .zip(results)
+            .filter_map(|(canister_identifier, result)| {
+                result.err().map(|error| ChildCanisterError {
+                    canister_identifier,
+                    error,
+                })
+            })
+            .collect()
+    }
+
+    // Inter canister call to replace the operator roles of the child canister
+    async fn set_child_operator_roles(
+        canister_principal: Principal,
+        roles: OperatorRoles,
+    ) -> Result<(), String> {
+        let result: Result<(OperatorRoles,), _> =
+            call::call(canister_principal, "set_operator_roles", (roles,)).await;
+
+        result.map(|_| ()).map_err(|err| {
+            record_call_failure("set_operator_roles");
+            err.1
+        })
+    }
+
+    // Method to check if a principal is one of the child canisters
+    pub fn is_child_canister(principal: &Principal) -> bool {
+        DATA.with(|data| data.borrow().canisters.contains_key(principal))
+    }
+
+    // Inter canister call to a backup method of the child canister (`restore_data` or `clear_backup`)
+    // the parent is allowed to call the backup methods of its child canisters
+    pub async fn call_child_backup_method(
+        canister_principal: Principal,
+        method: &str,
+    ) -> Result<(), String> {
+        let result: Result<(), _> = call::call(canister_principal, method, ()).await;
+
+        result.map_err(|err| {
+            record_call_failure(method);
+            format!("{:?} - {}", err.0, err.1)
+        })
+    }
+
+    // Inter canister calls to get all the export pages of an event from the child canister
+    async fn get_child_event_export_rows(
+        canister_principal: Principal,
+        event_identifier: Principal,
+    ) -> Result<Vec<EventExportRow>, String> {
+        let mut rows: Vec<EventExportRow> = vec![];
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let result: Result<(CursorPagedResponse<EventExportRow>,), _> = call::call(
+                canister_principal,
+                "get_event_export_page",
+                (event_identifier, MAX_PAGE_LIMIT, cursor),
+            )
+            .await;
+
+            let (mut page,) = result.map_err(|err| {
+                record_call_failure("get_event_export_page");
+                err.1
+            })?;
+            rows.append(&mut page.data);
+
+            match page.next_cursor {
+                None => return Ok(rows),
+                Some(_cursor) => cursor = Some(_cursor),
+            }
+        }
+    }
+
+    // Inter canister call to get the counts of an event from the child canister
+    async fn get_child_event_count(
+        canister_principal: Principal,
+        event_identifier: Principal,
+    ) -> Result<CertifiedEventCounts, String> {
+        let result: Result<(CertifiedEventCounts,), _> = call::call(
+            canister_principal,
+            "get_certified_event_counts",
+            (event_identifier,),
+        )
+        .await;
+
+        result.map(|(_counts,)| _counts).map_err(|err| err.1)
+    }
+
+    // Inter canister call to get the length of the attendance log of the child canister
+    async fn get_child_log_length(canister_principal: Principal) -> Result<Nat, String> {
+        let args: Vec<GetBlocksArgs> = vec![];
+        let result: Result<(GetBlocksResult,), _> =
+            call::call(canister_principal, "icrc3_get_blocks", (args,)).await;
+
+        result
+            .map(|(_result,)| _result.log_length)
+            .map_err(|err| err.1)
+    }
+
+    //
+    // CURSOR PAGES
+    //
+    // Method used to get a page of the members of an event from the child canisters ordered by principal
+    // every child canister returns at most a single page after the cursor, these pages are merged into a single page
+    // requires composite queries to be released to mainnet
+    pub async fn get_joined_child_canister_page(
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> MergedCursorPagedResponse<JoinedAttendeeResponse> {
+        Self::get_merged_child_page(
+            "get_event_attendees_page",
+            event_identifier,
+            limit,
+            cursor,
+            |attendee: &JoinedAttendeeResponse| attendee.principal,
+        )
+        .await
+    }
+
+    // Method used to get a page of the members of an event from the child canisters with the join details
+    // requires composite queries to be released to mainnet
+    pub async fn get_joined_child_canister_page_v2(
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> MergedCursorPagedResponse<JoinedAttendeeResponseV2> {
+        Self::get_merged_child_page(
+            "get_event_attendees_page_v2",
+            event_identifier,
+            limit,
+            cursor,
+            |attendee: &JoinedAttendeeResponseV2| attendee.principal,
+        )
+        .await
+    }
+
+    // Method used to get a page of the invites of an event from the child canisters ordered by principal
+    // requires composite queries to be released to mainnet
+    pub async fn get_invites_child_canister_page(
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> MergedCursorPagedResponse<InviteAttendeeResponse> {
+        Self::get_merged_child_page(
+            "get_event_invites_page",
+            event_identifier,
+            limit,
+            cursor,
+            |invite: &InviteAttendeeResponse| invite.principal,
+        )
+        .await
+    }
+
+    // Method used to get a page of the invites of an event from the child canisters with the invite details
+    // requires composite queries to be released to mainnet
+    pub async fn get_invites_child_canister_page_v2(
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> MergedCursorPagedResponse<InviteAttendeeResponseV2> {
+        Self::get_merged_child_page(
+            "get_event_invites_page_v2",
+            event_identifier,
+            limit,
+            cursor,
+            |invite: &InviteAttendeeResponseV2| invite.principal,
+        )
+        .await
+    }
+
+    // Method to fetch the pages of a paging method from all the child canisters and merge them
+    async fn get_merged_child_page<T: CandidType + for<'de> Deserialize<'de>>(
+        method: &str,
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+        principal: fn(&T) -> Principal,
+    ) -> MergedCursorPagedResponse<T> {
+        let canisters = Self::get_child_principals();
+
+        let results = Self::join_all_batched(
+            canisters
+                .iter()
+                .map(|canister| {
+                    Self::get_child_page::<T>(
+                        *canister,
+                        method,
+                        event_identifier,
+                        limit,
+                        cursor.clone(),
+                    )
+                })
+                .collect(),
+        )
+        .await;
+
+        Self::merge_child_pages(canisters, results, limit, principal)
+    }
+
+    // Inter canister call to fetch a single cursor page from the child canister
+    async fn get_child_page<T: CandidType + for<'de> Deserialize<'de>>(
+        canister_principal: Principal,
+        method: &str,
+        event_identifier: Principal,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> Result<CursorPagedResponse<T>, String> {
+        let result: Result<(CursorPagedResponse<T>,), _> = call::call(
+            canister_principal,
+            method,
+            (event_identifier, limit, cursor),
+        )
+        .await;
+
+        result.map(|(_page,)| _page).map_err(|err| err.1)
+    }
+
+    // Method to merge the pages of the child canisters into a single page
+    // the child canisters order on the principal text and continue after the same cursor, so the first items of the merged pages form the next page
+    fn merge_child_pages<T>(
+        canisters: Vec<Principal>,
+        results: Vec<Result<CursorPagedResponse<T>, String>>,
+        limit: usize,
+        principal: fn(&T) -> Principal,
+    ) -> MergedCursorPagedResponse<T> {
+        let limit = limit.min(MAX_PAGE_LIMIT);
+        let mut data: Vec<T> = vec![];
+        let mut failed_canisters: Vec<ChildCanisterError> = vec![];
+        let mut has_more = false;
+
+        for (canister_identifier, result) in canisters.into_iter().zip(results) {
+            match result {
+                Ok(mut _page) => {
+                    has_more |= _page.next_cursor.is_some();
+                    data.append(&mut _page.data);
+                }
+                Err(error) => failed_canisters.push(ChildCanisterError {
+                    canister_identifier,
+                    error,
+                }),
+            }
+        }
+
+        data.sort_by_cached_key(|item| principal(item).to_string());
+        if data.len() > limit {
+            data.truncate(limit);
+            has_more = true;
+        }
+
+        let next_cursor = match has_more {
+            true => data.last().map(|item| encode_cursor(&principal(item))),
+            false => None,
+        };
+
+        MergedCursorPagedResponse {
+            page: CursorPagedResponse { data, next_cursor },
+            failed_canisters,
+        }
+    }
+
+    //
+    // PERMISSIONS
+    //
+    // Method to check if the caller can read the attendees of the group, the roles are fetched from the group canister
+    pub async fn can_read(
+        caller: Principal,
+        group_identifier: Principal,
+        member_identifier: Principal,
+    ) -> Result<Principal, ApiError> {
+        let no_permission = |message: &str| {
+            api_error(
+                ApiErrorType::Unauthorized,
+                "NO_PERMISSION",
+                message,
+                Self::get_name().as_str(),
+                "can_read",
+                None,
+            )
+        };
+
+        let (principal, member_roles) = get_member_roles(member_identifier, group_identifier)
+            .await
+            .map_err(|err| no_permission(&err))?;
+
+        if caller != principal {
+            return Err(api_error(
+                ApiErrorType::Unauthorized,
+                "PRINCIPAL_MISMATCH",
+                "Principal mismatch",
+                Self::get_name().as_str(),
+                "can_read",
+                None,
+            ));
+        }
+
+        let mut group_roles = get_group_roles(group_identifier)
+            .await
+            .map_err(|err| no_permission(&err))?;
+        group_roles.append(&mut default_roles());
+
+        match has_permission(
+            &member_roles,
+            &PermissionType::Attendee(None),
+            &group_roles,
+            &PermissionActionType::Read,
+        ) {
+            true => Ok(caller),
+            false => Err(no_permission("No permission")),
+        }
+    }
+
+    //
+    // FAN-OUT
+    //
+    // Method to get the principals of all the child canisters
+    fn get_child_principals() -> Vec<Principal> {
+        DATA.with(|data| data.borrow().canisters.keys().copied().collect())
+    }
+
+    // Method to await the futures concurrently in batches of MAX_CONCURRENT_CALLS
+    // the results are returned in the same order as the futures
+    // Method to read all the chunks of the data of a child canister from the same snapshot
+    // the data is read again from the first chunk when it changed on the child canister while the chunks were read
+    async fn read_child_chunks<F: Future<Output = Result<ChunkedData, String>>>(
+        fetch: impl Fn(usize, Option<u64>) -> F,
+    ) -> Result<Vec<u8>, String> {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match Self::read_child_snapshot(&fetch).await {
+                Err(err) if err == SNAPSHOT_EXPIRED && attempts < MAX_SNAPSHOT_ATTEMPTS => continue,
+                result => return result,
+            }
+        }
+    }
+
+    async fn read_child_snapshot<F: Future<Output = Result<ChunkedData, String>>>(
+        fetch: &impl Fn(usize, Option<u64>) -> F,
+    ) -> Result<Vec<u8>, String> {
+        // Do initial fetch of the first chunk and determine the number of chunks
+        let first_chunk = fetch(0, None).await?;
+
+        // If there are more chunks, fetch them in parallel from the same snapshot as the first chunk
+        let snapshot_id = Some(first_chunk.snapshot_id);
+        let other_chunks = Self::join_all_batched(
+            (1..first_chunk.total_chunks)
+                .map(|i| fetch(i, snapshot_id))
+                .collect(),
+        )
+        .await;
+
+        let mut chunks = vec![first_chunk];
+        for chunk in other_chunks {
+            chunks.push(chunk?);
+        }
+
+        // Reassemble the chunks and verify the data against the length and hash of the child canister
+        assemble_chunks(chunks)
+    }
+
+    pub async fn join_all_batched<F: Future>(futures: Vec<F>) -> Vec<F::Output> {
+        let mut results = Vec::with_capacity(futures.len());
+        let mut futures = futures.into_iter().peekable();
+
+        while futures.peek().is_some() {
+            let batch: Vec<F> = futures.by_ref().take(MAX_CONCURRENT_CALLS).collect();
+            results.extend(join_all(batch).await);
+        }
+
+        results
+    }
+
+    // Method to combine the sorted results of the child canisters into a single sorted list and a list of failed child canisters
+    fn merge_child_results<T>(
+        canisters: Vec<Principal>,
+        results: Vec<Result<Vec<T>, String>>,
+        compare: impl Fn(&T, &T) -> Ordering,
+    ) -> (Vec<T>, Vec<ChildCanisterError>) {
+        let mut sorted_lists: Vec<Vec<T>> = vec![];
+        let mut failed_canisters: Vec<ChildCanisterError> = vec![];
+
+        for (canister_identifier, result) in canisters.into_iter().zip(results) {
+            match result {
+                Ok(_data) => sorted_lists.push(_data),
+                Err(error) => {
+                    ic_cdk::println!("Error: {} - {}", canister_identifier, error);
+                    failed_canisters.push(ChildCanisterError {
+                        canister_identifier,
+                        error,
+                    })
+                }
+            }
+        }
+
+        (Self::merge_sorted(sorted_lists, compare), failed_canisters)
+    }
+
+    // Method to merge lists that are sorted with the same compare function into a single sorted list (k-way merge)
+    fn merge_sorted<T>(sorted_lists: Vec<Vec<T>>, compare: impl Fn(&T, &T) -> Ordering) -> Vec<T> {
+        let mut lists: Vec<Peekable<IntoIter<T>>> = sorted_lists
+            .into_iter()
+            .map(|list| list.into_iter().peekable())
+            .collect();
+        let mut merged: Vec<T> = vec![];
+
+        loop {
+            // Find the list with the smallest head, the first list wins on equal items
+            let next = lists
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(index, list)| list.peek().map(|item| (index, item)))
+                .min_by(|(_, a), (_, b)| compare(a, b))
+                .map(|(index, _)| index);
+
+            match next.and_then(|index| lists[index].next()) {
+                None => break,
+                Some(item) => merged.push(item),
+            }
+        }
+
+        merged
+    }
+
+    //
+    // DIRECTORY
+    //
+    // Method called by the child canisters when new attendee records are created (inter-canister call)
+    // existing registrations are kept so the first canister that holds the record stays the owner
+    pub fn register_attendee_canisters(
+        caller: Principal,
+        principals: Vec<Principal>,
+    ) -> Result<(), ApiError> {
+        if DATA.with(|v| !v.borrow().canisters.contains_key(&caller)) {
+            return Err(api_error(
+                ApiErrorType::Unauthorized,
+                "UNKNOWN_CANISTER",
+                "The caller principal isnt known to this canister",
+                &Self::get_name(),
+                "register_attendee_canisters",
+                None,
+            ));
+        }
+
+        principals.into_iter().for_each(|principal| {
+            Self::claim_attendee_canister_for(principal, caller);
+        });
+        Ok(())
+    }
+
+    // Method called by a child canister before it creates a new attendee record
+    // the first child canister that claims a principal holds its record, the other child canisters forward their writes to it
+    pub fn claim_attendee_canister(
+        caller: Principal,
+        principal: Principal,
+    ) -> Result<Principal, ApiError> {
+        if DATA.with(|v| !v.borrow().canisters.contains_key(&caller)) {
+            return Err(api_error(
+                ApiErrorType::Unauthorized,
+                "UNKNOWN_CANISTER",
+                "The caller principal isnt known to this canister",
+                &Self::get_name(),
+                "claim_attendee_canister",
+                None,
+            ));
+        }
+
+        Ok(Self::claim_attendee_canister_for(principal, caller))
+    }
+
+    // Method to register the canister for a principal if there is none yet, returns the registered canister
+    fn claim_attendee_canister_for(principal: Principal, canister: Principal) -> Principal {
+        ATTENDEE_DIRECTORY.with(|directory| {
+            let mut directory = directory.borrow_mut();
+            match directory
+                .get(&principal.to_string())
+                .and_then(|owner| Principal::from_text(owner).ok())
+            {
+                Some(_owner) => _owner,
+                None => {
+                    directory.insert(principal.to_string(), canister.to_string());
+                    canister
+                }
+            }
+        })
+    }
+
+    // Method to get the child canister that holds the attendee record of a principal
+    pub fn get_attendee_canister(principal: Principal) -> Result<Principal, ApiError> {
+        match ATTENDEE_DIRECTORY.with(|directory| {
+            directory
+                .borrow()
+                .get(&principal.to_string())
+                .and_then(|owner| Principal::from_text(owner).ok())
+        }) {
+            None => Err(api_error(
+                ApiErrorType::NotFound,
+                "ATTENDEE_NOT_FOUND",
+                "Attendee not found",
+                &Self::get_name(),
+                "get_attendee_canister",
+                Some(vec![format!("principal - {}", &principal)]),
+            )),
+            Some(_canister) => Ok(_canister),
+        }
+    }
+
+    // Method to get the attendee record of a principal from the child canister that holds it
+    // if the principal is not in the directory (yet) all child canisters are queried
+    // requires composite queries to be released to mainnet
+    pub async fn get_attendee_anywhere(
+        principal: Principal,
+    ) -> Result<(Principal, Attendee), ApiError> {
+        let canisters: Vec<Principal> = match Self::get_attendee_canister(principal) {
+            Ok(_canister) => vec![_canister],
+            Err(_) => Self::get_canisters()
+                .into_iter()
+                .map(|canister| canister.principal)
+                .collect(),
+        };
+
+        for canister in canisters {
+            let result: Result<(Result<(Principal, Attendee), ApiError>,), _> =
+                call::call(canister, "get_attendee_by_principal", (principal,)).await;
+
+            if let Ok((Ok(_attendee),)) = result {
+                return Ok(_attendee);
+            }
+        }
+
+        Err(api_error(
+            ApiErrorType::NotFound,
+            "ATTENDEE_NOT_FOUND",
+            "Attendee not found",
+            &Self::get_name(),
+            "get_attendee_anywhere",
+            Some(vec![format!("principal - {}", &principal)]),
+        ))
+    }
+
+    // Method to get the joined events of a principal from the child canister that holds the attendee record
+    // if the principal is not in the directory (yet) all child canisters are queried
+    // requires composite queries to be released to mainnet
+    pub async fn get_attending_anywhere_v2(
+        principal: Principal,
+    ) -> Result<Vec<JoinedAttendeeResponseV2>, ApiError> {
+        let canisters: Vec<Principal> = match Self::get_attendee_canister(principal) {
+            Ok(_canister) => vec![_canister],
+            Err(_) => Self::get_child_principals(),
+        };
+
+        for canister in canisters {
+            let result: Result<(Result<Vec<JoinedAttendeeResponseV2>, ApiError>,), _> =
+                call::call(canister, "get_attending_from_principal_v2", (principal,)).await;
+
+            if let Ok((Ok(_joined),)) = result {
+                return Ok(_joined);
+            }
+        }
+
+        Err(api_error(
+            ApiErrorType::NotFound,
+            "ATTENDEE_NOT_FOUND",
+            "Attendee not found",
+            &Self::get_name(),
+            "get_attending_anywhere_v2",
+            Some(vec![format!("principal - {}", &principal)]),
+        ))
+    }
+
+    // One-time migration to remove attendee records that are duplicated across the child canisters
+    // the record is merged into the owning canister (directory entry or first canister) before the duplicate is removed
+    pub async fn deduplicate_attendees() -> DeduplicationReport {
+        let mut report = DeduplicationReport::default();
+
+        // Collect the canisters that hold a record for each principal
+        let mut canisters = Self::get_canisters()
+            .into_iter()
+            .map(|canister| canister.principal)
+            .collect::<Vec<Principal>>();
+        canisters.sort();
+
+        let mut holders: HashMap<Principal, Vec<Principal>> = HashMap::new();
+        for canister in canisters {
+            let result: Result<(Vec<Principal>,), _> =
+                call::call(canister, "get_attendee_principals", ()).await;
+
+            match result {
+                Err(err) => report.failed.push((canister, err.1)),
+                Ok((_principals,)) => _principals.into_iter().for_each(|principal| {
+                    holders.entry(principal).or_default().push(canister);
+                }),
+            }
+        }
+
+        for (principal, _holders) in holders {
+            let owner = match Self::get_attendee_canister(principal) {
+                Ok(_owner) if _holders.contains(&_owner) => _owner,
+                _ => _holders[0],
+            };
+            ATTENDEE_DIRECTORY.with(|d| {
+                d.borrow_mut()
+                    .insert(principal.to_string(), owner.to_string())
+            });
+
+            if _holders.len() < 2 {
+                continue;
+            }
+            report.duplicated_principals += 1;
+
+            for duplicate in _holders.into_iter().filter(|holder| holder != &owner) {
+                match Self::merge_duplicate_attendee(principal, duplicate, owner).await {
+                    Ok(_) => report.merged_records += 1,
+                    Err(err) => report.failed.push((principal, err)),
+                }
+            }
+        }
+
+        report
+    }
+
+    // Method to move a duplicate attendee record into the record on the owning canister (inter-canister calls)
+    async fn merge_duplicate_attendee(
+        principal: Principal,
+        duplicate: Principal,
+        owner: Principal,
+    ) -> Result<(), String> {
+        let duplicate_record: Result<(Result<(Principal, Attendee), ApiError>,), _> =
+            call::call(duplicate, "get_attendee_by_principal", (principal,)).await;
+
+        let attendee = match duplicate_record {
+            Err(err) => return Err(err.1),
+            Ok((Err(err),)) => return Err(format!("{:?}", err)),
+            Ok((Ok((_, _attendee)),)) => _attendee,
+        };
+
+        // merge first so the record is never lost when the removal fails
+        let merged: Result<(Result<(Principal, Attendee), ApiError>,), _> =
+            call::call(owner, "merge_attendee", (attendee,)).await;
+
+        match merged {
+            Err(err) => return Err(err.1),
+            Ok((Err(err),)) => return Err(format!("{:?}", err)),
+            Ok(_) => {}
+        }
+
+        let removed: Result<(Result<(), ApiError>,), _> =
+            call::call(duplicate, "remove_attendee", (principal,)).await;
+
+        match removed {
+            Err(err) => Err(err.1),
+            Ok((Err(err),)) => Err(format!("{:?}", err)),
+            Ok(_) => Ok(()),
         }
     }
 }
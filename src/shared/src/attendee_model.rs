@@ -1,11 +1,21 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{borrow::Cow, cmp::Ordering, collections::HashMap};
 
 use candid::{CandidType, Decode, Deserialize, Encode, Principal};
-use ic_scalable_misc::traits::stable_storage_trait::StableStorableTrait;
+use ic_scalable_misc::{
+    enums::{filter_type::FilterType, privacy_type::Privacy, sort_type::SortDirection},
+    models::{date_models::DateRange, paged_response_models::PagedResponse},
+    traits::stable_storage_trait::StableStorableTrait,
+};
 use ic_stable_structures::{storable::Bound, Storable};
 use serde::Serialize;
 
+use crate::history_model::AttendanceAction;
+
 pub type EventIdentifier = Principal;
+
+// Max number of items that are returned in a single cursor page
+pub const MAX_PAGE_LIMIT: usize = 500;
+
 #[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
 pub struct Attendee {
     pub principal: Principal,
@@ -37,14 +47,14 @@ impl Default for Attendee {
     }
 }
 
-#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Join {
     pub group_identifier: Principal,
     pub updated_at: u64,
     pub created_at: u64,
 }
 
-#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Invite {
     pub group_identifier: Principal,
     pub invite_type: InviteType,
@@ -81,3 +91,368 @@ pub struct InviteAttendeeResponse {
     pub principal: Principal,
     pub invite_type: InviteType,
 }
+
+// Response versions
+// the V1 responses are kept as is so existing clients keep working, new fields are only added to a new version
+// every version has its own methods (ex; get_event_attendees_v2) so clients can move to the new version when they are ready
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum AttendeeStatus {
+    // The attendee joined the event
+    Joined,
+    // The attendee is invited by the event owner
+    Invited,
+    // The attendee requested to join the event
+    Requested,
+}
+
+impl From<&InviteType> for AttendeeStatus {
+    fn from(invite_type: &InviteType) -> Self {
+        match invite_type {
+            InviteType::UserRequest => AttendeeStatus::Requested,
+            InviteType::OwnerRequest | InviteType::None => AttendeeStatus::Invited,
+        }
+    }
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct JoinedAttendeeResponseV2 {
+    pub event_identifier: Principal,
+    pub group_identifier: Principal,
+    pub attendee_identifier: Principal,
+    pub principal: Principal,
+    pub status: AttendeeStatus,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl JoinedAttendeeResponseV2 {
+    pub fn new(join: &Join, response: JoinedAttendeeResponse) -> Self {
+        Self {
+            event_identifier: response.event_identifier,
+            group_identifier: join.group_identifier,
+            attendee_identifier: response.attendee_identifier,
+            principal: response.principal,
+            status: AttendeeStatus::Joined,
+            created_at: join.created_at,
+            updated_at: join.updated_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct InviteAttendeeResponseV2 {
+    pub event_identifier: Principal,
+    pub group_identifier: Principal,
+    pub attendee_identifier: Principal,
+    pub principal: Principal,
+    pub invite_type: InviteType,
+    pub status: AttendeeStatus,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl InviteAttendeeResponseV2 {
+    pub fn new(invite: &Invite, response: InviteAttendeeResponse) -> Self {
+        Self {
+            event_identifier: response.event_identifier,
+            group_identifier: invite.group_identifier,
+            attendee_identifier: response.attendee_identifier,
+            principal: response.principal,
+            status: AttendeeStatus::from(&invite.invite_type),
+            invite_type: invite.invite_type.clone(),
+            created_at: invite.created_at,
+            updated_at: invite.updated_at,
+        }
+    }
+}
+
+// Write that is forwarded to the child canister that holds the attendee record
+// used to prevent duplicate attendee records across sibling child canisters
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub enum ForwardedMutation {
+    Join {
+        principal: Principal,
+        event_identifier: Principal,
+        group_identifier: Principal,
+        event_privacy: Privacy,
+    },
+    Invite {
+        principal: Principal,
+        event_identifier: Principal,
+        group_identifier: Principal,
+    },
+    AddOwner {
+        principal: Principal,
+        event_identifier: Principal,
+        group_identifier: Principal,
+    },
+    // The action is Left when the attendee left the event, or Removed when an admin removed the attendee
+    RemoveJoin {
+        principal: Principal,
+        event_identifier: Principal,
+        action: AttendanceAction,
+    },
+    RemoveInvite {
+        principal: Principal,
+        event_identifier: Principal,
+    },
+    AcceptUserRequest {
+        principal: Principal,
+        event_identifier: Principal,
+    },
+    AcceptOwnerRequest {
+        principal: Principal,
+        event_identifier: Principal,
+    },
+    CheckIn {
+        principal: Principal,
+        event_identifier: Principal,
+    },
+}
+
+// Result of the attendee deduplication across the child canisters
+#[derive(Clone, Debug, Default, CandidType, Deserialize, Serialize)]
+pub struct DeduplicationReport {
+    pub duplicated_principals: u64,
+    pub merged_records: u64,
+    pub failed: Vec<(Principal, String)>,
+}
+
+// Child canister that could not be reached or returned invalid data during a parent fan-out
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct ChildCanisterError {
+    pub canister_identifier: Principal,
+    pub error: String,
+}
+
+// Paged data that is merged from all the child canisters
+// the child canisters that failed are reported instead of being left out silently
+#[derive(Debug, CandidType, Deserialize, Serialize)]
+pub struct MergedPagedResponse<T> {
+    pub page: PagedResponse<T>,
+    pub failed_canisters: Vec<ChildCanisterError>,
+}
+
+// Number of attendees and invites of an event
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct EventCount {
+    pub event_identifier: Principal,
+    pub attendees: u64,
+    pub invites: u64,
+}
+
+// Event count that is summed over all the child canisters
+#[derive(Debug, CandidType, Deserialize, Serialize)]
+pub struct MergedEventCount {
+    pub count: EventCount,
+    pub failed_canisters: Vec<ChildCanisterError>,
+}
+
+// Single page of data and the cursor to get the next page, none if there are no more pages
+// the cursor is opaque for the caller and should be passed as is to get the next page
+#[derive(Debug, CandidType, Deserialize, Serialize)]
+pub struct CursorPagedResponse<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+// Cursor paged data that is merged from all the child canisters
+#[derive(Debug, CandidType, Deserialize, Serialize)]
+pub struct MergedCursorPagedResponse<T> {
+    pub page: CursorPagedResponse<T>,
+    pub failed_canisters: Vec<ChildCanisterError>,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub enum AttendeeSort {
+    CreatedOn(SortDirection),
+    UpdatedOn(SortDirection),
+    Principal(SortDirection),
+}
+
+impl Default for AttendeeSort {
+    fn default() -> Self {
+        AttendeeSort::CreatedOn(SortDirection::default())
+    }
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub enum AttendeeFilter {
+    // Only applies to invites, joins never match this filter
+    InviteType(InviteType),
+    GroupIdentifier(Principal),
+    Principals(Vec<Principal>),
+    CreatedOn(DateRange),
+    UpdatedOn(DateRange),
+}
+
+// Request used to get the members or invites of an event filtered, sorted and paged
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct AttendeeListRequest {
+    pub event_identifier: Principal,
+    pub filters: Vec<AttendeeFilter>,
+    pub filter_type: FilterType,
+    pub sort: AttendeeSort,
+    pub limit: usize,
+    pub page: usize,
+}
+
+// Request used to get the attendees or the attendance of all the events of a group paged
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct GroupAttendeeRequest {
+    pub group_identifier: Principal,
+    // Member of the group that needs read permission on the attendees of the group
+    pub member_identifier: Principal,
+    // Only count the events that did not end yet, events of which the dates are not fetched yet are skipped
+    pub upcoming_only: bool,
+    pub limit: usize,
+    pub page: usize,
+}
+
+// Participation of a single attendee in the events of a group
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct GroupAttendee {
+    pub group_identifier: Principal,
+    pub principal: Principal,
+    // Number of events of the group the attendee joined
+    pub joined_count: usize,
+    // Number of outstanding invites for events of the group
+    pub invite_count: usize,
+    pub first_joined_at: Option<u64>,
+    pub last_joined_at: Option<u64>,
+}
+
+impl GroupAttendee {
+    // Method to add the participation of the same attendee from another child canister
+    pub fn merge(&mut self, other: &GroupAttendee) {
+        self.joined_count += other.joined_count;
+        self.invite_count += other.invite_count;
+        self.first_joined_at = match (self.first_joined_at, other.first_joined_at) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.last_joined_at = self.last_joined_at.max(other.last_joined_at);
+    }
+}
+
+// Number of attendees and invites of a single event of a group
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct GroupEventAttendance {
+    pub group_identifier: Principal,
+    pub event_identifier: Principal,
+    pub attendee_count: usize,
+    pub invite_count: usize,
+}
+
+impl GroupEventAttendance {
+    // Method to add the attendance of the same event from another child canister
+    pub fn merge(&mut self, other: &GroupEventAttendance) {
+        self.attendee_count += other.attendee_count;
+        self.invite_count += other.invite_count;
+    }
+}
+
+// Join or invite of a single attendee that can be filtered and sorted
+pub trait AttendeeListEntry {
+    fn principal(&self) -> Principal;
+    fn group_identifier(&self) -> Principal;
+    fn invite_type(&self) -> Option<InviteType>;
+    fn created_at(&self) -> u64;
+    fn updated_at(&self) -> u64;
+}
+
+impl AttendeeListEntry for (Join, JoinedAttendeeResponse) {
+    fn principal(&self) -> Principal {
+        self.1.principal
+    }
+
+    fn group_identifier(&self) -> Principal {
+        self.0.group_identifier
+    }
+
+    fn invite_type(&self) -> Option<InviteType> {
+        None
+    }
+
+    fn created_at(&self) -> u64 {
+        self.0.created_at
+    }
+
+    fn updated_at(&self) -> u64 {
+        self.0.updated_at
+    }
+}
+
+impl AttendeeListEntry for (Invite, InviteAttendeeResponse) {
+    fn principal(&self) -> Principal {
+        self.1.principal
+    }
+
+    fn group_identifier(&self) -> Principal {
+        self.0.group_identifier
+    }
+
+    fn invite_type(&self) -> Option<InviteType> {
+        Some(self.0.invite_type.clone())
+    }
+
+    fn created_at(&self) -> u64 {
+        self.0.created_at
+    }
+
+    fn updated_at(&self) -> u64 {
+        self.0.updated_at
+    }
+}
+
+impl AttendeeFilter {
+    pub fn is_match<T: AttendeeListEntry>(&self, entry: &T) -> bool {
+        use AttendeeFilter::*;
+        match self {
+            InviteType(value) => entry.invite_type().as_ref() == Some(value),
+            GroupIdentifier(value) => &entry.group_identifier() == value,
+            Principals(value) => value.contains(&entry.principal()),
+            CreatedOn(value) => {
+                entry.created_at() >= value.start_date && entry.created_at() <= value.end_date
+            }
+            UpdatedOn(value) => {
+                entry.updated_at() >= value.start_date && entry.updated_at() <= value.end_date
+            }
+        }
+    }
+}
+
+impl AttendeeListRequest {
+    // Method to check if an entry matches the filters, no filters matches every entry
+    pub fn is_match<T: AttendeeListEntry>(&self, entry: &T) -> bool {
+        if self.filters.is_empty() {
+            return true;
+        }
+
+        match self.filter_type {
+            FilterType::And => self.filters.iter().all(|filter| filter.is_match(entry)),
+            FilterType::Or => self.filters.iter().any(|filter| filter.is_match(entry)),
+        }
+    }
+}
+
+impl AttendeeSort {
+    // Method to compare two entries, the principal text is used as tie breaker
+    // so the child canisters and the parent canister produce the exact same order
+    pub fn compare<T: AttendeeListEntry>(&self, a: &T, b: &T) -> Ordering {
+        use AttendeeSort::*;
+        let (ordering, direction) = match self {
+            CreatedOn(direction) => (a.created_at().cmp(&b.created_at()), direction),
+            UpdatedOn(direction) => (a.updated_at().cmp(&b.updated_at()), direction),
+            Principal(direction) => (Ordering::Equal, direction),
+        };
+        let ordering =
+            ordering.then_with(|| a.principal().to_string().cmp(&b.principal().to_string()));
+
+        match direction {
+            SortDirection::Asc => ordering,
+            SortDirection::Desc => ordering.reverse(),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeSet;
+
+use candid::Principal;
+
+use crate::{
+    attendee_model::{Attendee, InviteType},
+    audit_model::AuditAction,
+};
+
+// Method to get the audit actions of a write to an attendee record, one action with the event and group per changed event
+// the actor is the attendee when the attendee changed its own record, otherwise an admin (or canister) changed the record
+pub fn get_changed_actions(
+    actor_is_attendee: bool,
+    previous: &Attendee,
+    current: &Attendee,
+) -> Vec<(AuditAction, Principal, Principal)> {
+    use AuditAction::*;
+    let events: BTreeSet<&Principal> = previous
+        .joined
+        .keys()
+        .chain(current.joined.keys())
+        .chain(previous.invites.keys())
+        .chain(current.invites.keys())
+        .collect();
+
+    events
+        .into_iter()
+        .filter_map(|event_identifier| {
+            let previous_join = previous.joined.get(event_identifier);
+            let current_join = current.joined.get(event_identifier);
+            let previous_invite = previous.invites.get(event_identifier);
+            let current_invite = current.invites.get(event_identifier);
+
+            let (action, group_identifier) = match (previous_join, current_join) {
+                // A join that replaces an invite is an accepted invite
+                (None, Some(_join)) => match previous_invite.filter(|_| current_invite.is_none()) {
+                    Some(_invite) if _invite.invite_type == InviteType::UserRequest => {
+                        (AcceptUserRequest, _join.group_identifier)
+                    }
+                    Some(_invite) if _invite.invite_type == InviteType::OwnerRequest => {
+                        (AcceptOwnerRequest, _join.group_identifier)
+                    }
+                    _ => (Join, _join.group_identifier),
+                },
+                (Some(_join), None) => match actor_is_attendee {
+                    true => (Leave, _join.group_identifier),
+                    false => (RemoveAttendee, _join.group_identifier),
+                },
+                (Some(_previous), Some(_join)) if _previous != _join => {
+                    (Join, _join.group_identifier)
+                }
+                _ => match (previous_invite, current_invite) {
+                    (_, Some(_invite)) if previous_invite != current_invite => {
+                        match _invite.invite_type {
+                            // A user request is the request of the attendee to join a private event
+                            InviteType::UserRequest => (Join, _invite.group_identifier),
+                            _ => (Invite, _invite.group_identifier),
+                        }
+                    }
+                    (Some(_invite), None) => match actor_is_attendee {
+                        true => (RemoveInvite, _invite.group_identifier),
+                        false => (RemoveAttendeeInvite, _invite.group_identifier),
+                    },
+                    _ => return None,
+                },
+            };
+
+            Some((action, *event_identifier, group_identifier))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attendee_model::{Invite, Join};
+
+    fn principal(byte: u8) -> Principal {
+        Principal::from_slice(&[byte])
+    }
+
+    fn join(group: u8) -> Join {
+        Join {
+            group_identifier: principal(group),
+            updated_at: 0,
+            created_at: 0,
+        }
+    }
+
+    fn invite(group: u8, invite_type: InviteType) -> Invite {
+        Invite {
+            group_identifier: principal(group),
+            invite_type,
+            updated_at: 0,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn derives_the_actions_of_a_write() {
+        let mut previous = Attendee::default();
+        previous.joined.insert(principal(1), join(10));
+        previous
+            .invites
+            .insert(principal(2), invite(10, InviteType::OwnerRequest));
+        previous
+            .invites
+            .insert(principal(3), invite(11, InviteType::UserRequest));
+
+        let mut current = previous.clone();
+        current.joined.remove(&principal(1));
+        current.invites.remove(&principal(2));
+        current.joined.insert(principal(2), join(10));
+        current
+            .invites
+            .insert(principal(4), invite(12, InviteType::OwnerRequest));
+
+        assert_eq!(
+            get_changed_actions(true, &previous, &current),
+            vec![
+                (AuditAction::Leave, principal(1), principal(10)),
+                (AuditAction::AcceptOwnerRequest, principal(2), principal(10)),
+                (AuditAction::Invite, principal(4), principal(12)),
+            ]
+        );
+
+        assert_eq!(
+            get_changed_actions(false, &current, &Attendee::default()),
+            vec![
+                (AuditAction::RemoveAttendee, principal(2), principal(10)),
+                (
+                    AuditAction::RemoveAttendeeInvite,
+                    principal(3),
+                    principal(11)
+                ),
+                (
+                    AuditAction::RemoveAttendeeInvite,
+                    principal(4),
+                    principal(12)
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_scalable_misc::traits::stable_storage_trait::StableStorableTrait;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Serialize;
+
+// Single mutation of the attendee data, entries are only appended and removed by the retention policy
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct AuditEntry {
+    pub sequence: u64,
+    // The principal that called the method
+    pub actor: Principal,
+    pub action: AuditAction,
+    // The principal of the attendee that is changed
+    pub target: Principal,
+    pub event_identifier: Option<Principal>,
+    pub group_identifier: Option<Principal>,
+    // The error message if the mutation failed
+    pub result: Result<(), String>,
+    pub created_at: u64,
+}
+
+impl StableStorableTrait for AuditEntry {}
+
+impl Storable for AuditEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum AuditAction {
+    Join,
+    Invite,
+    AcceptUserRequest,
+    AcceptOwnerRequest,
+    Leave,
+    RemoveAttendee,
+    RemoveInvite,
+    RemoveAttendeeInvite,
+    AddOwner,
+    CheckIn,
+    ApplyForwardedMutation,
+    MergeAttendee,
+    RemoveAttendeeRecord,
+    RestoreData,
+}
@@ -0,0 +1,219 @@
+use candid::Principal;
+use ic_scalable_misc::{enums::location_type::Location, models::address_models::Address};
+
+use crate::calendar_model::CachedEvent;
+
+// Lines of an iCalendar file should not be longer than 75 octets (RFC 5545 section 3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+// Method to create an iCalendar file of the events, the dates of the events are in nanoseconds
+pub fn to_ics(
+    calendar_name: &str,
+    canister_identifier: Principal,
+    events: &[CachedEvent],
+) -> String {
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Catalyze//Event attendees//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(calendar_name)),
+    ];
+
+    for event in events {
+        let details = &event.details;
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:{}@{}",
+            event.event_identifier, canister_identifier
+        ));
+        lines.push(format!("DTSTAMP:{}", format_date_time(event.fetched_at)));
+        lines.push(format!(
+            "DTSTART:{}",
+            format_date_time(details.date.start_date)
+        ));
+        // An event without an end date (or an invalid one) ends at its start date
+        lines.push(format!(
+            "DTEND:{}",
+            format_date_time(details.date.end_date.max(details.date.start_date))
+        ));
+        lines.push(format!("SUMMARY:{}", escape_text(&details.name)));
+        if !details.description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape_text(&details.description)));
+        }
+        if let Some(_location) = location_text(&details.location) {
+            lines.push(format!("LOCATION:{}", escape_text(&_location)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+// Method to format a timestamp in nanoseconds as an UTC date time, ex; `20240131T093000Z`
+pub fn format_date_time(nanos: u64) -> String {
+    let seconds = nanos / 1_000_000_000;
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let seconds_of_day = seconds % 86_400;
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        (seconds_of_day % 3600) / 60,
+        seconds_of_day % 60
+    )
+}
+
+// Method to escape the special characters of a text value (RFC 5545 section 3.3.11)
+pub fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+// Method to split a line into lines of at most 75 octets, continuation lines start with a space
+// the line is only split on character boundaries so multi byte characters stay intact
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut line_octets = 0;
+    for character in line.chars() {
+        if line_octets + character.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the length of the continuation line
+            line_octets = 1;
+        }
+        folded.push(character);
+        line_octets += character.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn location_text(location: &Location) -> Option<String> {
+    let text = match location {
+        Location::None => String::new(),
+        Location::Digital(_url) => _url.clone(),
+        Location::Physical(_physical) => address_text(&_physical.address),
+        Location::MultiLocation(_multi) => vec![
+            address_text(&_multi.physical.address),
+            _multi.digital.clone(),
+        ]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<String>>()
+        .join(" - "),
+    };
+
+    match text.is_empty() {
+        true => None,
+        false => Some(text),
+    }
+}
+
+fn address_text(address: &Address) -> String {
+    vec![
+        address.label.clone(),
+        format!("{} {}", address.street, address.house_number),
+        address.city.clone(),
+        address.country.clone(),
+    ]
+    .into_iter()
+    .map(|part| part.trim().to_string())
+    .filter(|part| !part.is_empty())
+    .collect::<Vec<String>>()
+    .join(", ")
+}
+
+// Converts the number of days since 1970-01-01 to a (year, month, day) date
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_scalable_misc::models::date_models::DateRange;
+
+    use super::*;
+    use crate::calendar_model::EventDetails;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn formats_dates_in_utc() {
+        assert_eq!(format_date_time(0), "19700101T000000Z");
+        // 2024-02-29 12:34:56 UTC
+        assert_eq!(format_date_time(1_709_210_096 * SECOND), "20240229T123456Z");
+        // 2023-12-31 23:59:59 UTC
+        assert_eq!(format_date_time(1_704_067_199 * SECOND), "20231231T235959Z");
+    }
+
+    #[test]
+    fn escapes_and_folds_lines() {
+        assert_eq!(escape_text("a,b;c\\d\r\ne"), "a\\,b\\;c\\\\d\\ne");
+
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        let folded = fold_line(&line);
+        for part in folded.split("\r\n").filter(|part| !part.is_empty()) {
+            assert!(part.len() <= MAX_LINE_OCTETS);
+        }
+        assert_eq!(folded.replace("\r\n ", "").trim_end(), line);
+    }
+
+    #[test]
+    fn creates_a_calendar_with_an_event_per_cached_event() {
+        let event = CachedEvent {
+            event_identifier: Principal::from_slice(&[2, 1]),
+            group_identifier: Principal::from_slice(&[3, 1]),
+            details: EventDetails {
+                name: "Meetup, Amsterdam".to_string(),
+                description: String::new(),
+                date: DateRange {
+                    start_date: 1_709_210_096 * SECOND,
+                    end_date: 0,
+                },
+                location: Location::Digital("https://example.com".to_string()),
+            },
+            fetched_at: 0,
+        };
+
+        let ics = to_ics("Events", Principal::from_slice(&[1, 1]), &[event]);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("SUMMARY:Meetup\\, Amsterdam\r\n"));
+        assert!(ics.contains("DTEND:20240229T123456Z\r\n"));
+        assert!(ics.contains("LOCATION:https://example.com\r\n"));
+        assert!(!ics.contains("DESCRIPTION"));
+    }
+}
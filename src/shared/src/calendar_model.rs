@@ -0,0 +1,64 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_scalable_misc::{
+    enums::location_type::Location, models::date_models::DateRange,
+    traits::stable_storage_trait::StableStorableTrait,
+};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Serialize;
+
+// Secret token that gives access to the calendar feed of a principal, keyed by the token in stable memory
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct CalendarFeedToken {
+    pub token: String,
+    pub principal: Principal,
+    pub created_at: u64,
+}
+
+impl StableStorableTrait for CalendarFeedToken {}
+
+impl Storable for CalendarFeedToken {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Subset of the event response of the event canisters that is used for the calendar feed,
+// the other fields of the response are skipped when decoding
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct EventDetails {
+    pub name: String,
+    pub description: String,
+    pub date: DateRange,
+    pub location: Location,
+}
+
+// Event details that are fetched from the event canister, cached per event in stable memory
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct CachedEvent {
+    pub event_identifier: Principal,
+    pub group_identifier: Principal,
+    pub details: EventDetails,
+    pub fetched_at: u64,
+}
+
+impl StableStorableTrait for CachedEvent {}
+
+impl Storable for CachedEvent {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
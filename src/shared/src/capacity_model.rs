@@ -0,0 +1,23 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+// Cycles a new child canister is created with when no settings are stored
+pub const DEFAULT_CHILD_CANISTER_CYCLES: u64 = 2_000_000_000_000;
+
+// Settings used by the parent when it spawns a new child canister
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CapacitySettings {
+    // Cycles send with the creation of a new child canister
+    pub child_canister_cycles: u64,
+    // Max number of child canisters, no limit when not set
+    pub max_child_canisters: Option<u64>,
+}
+
+impl Default for CapacitySettings {
+    fn default() -> Self {
+        Self {
+            child_canister_cycles: DEFAULT_CHILD_CANISTER_CYCLES,
+            max_child_canisters: None,
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+
+use candid::Principal;
+use serde_cbor::Value as CborValue;
+
+use crate::{
+    attendee_model::AttendeeStatus,
+    hash_tree::{decode_leb128, HashTree},
+};
+
+// Labels of the certified tree of the child canisters
+pub const COUNTS_LABEL: &[u8] = b"counts";
+pub const ATTENDEES_LABEL: &[u8] = b"attendees";
+pub const INVITES_LABEL: &[u8] = b"invites";
+pub const MEMBERS_LABEL: &[u8] = b"members";
+// Labels of the tip of the attendance block log, as described in the ICRC-3 standard
+pub const LAST_BLOCK_INDEX_LABEL: &[u8] = b"last_block_index";
+pub const LAST_BLOCK_HASH_LABEL: &[u8] = b"last_block_hash";
+
+// Value of the membership leaf of an attendee
+pub fn membership_status_value(status: &AttendeeStatus) -> &'static [u8] {
+    match status {
+        AttendeeStatus::Joined => b"joined",
+        AttendeeStatus::Invited => b"invited",
+        AttendeeStatus::Requested => b"requested",
+    }
+}
+
+// Method to verify a value that is certified by a canister, returns the value of the leaf at the path of the witness
+// checks that the root hash of the witness is the certified data in the certificate and optionally that the certificate is recent,
+// the signature of the certificate is not checked here, verify it with an agent (ex; `Certificate::verify` in `ic-certification`)
+pub fn verify_certified_value(
+    certificate: &[u8],
+    witness: &[u8],
+    canister_identifier: Principal,
+    path: &[&[u8]],
+    max_certificate_age: Option<(u64, u64)>,
+) -> Result<Vec<u8>, String> {
+    let certificate_tree = certificate_tree(certificate)?;
+    let certified_data = certificate_tree
+        .lookup_path(&[
+            b"canister",
+            canister_identifier.as_slice(),
+            b"certified_data",
+        ])
+        .map_err(|_| "The certificate doesnt contain the certified data".to_string())?;
+
+    // The optional age check is passed as (current time, max age) in nanoseconds
+    if let Some((now, max_age)) = max_certificate_age {
+        let certificate_time = certificate_tree
+            .lookup_path(&[b"time"])
+            .ok()
+            .and_then(decode_leb128)
+            .ok_or_else(|| "The certificate doesnt contain a valid time".to_string())?;
+        if now.saturating_sub(certificate_time) > max_age {
+            return Err("The certificate is too old".to_string());
+        }
+    }
+
+    let witness = HashTree::from_cbor(witness)?;
+    if witness.digest().as_slice() != certified_data {
+        return Err("The witness doesnt match the certified data".to_string());
+    }
+
+    witness
+        .lookup_path(path)
+        .map(|value| value.to_vec())
+        .map_err(|_| "The value is not part of the witness".to_string())
+}
+
+// Method to verify the certified attendee and invite counts of an event, returns (attendees, invites)
+pub fn verify_certified_counts(
+    certificate: &[u8],
+    witness: &[u8],
+    canister_identifier: Principal,
+    event_identifier: Principal,
+    max_certificate_age: Option<(u64, u64)>,
+) -> Result<(u64, u64), String> {
+    let count = |label: &[u8]| {
+        let value = verify_certified_value(
+            certificate,
+            witness,
+            canister_identifier,
+            &[COUNTS_LABEL, event_identifier.as_slice(), label],
+            max_certificate_age,
+        )?;
+        decode_leb128(&value).ok_or_else(|| "The count is not a valid number".to_string())
+    };
+
+    Ok((count(ATTENDEES_LABEL)?, count(INVITES_LABEL)?))
+}
+
+// Method to verify the certified status of a principal for an event, the status is found at `members / event / principal`
+pub fn verify_membership(
+    certificate: &[u8],
+    witness: &[u8],
+    canister_identifier: Principal,
+    event_identifier: Principal,
+    principal: Principal,
+    max_certificate_age: Option<(u64, u64)>,
+) -> Result<AttendeeStatus, String> {
+    let value = verify_certified_value(
+        certificate,
+        witness,
+        canister_identifier,
+        &[
+            MEMBERS_LABEL,
+            event_identifier.as_slice(),
+            principal.as_slice(),
+        ],
+        max_certificate_age,
+    )?;
+
+    vec![
+        AttendeeStatus::Joined,
+        AttendeeStatus::Invited,
+        AttendeeStatus::Requested,
+    ]
+    .into_iter()
+    .find(|status| membership_status_value(status) == value.as_slice())
+    .ok_or_else(|| "The membership status is not valid".to_string())
+}
+
+// Method to read the hash tree from a CBOR encoded certificate
+fn certificate_tree(certificate: &[u8]) -> Result<HashTree, String> {
+    let value: CborValue = serde_cbor::from_slice(certificate).map_err(|err| err.to_string())?;
+    let fields: BTreeMap<CborValue, CborValue> = match value {
+        CborValue::Tag(_, _value) => match *_value {
+            CborValue::Map(_fields) => _fields,
+            _ => return Err("The certificate is not a map".to_string()),
+        },
+        CborValue::Map(_fields) => _fields,
+        _ => return Err("The certificate is not a map".to_string()),
+    };
+
+    match fields.get(&CborValue::Text("tree".to_string())) {
+        Some(_tree) => HashTree::from_cbor_value(_tree),
+        None => Err("The certificate doesnt contain a tree".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_cbor::Value as CborValue;
+
+    use super::*;
+    use crate::hash_tree::encode_leb128;
+
+    fn certificate(canister_identifier: Principal, certified_data: &[u8], time: u64) -> Vec<u8> {
+        let tree = HashTree::fork(
+            HashTree::labeled(
+                b"canister",
+                HashTree::labeled(
+                    canister_identifier.as_slice(),
+                    HashTree::labeled(b"certified_data", HashTree::Leaf(certified_data.to_vec())),
+                ),
+            ),
+            HashTree::labeled(b"time", HashTree::Leaf(encode_leb128(time))),
+        );
+        let tree: CborValue = serde_cbor::from_slice(&tree.to_cbor()).unwrap();
+
+        let mut fields = BTreeMap::new();
+        fields.insert(CborValue::Text("tree".to_string()), tree);
+        fields.insert(
+            CborValue::Text("signature".to_string()),
+            CborValue::Bytes(vec![]),
+        );
+        serde_cbor::to_vec(&CborValue::Map(fields)).unwrap()
+    }
+
+    #[test]
+    fn verifies_counts_against_the_certified_data() {
+        let canister_identifier = Principal::from_slice(&[1, 1]);
+        let event_identifier = Principal::from_slice(&[2, 1]);
+        let witness = HashTree::labeled(
+            COUNTS_LABEL,
+            HashTree::fork(
+                HashTree::Pruned([0; 32]),
+                HashTree::labeled(
+                    event_identifier.as_slice(),
+                    HashTree::fork(
+                        HashTree::labeled(ATTENDEES_LABEL, HashTree::Leaf(encode_leb128(12))),
+                        HashTree::labeled(INVITES_LABEL, HashTree::Leaf(encode_leb128(3))),
+                    ),
+                ),
+            ),
+        );
+        let certificate = certificate(canister_identifier, &witness.digest(), 100);
+
+        assert_eq!(
+            verify_certified_counts(
+                &certificate,
+                &witness.to_cbor(),
+                canister_identifier,
+                event_identifier,
+                Some((150, 100)),
+            ),
+            Ok((12, 3))
+        );
+        assert!(verify_certified_counts(
+            &certificate,
+            &witness.to_cbor(),
+            canister_identifier,
+            event_identifier,
+            Some((250, 100)),
+        )
+        .is_err());
+
+        // A witness with a changed count doesnt match the certified data
+        let tampered = HashTree::labeled(
+            COUNTS_LABEL,
+            HashTree::labeled(
+                event_identifier.as_slice(),
+                HashTree::fork(
+                    HashTree::labeled(ATTENDEES_LABEL, HashTree::Leaf(encode_leb128(13))),
+                    HashTree::labeled(INVITES_LABEL, HashTree::Leaf(encode_leb128(3))),
+                ),
+            ),
+        );
+        assert!(verify_certified_counts(
+            &certificate,
+            &tampered.to_cbor(),
+            canister_identifier,
+            event_identifier,
+            None,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn verifies_membership_against_the_certified_data() {
+        let canister_identifier = Principal::from_slice(&[1, 1]);
+        let event_identifier = Principal::from_slice(&[2, 1]);
+        let principal = Principal::from_slice(&[3, 2]);
+        let witness = HashTree::fork(
+            HashTree::Pruned([0; 32]),
+            HashTree::labeled(
+                MEMBERS_LABEL,
+                HashTree::labeled(
+                    event_identifier.as_slice(),
+                    HashTree::fork(
+                        HashTree::Pruned([1; 32]),
+                        HashTree::labeled(
+                            principal.as_slice(),
+                            HashTree::Leaf(
+                                membership_status_value(&AttendeeStatus::Joined).to_vec(),
+                            ),
+                        ),
+                    ),
+                ),
+            ),
+        );
+        let certificate = certificate(canister_identifier, &witness.digest(), 100);
+
+        assert_eq!(
+            verify_membership(
+                &certificate,
+                &witness.to_cbor(),
+                canister_identifier,
+                event_identifier,
+                principal,
+                None,
+            ),
+            Ok(AttendeeStatus::Joined)
+        );
+
+        // The principal could be part of the pruned subtree, so it can't be verified
+        assert!(verify_membership(
+            &certificate,
+            &witness.to_cbor(),
+            canister_identifier,
+            event_identifier,
+            Principal::from_slice(&[4, 2]),
+            None,
+        )
+        .is_err());
+    }
+}
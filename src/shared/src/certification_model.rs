@@ -0,0 +1,31 @@
+use candid::{CandidType, Deserialize, Principal};
+use serde::Serialize;
+use serde_bytes::ByteBuf;
+
+use crate::attendee_model::AttendeeStatus;
+
+// Attendee and invite counts of an event with the proof that they are certified by the child canister
+// verify them with `verify_certified_counts` from the certification helper
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct CertifiedEventCounts {
+    pub event_identifier: Principal,
+    pub attendees: u64,
+    pub invites: u64,
+    // CBOR encoded certificate of the subnet, only available for query calls
+    pub certificate: Option<ByteBuf>,
+    // CBOR encoded hash tree with the path to the counts of the event
+    pub witness: ByteBuf,
+}
+
+// Status of a principal for an event with the proof that it is certified by the child canister
+// verify it with `verify_membership` from the certification helper
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct MembershipProof {
+    pub principal: Principal,
+    pub event_identifier: Principal,
+    pub status: AttendeeStatus,
+    // CBOR encoded certificate of the subnet, only available for query calls
+    pub certificate: Option<ByteBuf>,
+    // CBOR encoded hash tree with the path to the status of the principal
+    pub witness: ByteBuf,
+}
@@ -0,0 +1,383 @@
+use std::cmp::Ordering;
+
+use sha2::{Digest, Sha256};
+
+use crate::hash_tree::{empty_hash, fork_hash, labeled_hash, leaf_hash, Hash, HashTree};
+
+// Values that can be stored in the certified map, nested maps are values as well
+pub trait AsHashTree {
+    fn root_hash(&self) -> Hash;
+    fn as_hash_tree(&self) -> HashTree;
+}
+
+impl AsHashTree for Vec<u8> {
+    fn root_hash(&self) -> Hash {
+        leaf_hash(self)
+    }
+
+    fn as_hash_tree(&self) -> HashTree {
+        HashTree::Leaf(self.clone())
+    }
+}
+
+// Map with a root hash that is updated incrementally on every insert and removal
+// the entries are kept in a treap ordered by key, the priority of a node is derived from the hash of its key,
+// so the shape of the tree (and the root hash) only depends on the entries and not on the order they are inserted in
+#[derive(Clone, Debug)]
+pub struct CertifiedMap<V> {
+    root: Option<Box<Node<V>>>,
+    len: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Node<V> {
+    key: Vec<u8>,
+    value: V,
+    priority: u64,
+    left: Option<Box<Node<V>>>,
+    right: Option<Box<Node<V>>>,
+    // Hash of the subtree of this node
+    hash: Hash,
+}
+
+impl<V: AsHashTree> Node<V> {
+    fn new(key: Vec<u8>, value: V) -> Box<Self> {
+        let mut priority = [0; 8];
+        priority.copy_from_slice(&Sha256::digest(&key)[..8]);
+
+        let mut node = Box::new(Node {
+            key,
+            value,
+            priority: u64::from_be_bytes(priority),
+            left: None,
+            right: None,
+            hash: empty_hash(),
+        });
+        node.update_hash();
+        node
+    }
+
+    // A node is a fork of its left subtree with its labeled value and its right subtree
+    fn update_hash(&mut self) {
+        self.hash = fork_hash(
+            &fork_hash(
+                &subtree_hash(&self.left),
+                &labeled_hash(&self.key, &self.value.root_hash()),
+            ),
+            &subtree_hash(&self.right),
+        );
+    }
+}
+
+impl<V> Default for CertifiedMap<V> {
+    fn default() -> Self {
+        CertifiedMap { root: None, len: 0 }
+    }
+}
+
+fn subtree_hash<V>(node: &Option<Box<Node<V>>>) -> Hash {
+    match node {
+        None => empty_hash(),
+        Some(_node) => _node.hash,
+    }
+}
+
+fn pruned<V>(node: &Option<Box<Node<V>>>) -> HashTree {
+    match node {
+        None => HashTree::Empty,
+        Some(_node) => HashTree::Pruned(_node.hash),
+    }
+}
+
+impl<V: AsHashTree> CertifiedMap<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        let mut node = &self.root;
+        while let Some(_node) = node {
+            node = match key.cmp(&_node.key) {
+                Ordering::Equal => return Some(&_node.value),
+                Ordering::Less => &_node.left,
+                Ordering::Greater => &_node.right,
+            };
+        }
+        None
+    }
+
+    // Method to insert or replace a value, returns the replaced value
+    pub fn insert(&mut self, key: Vec<u8>, value: V) -> Option<V> {
+        let mut replaced = None;
+        self.root = Some(Self::insert_node(
+            self.root.take(),
+            key,
+            value,
+            &mut replaced,
+        ));
+        if replaced.is_none() {
+            self.len += 1;
+        }
+        replaced
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+        let mut removed = None;
+        self.root = Self::remove_node(self.root.take(), key, &mut removed);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    // Method to change a value in place, the hashes on the path to the value are updated afterwards
+    pub fn modify<R>(&mut self, key: &[u8], f: impl FnOnce(&mut V) -> R) -> Option<R> {
+        Self::modify_node(&mut self.root, key, f)
+    }
+
+    // Method to get a tree with the value of the key and only the hashes of the other entries
+    // the value tree is created by the callback, so nested maps can return their own witness
+    pub fn witness(&self, key: &[u8], f: impl FnOnce(&V) -> HashTree) -> HashTree {
+        Self::witness_node(&self.root, key, f)
+    }
+
+    fn insert_node(
+        node: Option<Box<Node<V>>>,
+        key: Vec<u8>,
+        value: V,
+        replaced: &mut Option<V>,
+    ) -> Box<Node<V>> {
+        let mut node = match node {
+            None => return Node::new(key, value),
+            Some(_node) => _node,
+        };
+
+        match key.cmp(&node.key) {
+            Ordering::Equal => {
+                *replaced = Some(std::mem::replace(&mut node.value, value));
+                node.update_hash();
+                node
+            }
+            Ordering::Less => {
+                let left = Self::insert_node(node.left.take(), key, value, replaced);
+                let rotate = left.priority > node.priority;
+                node.left = Some(left);
+                match rotate {
+                    true => Self::rotate_right(node),
+                    false => {
+                        node.update_hash();
+                        node
+                    }
+                }
+            }
+            Ordering::Greater => {
+                let right = Self::insert_node(node.right.take(), key, value, replaced);
+                let rotate = right.priority > node.priority;
+                node.right = Some(right);
+                match rotate {
+                    true => Self::rotate_left(node),
+                    false => {
+                        node.update_hash();
+                        node
+                    }
+                }
+            }
+        }
+    }
+
+    fn remove_node(
+        node: Option<Box<Node<V>>>,
+        key: &[u8],
+        removed: &mut Option<V>,
+    ) -> Option<Box<Node<V>>> {
+        let mut node = node?;
+        match key.cmp(&node.key) {
+            Ordering::Equal => {
+                let node = *node;
+                *removed = Some(node.value);
+                Self::merge(node.left, node.right)
+            }
+            Ordering::Less => {
+                node.left = Self::remove_node(node.left.take(), key, removed);
+                node.update_hash();
+                Some(node)
+            }
+            Ordering::Greater => {
+                node.right = Self::remove_node(node.right.take(), key, removed);
+                node.update_hash();
+                Some(node)
+            }
+        }
+    }
+
+    fn modify_node<R>(
+        node: &mut Option<Box<Node<V>>>,
+        key: &[u8],
+        f: impl FnOnce(&mut V) -> R,
+    ) -> Option<R> {
+        let node = node.as_mut()?;
+        let result = match key.cmp(&node.key) {
+            Ordering::Equal => Some(f(&mut node.value)),
+            Ordering::Less => Self::modify_node(&mut node.left, key, f),
+            Ordering::Greater => Self::modify_node(&mut node.right, key, f),
+        };
+        if result.is_some() {
+            node.update_hash();
+        }
+        result
+    }
+
+    fn witness_node(
+        node: &Option<Box<Node<V>>>,
+        key: &[u8],
+        f: impl FnOnce(&V) -> HashTree,
+    ) -> HashTree {
+        let node = match node {
+            None => return HashTree::Empty,
+            Some(_node) => _node,
+        };
+
+        // The labels on the path are kept so the position of the key can be checked
+        let pruned_value =
+            || HashTree::labeled(&node.key, HashTree::Pruned(node.value.root_hash()));
+        let (left, value, right) = match key.cmp(&node.key) {
+            Ordering::Equal => (
+                pruned(&node.left),
+                HashTree::labeled(&node.key, f(&node.value)),
+                pruned(&node.right),
+            ),
+            Ordering::Less => (
+                Self::witness_node(&node.left, key, f),
+                pruned_value(),
+                pruned(&node.right),
+            ),
+            Ordering::Greater => (
+                pruned(&node.left),
+                pruned_value(),
+                Self::witness_node(&node.right, key, f),
+            ),
+        };
+        HashTree::fork(HashTree::fork(left, value), right)
+    }
+
+    // Merges two subtrees where all the keys of the left subtree are smaller than the keys of the right subtree
+    fn merge(left: Option<Box<Node<V>>>, right: Option<Box<Node<V>>>) -> Option<Box<Node<V>>> {
+        match (left, right) {
+            (None, right) => right,
+            (left, None) => left,
+            (Some(mut left), Some(mut right)) => {
+                if left.priority > right.priority {
+                    left.right = Self::merge(left.right.take(), Some(right));
+                    left.update_hash();
+                    Some(left)
+                } else {
+                    right.left = Self::merge(Some(left), right.left.take());
+                    right.update_hash();
+                    Some(right)
+                }
+            }
+        }
+    }
+
+    fn rotate_right(mut node: Box<Node<V>>) -> Box<Node<V>> {
+        let mut left = node.left.take().expect("Rotation without a left child");
+        node.left = left.right.take();
+        node.update_hash();
+        left.right = Some(node);
+        left.update_hash();
+        left
+    }
+
+    fn rotate_left(mut node: Box<Node<V>>) -> Box<Node<V>> {
+        let mut right = node.right.take().expect("Rotation without a right child");
+        node.right = right.left.take();
+        node.update_hash();
+        right.left = Some(node);
+        right.update_hash();
+        right
+    }
+
+    fn full_tree(node: &Option<Box<Node<V>>>) -> HashTree {
+        match node {
+            None => HashTree::Empty,
+            Some(_node) => HashTree::fork(
+                HashTree::fork(
+                    Self::full_tree(&_node.left),
+                    HashTree::labeled(&_node.key, _node.value.as_hash_tree()),
+                ),
+                Self::full_tree(&_node.right),
+            ),
+        }
+    }
+}
+
+impl<V: AsHashTree> AsHashTree for CertifiedMap<V> {
+    fn root_hash(&self) -> Hash {
+        subtree_hash(&self.root)
+    }
+
+    fn as_hash_tree(&self) -> HashTree {
+        Self::full_tree(&self.root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(index: u32) -> Vec<u8> {
+        index.to_be_bytes().to_vec()
+    }
+
+    #[test]
+    fn root_hash_doesnt_depend_on_the_insert_order() {
+        let mut a: CertifiedMap<Vec<u8>> = CertifiedMap::new();
+        let mut b: CertifiedMap<Vec<u8>> = CertifiedMap::new();
+        (0..100).for_each(|index| {
+            a.insert(key(index), key(index));
+        });
+        (0..100).rev().for_each(|index| {
+            b.insert(key(index), key(index));
+        });
+        assert_eq!(a.root_hash(), b.root_hash());
+        assert_eq!(a.root_hash(), a.as_hash_tree().digest());
+
+        // Removing the entries that were added last gives the same hash as never adding them
+        let mut c: CertifiedMap<Vec<u8>> = CertifiedMap::new();
+        (0..50).for_each(|index| {
+            c.insert(key(index), key(index));
+        });
+        (50..100).for_each(|index| {
+            a.remove(&key(index));
+        });
+        assert_eq!(a.root_hash(), c.root_hash());
+        assert_eq!(a.len(), 50);
+    }
+
+    #[test]
+    fn witness_contains_the_value_and_matches_the_root_hash() {
+        let mut map: CertifiedMap<Vec<u8>> = CertifiedMap::new();
+        (0..100).for_each(|index| {
+            map.insert(key(index), key(index * 2));
+        });
+        map.modify(&key(42), |value| *value = b"changed".to_vec());
+
+        let witness = map.witness(&key(42), |value| value.as_hash_tree());
+        assert_eq!(witness.digest(), map.root_hash());
+        assert_eq!(witness.lookup_path(&[&key(42)]), Ok(b"changed".as_ref()));
+        assert!(witness.lookup_path(&[&key(43)]).is_err());
+
+        // A witness of a missing key still matches the root hash but doesnt contain the key
+        let witness = map.witness(&key(1000), |value| value.as_hash_tree());
+        assert_eq!(witness.digest(), map.root_hash());
+        assert!(witness.lookup_path(&[&key(1000)]).is_err());
+    }
+}
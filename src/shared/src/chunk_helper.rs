@@ -0,0 +1,190 @@
+use sha2::{Digest, Sha256};
+
+use crate::chunk_model::{ChunkedData, CHUNK_PROTOCOL_VERSION};
+
+// Method to get the SHA-256 hash of the data
+pub fn hash_bytes(bytes: &[u8]) -> Vec<u8> {
+    Sha256::digest(bytes).to_vec()
+}
+
+// Method to get a single chunk of the data
+// empty data results in a single empty chunk so there is always a first chunk to read the totals from
+pub fn get_chunk(
+    bytes: &[u8],
+    chunk: usize,
+    max_bytes_per_chunk: usize,
+    snapshot_id: u64,
+) -> Result<ChunkedData, String> {
+    let max_bytes_per_chunk = max_bytes_per_chunk.max(1);
+    let total_chunks = bytes.len().div_ceil(max_bytes_per_chunk).max(1);
+
+    if chunk >= total_chunks {
+        return Err(format!(
+            "Chunk {} is out of range, the data has {} chunks",
+            chunk, total_chunks
+        ));
+    }
+
+    let start = chunk * max_bytes_per_chunk;
+    let end = (start + max_bytes_per_chunk).min(bytes.len());
+
+    Ok(ChunkedData {
+        version: CHUNK_PROTOCOL_VERSION,
+        snapshot_id,
+        chunk,
+        total_chunks,
+        total_bytes: bytes.len(),
+        hash: hash_bytes(bytes),
+        bytes: bytes[start..end].to_vec(),
+    })
+}
+
+// Method to reassemble the chunks into the complete data
+// the chunks need to be ordered and all belong to the same data, the result is verified against the length and hash
+pub fn assemble_chunks(chunks: Vec<ChunkedData>) -> Result<Vec<u8>, String> {
+    let (snapshot_id, total_chunks, total_bytes, hash) = match chunks.first() {
+        None => return Err("No chunks received".to_string()),
+        Some(_first) => (
+            _first.snapshot_id,
+            _first.total_chunks,
+            _first.total_bytes,
+            _first.hash.clone(),
+        ),
+    };
+
+    if chunks.len() != total_chunks {
+        return Err(format!(
+            "Expected {} chunks, received {}",
+            total_chunks,
+            chunks.len()
+        ));
+    }
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(total_bytes);
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        if chunk.version != CHUNK_PROTOCOL_VERSION {
+            return Err(format!(
+                "Unsupported chunk protocol version {}",
+                chunk.version
+            ));
+        }
+
+        if chunk.chunk != index
+            || chunk.snapshot_id != snapshot_id
+            || chunk.total_chunks != total_chunks
+            || chunk.total_bytes != total_bytes
+            || chunk.hash != hash
+        {
+            return Err(format!(
+                "Chunk {} does not belong to the same data",
+                chunk.chunk
+            ));
+        }
+
+        bytes.extend(chunk.bytes);
+    }
+
+    if bytes.len() != total_bytes {
+        return Err(format!(
+            "Expected {} bytes, received {}",
+            total_bytes,
+            bytes.len()
+        ));
+    }
+
+    if hash_bytes(&bytes) != hash {
+        return Err("Hash of the reassembled data does not match".to_string());
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn get_all_chunks(bytes: &[u8], max_bytes_per_chunk: usize) -> Vec<ChunkedData> {
+        let first = get_chunk(bytes, 0, max_bytes_per_chunk, 1).unwrap();
+        (0..first.total_chunks)
+            .map(|chunk| get_chunk(bytes, chunk, max_bytes_per_chunk, 1).unwrap())
+            .collect()
+    }
+
+    // Sizes around the chunk boundaries (0, max - 1, max, max + 1, n * max and n * max +- 1)
+    fn boundary_sizes() -> impl Strategy<Value = (usize, usize)> {
+        (1usize..64, 0usize..8, -1i64..=1).prop_map(|(max_bytes_per_chunk, chunks, offset)| {
+            let size = (max_bytes_per_chunk * chunks) as i64 + offset;
+            (size.max(0) as usize, max_bytes_per_chunk)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn chunks_reassemble_to_the_original_data(
+            bytes in proptest::collection::vec(any::<u8>(), 0..2048),
+            max_bytes_per_chunk in 1usize..512,
+        ) {
+            let chunks = get_all_chunks(&bytes, max_bytes_per_chunk);
+            prop_assert_eq!(assemble_chunks(chunks).unwrap(), bytes);
+        }
+
+        #[test]
+        fn chunk_count_and_sizes_at_boundaries((size, max_bytes_per_chunk) in boundary_sizes()) {
+            let bytes = vec![7u8; size];
+            let chunks = get_all_chunks(&bytes, max_bytes_per_chunk);
+
+            let expected_chunks = std::cmp::max(1, size.div_ceil(max_bytes_per_chunk));
+            prop_assert_eq!(chunks.len(), expected_chunks);
+            prop_assert!(chunks.iter().all(|chunk| chunk.bytes.len() <= max_bytes_per_chunk));
+            // Only the last chunk can be smaller than the max bytes per chunk
+            prop_assert!(chunks[..chunks.len() - 1]
+                .iter()
+                .all(|chunk| chunk.bytes.len() == max_bytes_per_chunk));
+            prop_assert_eq!(chunks.iter().map(|chunk| chunk.bytes.len()).sum::<usize>(), size);
+            prop_assert!(get_chunk(&bytes, expected_chunks, max_bytes_per_chunk, 1).is_err());
+        }
+
+        #[test]
+        fn missing_or_reordered_chunks_are_rejected(
+            bytes in proptest::collection::vec(any::<u8>(), 2..1024),
+            max_bytes_per_chunk in 1usize..64,
+        ) {
+            let chunks = get_all_chunks(&bytes, max_bytes_per_chunk);
+            prop_assume!(chunks.len() > 1);
+
+            let mut missing = chunks.clone();
+            missing.pop();
+            prop_assert!(assemble_chunks(missing).is_err());
+
+            let mut reordered = chunks;
+            reordered.swap(0, 1);
+            prop_assert!(assemble_chunks(reordered).is_err());
+        }
+
+        #[test]
+        fn chunks_of_changed_data_are_rejected(
+            bytes in proptest::collection::vec(any::<u8>(), 2..1024),
+            max_bytes_per_chunk in 1usize..64,
+        ) {
+            let mut changed = bytes.clone();
+            changed[0] = changed[0].wrapping_add(1);
+
+            let mut chunks = get_all_chunks(&bytes, max_bytes_per_chunk);
+            prop_assume!(chunks.len() > 1);
+            chunks[1] = get_chunk(&changed, 1, max_bytes_per_chunk, 1).unwrap();
+            prop_assert!(assemble_chunks(chunks).is_err());
+        }
+
+        #[test]
+        fn chunks_of_other_snapshots_are_rejected(
+            bytes in proptest::collection::vec(any::<u8>(), 2..1024),
+            max_bytes_per_chunk in 1usize..64,
+        ) {
+            let mut chunks = get_all_chunks(&bytes, max_bytes_per_chunk);
+            prop_assume!(chunks.len() > 1);
+            chunks[1] = get_chunk(&bytes, 1, max_bytes_per_chunk, 2).unwrap();
+            prop_assert!(assemble_chunks(chunks).is_err());
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+// Version of the chunk protocol, bumped when the chunk response changes
+// version 1 returned the bytes with the chunk index and the last chunk index `(bytes, (chunk, last_chunk))`
+// version 2 added the totals and the hash, version 3 added the snapshot id
+pub const CHUNK_PROTOCOL_VERSION: u16 = 3;
+
+// Single chunk of serialized data that is send from a child canister to the parent canister
+// every chunk carries the totals and hash of the complete data so the parent canister can verify the reassembled data
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct ChunkedData {
+    pub version: u16,
+    // Snapshot of the data the chunk belongs to, needs to be passed when requesting the other chunks
+    pub snapshot_id: u64,
+    // Index of this chunk, starts at 0
+    pub chunk: usize,
+    pub total_chunks: usize,
+    pub total_bytes: usize,
+    // SHA-256 hash of the complete data
+    pub hash: Vec<u8>,
+    pub bytes: Vec<u8>,
+}
@@ -0,0 +1,38 @@
+use candid::Principal;
+
+use crate::hex_helper::{from_hex, to_hex};
+
+// Version of the cursor format, a cursor of an other version is rejected
+const CURSOR_VERSION: &str = "c1";
+
+// Method to turn the principal of the last item of a page into the cursor that is returned to the caller
+// the cursor is opaque, so the ordering key of the index can change without breaking the callers
+pub fn encode_cursor(principal: &Principal) -> String {
+    format!("{}{}", CURSOR_VERSION, to_hex(principal.as_slice()))
+}
+
+// Method to get the principal of the last item of the previous page from a cursor, None if the cursor is invalid
+pub fn decode_cursor(cursor: &str) -> Option<Principal> {
+    let bytes = from_hex(cursor.strip_prefix(CURSOR_VERSION)?)?;
+    Principal::try_from_slice(&bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_and_decodes_cursors() {
+        let principal = Principal::from_text("aaaaa-aa").unwrap();
+        assert_eq!(decode_cursor(&encode_cursor(&principal)), Some(principal));
+
+        let principal = Principal::from_slice(&[1, 2, 3, 255]);
+        let cursor = encode_cursor(&principal);
+        assert!(!cursor.contains(&principal.to_string()));
+        assert_eq!(decode_cursor(&cursor), Some(principal));
+
+        assert_eq!(decode_cursor(&principal.to_string()), None);
+        assert_eq!(decode_cursor("c1abc"), None);
+        assert_eq!(decode_cursor("c1zz"), None);
+    }
+}
@@ -0,0 +1,231 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{
+    attendee_model::{AttendeeStatus, InviteType},
+    export_model::{EventExportRow, ExportFormat},
+    hex_helper::{from_hex, to_hex},
+};
+
+const CSV_HEADER: [&str; 8] = [
+    "principal",
+    "attendee_identifier",
+    "group_identifier",
+    "status",
+    "invite_type",
+    "created_at",
+    "updated_at",
+    "checked_in_at",
+];
+
+// Method to serialize the rows of an export in the requested format
+pub fn export_rows(rows: &[EventExportRow], format: ExportFormat) -> Result<Vec<u8>, String> {
+    match format {
+        ExportFormat::Csv => Ok(to_csv(rows)),
+        ExportFormat::Json => serde_json::to_vec(rows).map_err(|err| err.to_string()),
+    }
+}
+
+pub fn content_type(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Csv => "text/csv; charset=utf-8",
+        ExportFormat::Json => "application/json",
+    }
+}
+
+pub fn file_extension(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Csv => "csv",
+        ExportFormat::Json => "json",
+    }
+}
+
+// Method to create a CSV file (RFC 4180) with a header row, the timestamps are in nanoseconds
+pub fn to_csv(rows: &[EventExportRow]) -> Vec<u8> {
+    let mut lines: Vec<String> = vec![CSV_HEADER.join(",")];
+    for row in rows {
+        let fields = [
+            row.principal.to_string(),
+            row.attendee_identifier.to_string(),
+            row.group_identifier.to_string(),
+            status_text(&row.status).to_string(),
+            row.invite_type
+                .as_ref()
+                .map(|invite_type| invite_type_text(invite_type).to_string())
+                .unwrap_or_default(),
+            row.created_at.to_string(),
+            row.updated_at.to_string(),
+            row.checked_in_at
+                .map(|checked_in_at| checked_in_at.to_string())
+                .unwrap_or_default(),
+        ];
+        lines.push(
+            fields
+                .iter()
+                .map(|field| escape_csv_field(field))
+                .collect::<Vec<String>>()
+                .join(","),
+        );
+    }
+
+    let mut csv = lines.join("\r\n");
+    csv.push_str("\r\n");
+    csv.into_bytes()
+}
+
+// Fields with a separator, quote or line break are quoted, quotes are escaped by doubling them
+fn escape_csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\r', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+fn status_text(status: &AttendeeStatus) -> &'static str {
+    match status {
+        AttendeeStatus::Joined => "joined",
+        AttendeeStatus::Invited => "invited",
+        AttendeeStatus::Requested => "requested",
+    }
+}
+
+fn invite_type_text(invite_type: &InviteType) -> &'static str {
+    match invite_type {
+        InviteType::None => "none",
+        InviteType::OwnerRequest => "owner_request",
+        InviteType::UserRequest => "user_request",
+    }
+}
+
+// HMAC-SHA256 as described in RFC 2104
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    download_mac(key, message).finalize().into_bytes().into()
+}
+
+// Method to create a download token for an export, formatted as `{expires_at}.{signature}`
+// the signature covers the export id and the expiry, so a token can't be used for another export or extended
+pub fn sign_download_token(secret: &[u8], export_id: u64, expires_at: u64) -> String {
+    format!(
+        "{}.{}",
+        expires_at,
+        to_hex(&hmac_sha256(
+            secret,
+            &download_message(export_id, expires_at)
+        ))
+    )
+}
+
+// Method to check the signature and expiry of a download token
+pub fn verify_download_token(secret: &[u8], export_id: u64, token: &str, now: u64) -> bool {
+    let (expires_at, signature) = match token.split_once('.') {
+        None => return false,
+        Some((_expires_at, _signature)) => match _expires_at.parse::<u64>() {
+            Err(_) => return false,
+            Ok(_expires_at) => (_expires_at, _signature),
+        },
+    };
+
+    if expires_at < now {
+        return false;
+    }
+
+    let signature = match from_hex(signature) {
+        None => return false,
+        Some(_signature) => _signature,
+    };
+    // The comparison takes the same time wherever the first difference is
+    download_mac(secret, &download_message(export_id, expires_at))
+        .verify_slice(&signature)
+        .is_ok()
+}
+
+fn download_mac(key: &[u8], message: &[u8]) -> Hmac<Sha256> {
+    // HMAC accepts keys of any length, so creating the mac can't fail
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac
+}
+
+fn download_message(export_id: u64, expires_at: u64) -> Vec<u8> {
+    format!("export:{}:{}", export_id, expires_at).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use super::*;
+
+    #[test]
+    fn hmac_matches_the_rfc_4231_test_vectors() {
+        assert_eq!(
+            to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // Keys longer than the block size are hashed first
+        assert_eq!(
+            to_hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn download_tokens_are_bound_to_the_export_and_expire() {
+        let token = sign_download_token(b"secret", 7, 1_000);
+        assert!(verify_download_token(b"secret", 7, &token, 999));
+        assert!(!verify_download_token(b"secret", 7, &token, 1_001));
+        assert!(!verify_download_token(b"secret", 8, &token, 999));
+        assert!(!verify_download_token(b"other", 7, &token, 999));
+
+        // Extending the expiry invalidates the signature
+        let extended = token.replacen("1000", "2000", 1);
+        assert!(!verify_download_token(b"secret", 7, &extended, 999));
+        assert!(!verify_download_token(b"secret", 7, "invalid", 999));
+    }
+
+    #[test]
+    fn creates_a_csv_with_a_header_and_a_row_per_join_or_invite() {
+        let principal = Principal::from_slice(&[1, 2]);
+        let rows = vec![
+            EventExportRow {
+                principal,
+                attendee_identifier: principal,
+                group_identifier: principal,
+                status: AttendeeStatus::Joined,
+                invite_type: None,
+                created_at: 1,
+                updated_at: 2,
+                checked_in_at: Some(3),
+            },
+            EventExportRow {
+                principal,
+                attendee_identifier: principal,
+                group_identifier: principal,
+                status: AttendeeStatus::Requested,
+                invite_type: Some(InviteType::UserRequest),
+                created_at: 4,
+                updated_at: 5,
+                checked_in_at: None,
+            },
+        ];
+
+        let csv = String::from_utf8(to_csv(&rows)).unwrap();
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(lines[0], CSV_HEADER.join(","));
+        assert_eq!(
+            lines[1],
+            format!("{0},{0},{0},joined,,1,2,3", principal.to_string())
+        );
+        assert_eq!(
+            lines[2],
+            format!(
+                "{0},{0},{0},requested,user_request,4,5,",
+                principal.to_string()
+            )
+        );
+        assert_eq!(escape_csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
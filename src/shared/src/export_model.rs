@@ -0,0 +1,51 @@
+use candid::{CandidType, Deserialize, Principal};
+use serde::Serialize;
+
+use crate::attendee_model::{AttendeeStatus, ChildCanisterError, InviteType};
+
+#[derive(CandidType, Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+// Request to export the attendees and invites of an event, the member identifier is used for the permission check
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct ExportRequest {
+    pub event_identifier: Principal,
+    pub group_identifier: Principal,
+    pub member_identifier: Principal,
+    pub format: ExportFormat,
+}
+
+// Single row of an export, every join and invite of the event results in a row
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct EventExportRow {
+    pub principal: Principal,
+    pub attendee_identifier: Principal,
+    pub group_identifier: Principal,
+    pub status: AttendeeStatus,
+    // Only set for invites and join requests
+    pub invite_type: Option<InviteType>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    // Time of the last check-in of the attendee at the event
+    pub checked_in_at: Option<u64>,
+}
+
+// Export that is stored on the parent canister, the data is read in chunks or downloaded with the token
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct EventExport {
+    pub export_id: u64,
+    pub event_identifier: Principal,
+    pub format: ExportFormat,
+    pub total_rows: usize,
+    pub total_bytes: usize,
+    pub total_chunks: usize,
+    // SHA-256 hash of the complete export
+    pub hash: Vec<u8>,
+    // Signed token for the `/exports/{export_id}?token=` download route, valid until the export expires
+    pub download_token: String,
+    pub expires_at: u64,
+    pub failed_canisters: Vec<ChildCanisterError>,
+}
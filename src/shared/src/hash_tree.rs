@@ -0,0 +1,298 @@
+use serde::{ser::SerializeSeq, Serialize, Serializer};
+use serde_bytes::Bytes;
+use serde_cbor::Value as CborValue;
+use sha2::{Digest, Sha256};
+
+pub type Hash = [u8; 32];
+
+// Hash tree as described in the IC interface specification, used for the certified data of the child canisters
+// https://internetcomputer.org/docs/current/references/ic-interface-spec#certification-encoding
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HashTree {
+    Empty,
+    Fork(Box<HashTree>, Box<HashTree>),
+    Labeled(Vec<u8>, Box<HashTree>),
+    Leaf(Vec<u8>),
+    Pruned(Hash),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LookupResult<'a> {
+    Found(&'a HashTree),
+    // The label is not part of the tree
+    Absent,
+    // The label could be part of a pruned subtree
+    Unknown,
+}
+
+impl HashTree {
+    pub fn fork(left: HashTree, right: HashTree) -> Self {
+        HashTree::Fork(Box::new(left), Box::new(right))
+    }
+
+    pub fn labeled(label: &[u8], tree: HashTree) -> Self {
+        HashTree::Labeled(label.to_vec(), Box::new(tree))
+    }
+
+    // Root hash of the tree, pruned subtrees are represented by their hash
+    pub fn digest(&self) -> Hash {
+        match self {
+            HashTree::Empty => empty_hash(),
+            HashTree::Fork(left, right) => fork_hash(&left.digest(), &right.digest()),
+            HashTree::Labeled(label, tree) => labeled_hash(label, &tree.digest()),
+            HashTree::Leaf(value) => leaf_hash(value),
+            HashTree::Pruned(hash) => *hash,
+        }
+    }
+
+    // Method to find the subtree with the label, the labels of a single level are spread over the forks
+    pub fn find_label(&self, label: &[u8]) -> LookupResult<'_> {
+        match self {
+            HashTree::Labeled(_label, tree) if _label.as_slice() == label => {
+                LookupResult::Found(tree)
+            }
+            HashTree::Fork(left, right) => match left.find_label(label) {
+                LookupResult::Found(tree) => LookupResult::Found(tree),
+                LookupResult::Absent => right.find_label(label),
+                LookupResult::Unknown => match right.find_label(label) {
+                    LookupResult::Found(tree) => LookupResult::Found(tree),
+                    _ => LookupResult::Unknown,
+                },
+            },
+            HashTree::Pruned(_) => LookupResult::Unknown,
+            _ => LookupResult::Absent,
+        }
+    }
+
+    // Method to get the value of the leaf at the path
+    pub fn lookup_path(&self, path: &[&[u8]]) -> Result<&[u8], LookupResult<'_>> {
+        let mut tree = self;
+        for label in path {
+            tree = match tree.find_label(label) {
+                LookupResult::Found(_tree) => _tree,
+                result => return Err(result),
+            };
+        }
+
+        match tree {
+            HashTree::Leaf(value) => Ok(value),
+            HashTree::Pruned(_) => Err(LookupResult::Unknown),
+            _ => Err(LookupResult::Absent),
+        }
+    }
+
+    // CBOR encoding of the tree with the self describe tag, the format used by the IC for witnesses
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut serializer = serde_cbor::Serializer::new(vec![]);
+        serializer
+            .self_describe()
+            .expect("Failed to write the self describe tag");
+        self.serialize(&mut serializer)
+            .expect("Failed to serialize the hash tree");
+        serializer.into_inner()
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, String> {
+        let value: CborValue = serde_cbor::from_slice(bytes).map_err(|err| err.to_string())?;
+        Self::from_cbor_value(&value)
+    }
+
+    // Method to read the tree from a decoded CBOR value, used for the tree in a certificate
+    pub fn from_cbor_value(value: &CborValue) -> Result<Self, String> {
+        let items = match value {
+            CborValue::Tag(_, _value) => return Self::from_cbor_value(_value),
+            CborValue::Array(_items) => _items,
+            _ => return Err("Hash tree is not an array".to_string()),
+        };
+
+        let bytes = |index: usize| match items.get(index) {
+            Some(CborValue::Bytes(_bytes)) => Ok(_bytes.clone()),
+            _ => Err("Hash tree node contains invalid bytes".to_string()),
+        };
+        let subtree = |index: usize| match items.get(index) {
+            Some(_value) => Self::from_cbor_value(_value),
+            None => Err("Hash tree node is missing a subtree".to_string()),
+        };
+
+        match items.first() {
+            Some(CborValue::Integer(0)) => Ok(HashTree::Empty),
+            Some(CborValue::Integer(1)) => Ok(HashTree::fork(subtree(1)?, subtree(2)?)),
+            Some(CborValue::Integer(2)) => Ok(HashTree::Labeled(bytes(1)?, Box::new(subtree(2)?))),
+            Some(CborValue::Integer(3)) => Ok(HashTree::Leaf(bytes(1)?)),
+            Some(CborValue::Integer(4)) => {
+                let mut hash: Hash = [0; 32];
+                let pruned = bytes(1)?;
+                if pruned.len() != hash.len() {
+                    return Err("Pruned hash has an invalid length".to_string());
+                }
+                hash.copy_from_slice(&pruned);
+                Ok(HashTree::Pruned(hash))
+            }
+            _ => Err("Hash tree node has an unknown tag".to_string()),
+        }
+    }
+}
+
+impl Serialize for HashTree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            HashTree::Empty => {
+                let mut seq = serializer.serialize_seq(Some(1))?;
+                seq.serialize_element(&0u8)?;
+                seq.end()
+            }
+            HashTree::Fork(left, right) => {
+                let mut seq = serializer.serialize_seq(Some(3))?;
+                seq.serialize_element(&1u8)?;
+                seq.serialize_element(left)?;
+                seq.serialize_element(right)?;
+                seq.end()
+            }
+            HashTree::Labeled(label, tree) => {
+                let mut seq = serializer.serialize_seq(Some(3))?;
+                seq.serialize_element(&2u8)?;
+                seq.serialize_element(Bytes::new(label))?;
+                seq.serialize_element(tree)?;
+                seq.end()
+            }
+            HashTree::Leaf(value) => {
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                seq.serialize_element(&3u8)?;
+                seq.serialize_element(Bytes::new(value))?;
+                seq.end()
+            }
+            HashTree::Pruned(hash) => {
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                seq.serialize_element(&4u8)?;
+                seq.serialize_element(Bytes::new(hash))?;
+                seq.end()
+            }
+        }
+    }
+}
+
+fn domain_separated(domain: &str) -> Sha256 {
+    let mut hasher = Sha256::new();
+    hasher.update([domain.len() as u8]);
+    hasher.update(domain.as_bytes());
+    hasher
+}
+
+pub fn empty_hash() -> Hash {
+    domain_separated("ic-hashtree-empty").finalize().into()
+}
+
+pub fn fork_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = domain_separated("ic-hashtree-fork");
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+pub fn labeled_hash(label: &[u8], tree: &Hash) -> Hash {
+    let mut hasher = domain_separated("ic-hashtree-labeled");
+    hasher.update(label);
+    hasher.update(tree);
+    hasher.finalize().into()
+}
+
+pub fn leaf_hash(value: &[u8]) -> Hash {
+    let mut hasher = domain_separated("ic-hashtree-leaf");
+    hasher.update(value);
+    hasher.finalize().into()
+}
+
+// Numbers in the tree are encoded as unsigned LEB128, the same encoding the IC uses for the certificate time
+pub fn encode_leb128(mut value: u64) -> Vec<u8> {
+    let mut bytes = vec![];
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+pub fn decode_leb128(bytes: &[u8]) -> Option<u64> {
+    let mut value: u64 = 0;
+    for (index, byte) in bytes.iter().enumerate() {
+        let shift = index as u32 * 7;
+        if shift >= 64 {
+            return None;
+        }
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex_helper::to_hex;
+
+    fn leaf(value: &str) -> HashTree {
+        HashTree::Leaf(value.as_bytes().to_vec())
+    }
+
+    // Example tree from the IC interface specification
+    fn example_tree() -> HashTree {
+        HashTree::fork(
+            HashTree::fork(
+                HashTree::labeled(
+                    b"a",
+                    HashTree::fork(
+                        HashTree::fork(HashTree::labeled(b"x", leaf("hello")), HashTree::Empty),
+                        HashTree::labeled(b"y", leaf("world")),
+                    ),
+                ),
+                HashTree::labeled(b"b", leaf("good")),
+            ),
+            HashTree::fork(
+                HashTree::labeled(b"c", HashTree::Empty),
+                HashTree::labeled(b"d", leaf("morning")),
+            ),
+        )
+    }
+
+    #[test]
+    fn digest_matches_the_specification_example() {
+        assert_eq!(
+            to_hex(&example_tree().digest()),
+            "eb5c5b2195e62d996b84c9bcc8259d19a83786a2f59e0878cec84c811f669aa0"
+        );
+    }
+
+    #[test]
+    fn lookup_finds_leaves_and_handles_pruned_subtrees() {
+        let tree = example_tree();
+        assert_eq!(tree.lookup_path(&[b"a", b"y"]), Ok(b"world".as_ref()));
+        assert_eq!(tree.lookup_path(&[b"e"]), Err(LookupResult::Absent));
+
+        let pruned = HashTree::fork(
+            HashTree::Pruned(tree.digest()),
+            HashTree::labeled(b"d", leaf("morning")),
+        );
+        assert_eq!(pruned.lookup_path(&[b"d"]), Ok(b"morning".as_ref()));
+        assert_eq!(pruned.lookup_path(&[b"a"]), Err(LookupResult::Unknown));
+    }
+
+    #[test]
+    fn cbor_encoding_round_trips() {
+        let tree = example_tree();
+        assert_eq!(HashTree::from_cbor(&tree.to_cbor()), Ok(tree));
+    }
+
+    #[test]
+    fn leb128_round_trips() {
+        for value in [0, 1, 127, 128, 624_485, u64::MAX] {
+            assert_eq!(decode_leb128(&encode_leb128(value)), Some(value));
+        }
+        assert_eq!(encode_leb128(624_485), vec![0xe5, 0x8e, 0x26]);
+    }
+}
@@ -0,0 +1,44 @@
+// Method to encode bytes as a lowercase hex string
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Method to decode a hex string, None if the string is not valid hex
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [high, low] => Some(hex_value(*high) << 4 | hex_value(*low)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        _ => digit - b'A' + 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_and_decodes_hex() {
+        assert_eq!(to_hex(&[0, 1, 171, 255]), "0001abff");
+        assert_eq!(from_hex("0001abff"), Some(vec![0, 1, 171, 255]));
+        assert_eq!(from_hex("0001ABFF"), Some(vec![0, 1, 171, 255]));
+        assert_eq!(from_hex(""), Some(vec![]));
+
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+        assert_eq!(from_hex("+1"), None);
+    }
+}
@@ -0,0 +1,72 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_scalable_misc::traits::stable_storage_trait::StableStorableTrait;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Serialize;
+
+// Single change in the attendance of a principal, kept after the join itself is removed
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct AttendanceHistoryEntry {
+    pub event_identifier: Principal,
+    pub group_identifier: Principal,
+    pub action: AttendanceAction,
+    pub created_at: u64,
+}
+
+impl StableStorableTrait for AttendanceHistoryEntry {}
+
+impl Storable for AttendanceHistoryEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum AttendanceAction {
+    // The principal joined the event (directly or by an accepted invite)
+    Joined,
+    // The principal left the event
+    Left,
+    // The principal is removed from the event by an admin
+    Removed,
+    // The principal is checked in at the event
+    CheckedIn,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum AttendanceHistoryPrivacy {
+    // The history can be read by everyone
+    Public,
+    // The history can only be read by the principal itself
+    Private,
+}
+
+// Summary of the attendance history within the requested range
+#[derive(Clone, Debug, Default, CandidType, Deserialize, Serialize)]
+pub struct AttendanceStats {
+    // Number of distinct events the principal joined
+    pub total_events_joined: u64,
+    // Number of distinct events the principal is checked in at
+    pub total_events_attended: u64,
+    // Share of the ended events that are still joined without a check-in, between 0 and 1
+    pub no_show_rate: f64,
+    // Groups with the most joined events, most active first
+    pub most_active_groups: Vec<(Principal, u64)>,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct AttendanceHistory {
+    pub principal: Principal,
+    // Page of the history entries, ordered by time
+    pub entries: Vec<AttendanceHistoryEntry>,
+    // Cursor to get the next page of entries, none if there are no more entries
+    pub next_cursor: Option<String>,
+    pub stats: AttendanceStats,
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use candid::Principal;
+use ic_scalable_misc::{
+    enums::api_error_type::ApiError,
+    models::http_models::{HeaderField, HttpRequest, HttpResponse},
+};
+use serde::Serialize;
+
+use crate::attendee_model::MAX_PAGE_LIMIT;
+
+// Number of items that is returned when the limit query param is not set
+pub const DEFAULT_PAGE_LIMIT: usize = 25;
+
+#[derive(Serialize)]
+struct HttpError<'a, T: Serialize> {
+    error: &'a T,
+}
+
+// Parsed url of a request, ex; `/events/{id}/attendees?limit=10` has the path ["events", "{id}", "attendees"]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpRoute {
+    pub method: String,
+    pub path: Vec<String>,
+    pub query: HashMap<String, String>,
+}
+
+impl HttpRoute {
+    pub fn parse(req: &HttpRequest) -> Self {
+        let (path, query) = match req.url.split_once('?') {
+            None => (req.url.as_str(), ""),
+            Some((_path, _query)) => (_path, _query),
+        };
+
+        HttpRoute {
+            method: req.method.to_uppercase(),
+            path: path
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .map(|segment| segment.to_string())
+                .collect(),
+            query: query
+                .split('&')
+                .filter(|param| !param.is_empty())
+                .map(|param| match param.split_once('=') {
+                    None => (param.to_string(), String::new()),
+                    Some((_key, _value)) => (_key.to_string(), _value.to_string()),
+                })
+                .collect(),
+        }
+    }
+
+    // Method to check if the path matches the pattern, segments in the pattern starting with ':' match any value
+    pub fn matches(&self, pattern: &[&str]) -> bool {
+        self.path.len() == pattern.len()
+            && self
+                .path
+                .iter()
+                .zip(pattern.iter())
+                .all(|(segment, _pattern)| _pattern.starts_with(':') || segment == _pattern)
+    }
+
+    // Method to get the principal from a segment of the path
+    pub fn principal(&self, index: usize) -> Result<Principal, HttpResponse> {
+        self.path
+            .get(index)
+            .and_then(|segment| Principal::from_text(segment).ok())
+            .ok_or_else(|| error_response(400, "Invalid principal in the path"))
+    }
+
+    // The limit query param, capped at the max page limit
+    pub fn limit(&self) -> Result<usize, HttpResponse> {
+        match self.query.get("limit") {
+            None => Ok(DEFAULT_PAGE_LIMIT),
+            Some(_limit) => match _limit.parse::<usize>() {
+                Ok(_limit) if _limit > 0 => Ok(_limit.min(MAX_PAGE_LIMIT)),
+                _ => Err(error_response(400, "The limit should be a positive number")),
+            },
+        }
+    }
+
+    // The page query param, pages start at 1
+    pub fn page(&self) -> Result<usize, HttpResponse> {
+        match self.query.get("page") {
+            None => Ok(1),
+            Some(_page) => match _page.parse::<usize>() {
+                Ok(_page) if _page > 0 => Ok(_page),
+                _ => Err(error_response(400, "The page should be a positive number")),
+            },
+        }
+    }
+
+    pub fn cursor(&self) -> Option<String> {
+        self.query
+            .get("cursor")
+            .filter(|cursor| !cursor.is_empty())
+            .cloned()
+    }
+}
+
+pub fn json_response<T: Serialize>(status_code: u16, body: &T) -> HttpResponse {
+    match serde_json::to_vec(body) {
+        Ok(_body) => HttpResponse {
+            status_code,
+            headers: vec![HeaderField(
+                "content-type".to_string(),
+                "application/json".to_string(),
+            )],
+            body: _body,
+        },
+        Err(_) => HttpResponse {
+            status_code: 500,
+            headers: vec![HeaderField(
+                "content-type".to_string(),
+                "application/json".to_string(),
+            )],
+            body: br#"{"error":"Failed to serialize the response"}"#.to_vec(),
+        },
+    }
+}
+
+pub fn error_response(status_code: u16, message: &str) -> HttpResponse {
+    json_response(
+        status_code,
+        &HttpError {
+            error: &message.to_string(),
+        },
+    )
+}
+
+pub fn method_not_allowed_response() -> HttpResponse {
+    let mut response = error_response(405, "Only GET requests are supported");
+    response
+        .headers
+        .push(HeaderField("allow".to_string(), "GET".to_string()));
+    response
+}
+
+// Method to map an api error to a response with a matching status code
+pub fn api_error_response(err: &ApiError) -> HttpResponse {
+    let status_code = match err {
+        ApiError::NotFound(_) => 404,
+        ApiError::Unauthorized(_) => 403,
+        ApiError::BadRequest(_) | ApiError::ValidationError(_) => 400,
+        _ => 500,
+    };
+    json_response(status_code, &HttpError { error: err })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, url: &str) -> HttpRequest {
+        HttpRequest {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    #[test]
+    fn parses_the_path_and_query_params() {
+        let route = HttpRoute::parse(&request(
+            "get",
+            "/events/aaaaa-aa/attendees?limit=10&cursor=abc",
+        ));
+        assert_eq!(route.method, "GET");
+        assert!(route.matches(&["events", ":id", "attendees"]));
+        assert!(!route.matches(&["events", ":id", "count"]));
+        assert_eq!(
+            route.principal(1).ok(),
+            Some(Principal::management_canister())
+        );
+        assert_eq!(route.limit().ok(), Some(10));
+        assert_eq!(route.page().ok(), Some(1));
+        assert_eq!(route.cursor(), Some("abc".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_params() {
+        let route = HttpRoute::parse(&request("GET", "/events/invalid/count?limit=0&page=x"));
+        assert_eq!(route.principal(1).err().map(|r| r.status_code), Some(400));
+        assert_eq!(route.limit().err().map(|r| r.status_code), Some(400));
+        assert_eq!(route.page().err().map(|r| r.status_code), Some(400));
+
+        let route = HttpRoute::parse(&request("GET", "/events?limit=100000"));
+        assert_eq!(route.limit().ok(), Some(MAX_PAGE_LIMIT));
+    }
+}
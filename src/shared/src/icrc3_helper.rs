@@ -0,0 +1,81 @@
+use sha2::{Digest, Sha256};
+
+use crate::icrc3_model::Value;
+
+// Representation independent hash of an ICRC-3 value
+// https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-3#value
+pub fn hash_value(value: &Value) -> [u8; 32] {
+    match value {
+        Value::Blob(bytes) => Sha256::digest(bytes).into(),
+        Value::Text(text) => Sha256::digest(text.as_bytes()).into(),
+        Value::Nat(nat) => {
+            // Nat is hashed as its unsigned LEB128 encoding
+            let mut bytes = vec![];
+            nat.encode(&mut bytes).expect("Failed to encode nat");
+            Sha256::digest(bytes).into()
+        }
+        Value::Int(int) => {
+            // Int is hashed as its signed LEB128 encoding
+            let mut bytes = vec![];
+            int.encode(&mut bytes).expect("Failed to encode int");
+            Sha256::digest(bytes).into()
+        }
+        Value::Array(values) => {
+            let mut hasher = Sha256::new();
+            values
+                .iter()
+                .for_each(|value| hasher.update(hash_value(value)));
+            hasher.finalize().into()
+        }
+        Value::Map(entries) => {
+            // The hashes of the key and value pairs are sorted so the hash doesnt depend on the order of the entries
+            let mut pairs: Vec<Vec<u8>> = entries
+                .iter()
+                .map(|(key, value)| {
+                    let mut pair = Sha256::digest(key.as_bytes()).to_vec();
+                    pair.extend_from_slice(&hash_value(value));
+                    pair
+                })
+                .collect();
+            pairs.sort();
+
+            let mut hasher = Sha256::new();
+            pairs.iter().for_each(|pair| hasher.update(pair));
+            hasher.finalize().into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Nat;
+
+    use super::*;
+    use crate::hex_helper::to_hex;
+
+    // Examples from the ICRC-3 standard
+    #[test]
+    fn hashes_match_the_standard_examples() {
+        assert_eq!(
+            to_hex(&hash_value(&Value::Nat(Nat::from(42u64)))),
+            "684888c0ebb17f374298b65ee2807526c066094c701bcc7ebbe1c1095f494fc1"
+        );
+        assert_eq!(
+            to_hex(&hash_value(&Value::Text("Hello, World!".to_string()))),
+            "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"
+        );
+    }
+
+    #[test]
+    fn map_hash_doesnt_depend_on_the_order() {
+        let a = Value::Map(vec![
+            ("a".to_string(), Value::Nat(Nat::from(1u64))),
+            ("b".to_string(), Value::Text("b".to_string())),
+        ]);
+        let b = Value::Map(vec![
+            ("b".to_string(), Value::Text("b".to_string())),
+            ("a".to_string(), Value::Nat(Nat::from(1u64))),
+        ]);
+        assert_eq!(hash_value(&a), hash_value(&b));
+    }
+}
@@ -0,0 +1,116 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Int, Nat, Principal};
+use ic_scalable_misc::traits::stable_storage_trait::StableStorableTrait;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Serialize;
+use serde_bytes::ByteBuf;
+
+// Block types of the attendance log, described in the README
+pub const BLOCK_TYPE_JOIN: &str = "attendee_join";
+pub const BLOCK_TYPE_LEAVE: &str = "attendee_leave";
+pub const BLOCK_TYPE_REMOVE: &str = "attendee_remove";
+pub const BLOCK_TYPE_INVITE: &str = "attendee_invite";
+pub const BLOCK_TYPE_ACCEPT: &str = "attendee_accept";
+pub const BLOCK_TYPE_REMOVE_INVITE: &str = "attendee_remove_invite";
+
+// ICRC-3 generic value, blocks are maps of values
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Value {
+    Blob(ByteBuf),
+    Text(String),
+    Nat(Nat),
+    Int(Int),
+    Array(Vec<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+// Block with the hash of the block, the hash is stored so the next block can be chained without hashing again
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct StoredBlock {
+    pub block: Value,
+    pub hash: ByteBuf,
+}
+
+impl StableStorableTrait for StoredBlock {}
+
+impl Storable for StoredBlock {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct GetBlocksArgs {
+    pub start: Nat,
+    pub length: Nat,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct BlockWithId {
+    pub id: Nat,
+    pub block: Value,
+}
+
+// The blocks are never archived, the archived blocks are always empty
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct ArchivedBlocks {
+    pub args: Vec<GetBlocksArgs>,
+    pub callback: GetBlocksCallback,
+}
+
+candid::define_function!(pub GetBlocksCallback : (Vec<GetBlocksArgs>) -> (GetBlocksResult) query);
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct GetBlocksResult {
+    pub log_length: Nat,
+    pub blocks: Vec<BlockWithId>,
+    pub archived_blocks: Vec<ArchivedBlocks>,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct GetArchivesArgs {
+    pub from: Option<Principal>,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct ICRC3ArchiveInfo {
+    pub canister_id: Principal,
+    pub start: Nat,
+    pub end: Nat,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct SupportedBlockType {
+    pub block_type: String,
+    pub url: String,
+}
+
+// Certificate of the last block of the log, the hash tree contains the last_block_index and last_block_hash
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct ICRC3DataCertificate {
+    pub certificate: ByteBuf,
+    pub hash_tree: ByteBuf,
+}
+
+// Documentation of a block type, served by the child canisters at the url of the supported block types
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct BlockTypeDocumentation {
+    pub block_type: String,
+    pub description: String,
+}
+
+// Block log of a single child canister, listed by the parent canister so an indexer can follow all the logs
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct AttendanceLog {
+    pub canister_identifier: Principal,
+    pub log_length: Option<Nat>,
+    // Error if the child canister could not be reached
+    pub error: Option<String>,
+}
@@ -1 +1,31 @@
 pub mod attendee_model;
+pub mod audit_helper;
+pub mod audit_model;
+pub mod calendar_helper;
+pub mod calendar_model;
+pub mod capacity_model;
+pub mod certification_helper;
+pub mod certification_model;
+pub mod certified_map;
+pub mod chunk_helper;
+pub mod chunk_model;
+pub mod cursor_helper;
+pub mod export_helper;
+pub mod export_model;
+pub mod hash_tree;
+pub mod hex_helper;
+pub mod history_model;
+pub mod http_helper;
+pub mod icrc3_helper;
+pub mod icrc3_model;
+pub mod lock_helper;
+pub mod metrics_helper;
+pub mod metrics_model;
+pub mod operator_helper;
+pub mod operator_model;
+pub mod outbox_model;
+pub mod rollout_helper;
+pub mod rollout_model;
+pub mod subscription_model;
+pub mod wasm_helper;
+pub mod wasm_model;
@@ -0,0 +1,43 @@
+use std::{cell::Cell, thread::LocalKey};
+
+// Lock that is held while an async job runs over multiple messages (ex; draining the outbox)
+// the lock is released when the guard is dropped, this also happens when a callback after an await traps
+// because the cdk drops the pending future in the cleanup of the call
+pub struct LockGuard {
+    lock: &'static LocalKey<Cell<bool>>,
+}
+
+impl LockGuard {
+    // Method to take the lock, returns None when the lock is already held
+    pub fn acquire(lock: &'static LocalKey<Cell<bool>>) -> Option<Self> {
+        match lock.with(|held| held.replace(true)) {
+            true => None,
+            false => Some(Self { lock }),
+        }
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        self.lock.with(|held| held.set(false));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    thread_local! {
+        static LOCK: Cell<bool> = const { Cell::new(false) };
+    }
+
+    #[test]
+    fn releases_the_lock_when_dropped() {
+        let guard = LockGuard::acquire(&LOCK);
+        assert!(guard.is_some());
+        assert!(LockGuard::acquire(&LOCK).is_none());
+
+        drop(guard);
+        assert!(LockGuard::acquire(&LOCK).is_some());
+    }
+}
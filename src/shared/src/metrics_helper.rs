@@ -0,0 +1,235 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+};
+
+use candid::Principal;
+
+use crate::metrics_model::AttendeeMetrics;
+
+// Number of events that are reported in the top events
+pub const TOP_EVENTS: usize = 10;
+
+thread_local! {
+    // Failed inter-canister calls per method, kept on the heap so the counters are reset by an upgrade
+    static CALL_FAILURES: RefCell<BTreeMap<String, u64>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+// Method to count a failed inter-canister call, only calls made in update calls and timers are kept
+pub fn record_call_failure(method: &str) {
+    CALL_FAILURES
+        .with(|failures| *failures.borrow_mut().entry(method.to_string()).or_default() += 1);
+}
+
+pub fn get_call_failures() -> Vec<(String, u64)> {
+    CALL_FAILURES.with(|failures| {
+        failures
+            .borrow()
+            .iter()
+            .map(|(method, count)| (method.clone(), *count))
+            .collect()
+    })
+}
+
+// Method to check if a request is served by the `/metrics` path of ic_scalable_misc, which matches the non-empty path segments
+pub fn is_metrics_request(method: &str, url: &str) -> bool {
+    method == "GET"
+        && url
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .eq(["metrics"])
+}
+
+// Method to merge the metrics of the child canisters, the counts of an event on multiple canisters are added up
+pub fn merge_metrics(metrics: Vec<AttendeeMetrics>) -> AttendeeMetrics {
+    let mut merged = AttendeeMetrics::default();
+    let mut changes: BTreeMap<String, u64> = BTreeMap::new();
+    let mut failures: BTreeMap<String, u64> = BTreeMap::new();
+    let mut events: HashMap<Principal, u64> = HashMap::new();
+
+    for metric in metrics {
+        merged.attendees += metric.attendees;
+        merged.joins += metric.joins;
+        merged.invites += metric.invites;
+        merged.outbox_pending += metric.outbox_pending;
+        merged.outbox_failed += metric.outbox_failed;
+        merged.directory_queue += metric.directory_queue;
+        for (block_type, count) in metric.changes_last_hour {
+            *changes.entry(block_type).or_default() += count;
+        }
+        for (method, count) in metric.call_failures {
+            *failures.entry(method).or_default() += count;
+        }
+        for (event_identifier, count) in metric.top_events {
+            *events.entry(event_identifier).or_default() += count;
+        }
+    }
+
+    merged.changes_last_hour = changes.into_iter().collect();
+    merged.call_failures = failures.into_iter().collect();
+    merged.top_events = top_events(events.into_iter().collect());
+    merged
+}
+
+// Method to get the largest events, ties are ordered by event identifier so the output is stable
+pub fn top_events(mut events: Vec<(Principal, u64)>) -> Vec<(Principal, u64)> {
+    events.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    events.truncate(TOP_EVENTS);
+    events
+}
+
+// Method to render the metrics in the Prometheus text format, appended to the canister metrics of `/metrics`
+pub fn render_attendee_metrics(metrics: &AttendeeMetrics) -> String {
+    let mut output = String::new();
+
+    write_metric(
+        &mut output,
+        "attendees",
+        "Number of attendee records",
+        "gauge",
+        &[(vec![], metrics.attendees)],
+    );
+    write_metric(
+        &mut output,
+        "attendee_joins",
+        "Number of joins over all the events",
+        "gauge",
+        &[(vec![], metrics.joins)],
+    );
+    write_metric(
+        &mut output,
+        "attendee_invites",
+        "Number of invites and join requests over all the events",
+        "gauge",
+        &[(vec![], metrics.invites)],
+    );
+    write_metric(
+        &mut output,
+        "attendee_changes_last_hour",
+        "Number of attendance changes during the last hour per block type",
+        "gauge",
+        &metrics
+            .changes_last_hour
+            .iter()
+            .map(|(block_type, count)| (vec![("type", block_type.clone())], *count))
+            .collect::<Vec<_>>(),
+    );
+    write_metric(
+        &mut output,
+        "attendee_outbox_entries",
+        "Attendee count notifications that are not yet delivered to the event canisters",
+        "gauge",
+        &[
+            (
+                vec![("status", "pending".to_string())],
+                metrics.outbox_pending,
+            ),
+            (
+                vec![("status", "failed".to_string())],
+                metrics.outbox_failed,
+            ),
+        ],
+    );
+    write_metric(
+        &mut output,
+        "attendee_directory_queue",
+        "Attendees that are not yet registered in the directory of the parent",
+        "gauge",
+        &[(vec![], metrics.directory_queue)],
+    );
+    write_metric(
+        &mut output,
+        "inter_canister_call_failures_total",
+        "Failed inter-canister calls per method since the last upgrade",
+        "counter",
+        &metrics
+            .call_failures
+            .iter()
+            .map(|(method, count)| (vec![("method", method.clone())], *count))
+            .collect::<Vec<_>>(),
+    );
+    write_metric(
+        &mut output,
+        "event_attendees",
+        "Number of attendees of the largest events",
+        "gauge",
+        &metrics
+            .top_events
+            .iter()
+            .map(|(event_identifier, count)| {
+                (vec![("event", event_identifier.to_string())], *count)
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    output
+}
+
+fn write_metric(
+    output: &mut String,
+    name: &str,
+    help: &str,
+    metric_type: &str,
+    samples: &[(Vec<(&str, String)>, u64)],
+) {
+    output.push_str(&format!("# HELP {} {}\n", name, help));
+    output.push_str(&format!("# TYPE {} {}\n", name, metric_type));
+    for (labels, value) in samples {
+        let labels = labels
+            .iter()
+            .map(|(label, value)| format!("{}=\"{}\"", label, escape_label_value(value)))
+            .collect::<Vec<String>>()
+            .join(",");
+        output.push_str(&format!("{}{{{}}} {}\n", name, labels, value));
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_the_metrics_of_the_child_canisters() {
+        let event_a = Principal::from_slice(&[1, 1]);
+        let event_b = Principal::from_slice(&[2, 1]);
+        let child = |attendees: u64, a: u64, b: u64| AttendeeMetrics {
+            attendees,
+            changes_last_hour: vec![("attendee_join".to_string(), attendees)],
+            call_failures: vec![("get_event".to_string(), 1)],
+            top_events: vec![(event_a, a), (event_b, b)],
+            ..Default::default()
+        };
+
+        let merged = merge_metrics(vec![child(3, 1, 5), child(4, 6, 1)]);
+        assert_eq!(merged.attendees, 7);
+        assert_eq!(
+            merged.changes_last_hour,
+            vec![("attendee_join".to_string(), 7)]
+        );
+        assert_eq!(merged.call_failures, vec![("get_event".to_string(), 2)]);
+        assert_eq!(merged.top_events, vec![(event_a, 7), (event_b, 6)]);
+    }
+
+    #[test]
+    fn renders_the_prometheus_text_format() {
+        let metrics = AttendeeMetrics {
+            attendees: 2,
+            call_failures: vec![("get_\"event\"".to_string(), 3)],
+            ..Default::default()
+        };
+
+        let output = render_attendee_metrics(&metrics);
+        assert!(output.contains("# TYPE attendees gauge\nattendees{} 2\n"));
+        assert!(
+            output.contains("inter_canister_call_failures_total{method=\"get_\\\"event\\\"\"} 3\n")
+        );
+        assert!(output.contains("attendee_outbox_entries{status=\"failed\"} 0\n"));
+    }
+}
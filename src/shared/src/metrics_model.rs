@@ -0,0 +1,23 @@
+use candid::{CandidType, Deserialize, Principal};
+use serde::Serialize;
+
+// Attendee domain metrics of a child canister, or merged over all the child canisters by the parent
+#[derive(Clone, Debug, Default, CandidType, Deserialize, Serialize)]
+pub struct AttendeeMetrics {
+    // Number of attendee records
+    pub attendees: u64,
+    // Number of joins and invites over all the events
+    pub joins: u64,
+    pub invites: u64,
+    // Number of changes in the attendance log during the last hour per block type (ex; `attendee_join`)
+    pub changes_last_hour: Vec<(String, u64)>,
+    // Attendee count notifications that are not yet delivered to the event canisters
+    pub outbox_pending: u64,
+    pub outbox_failed: u64,
+    // Attendees that are not yet registered in the directory of the parent
+    pub directory_queue: u64,
+    // Number of failed inter-canister calls per method since the last upgrade
+    pub call_failures: Vec<(String, u64)>,
+    // Events with the most attendees, largest first
+    pub top_events: Vec<(Principal, u64)>,
+}
@@ -0,0 +1,137 @@
+use candid::Principal;
+
+use crate::operator_model::{OperatorRole, OperatorRoles};
+
+// Max number of principals per role
+pub const MAX_PRINCIPALS_PER_ROLE: usize = 20;
+
+pub fn has_operator_role(roles: &OperatorRoles, role: OperatorRole, principal: &Principal) -> bool {
+    role_principals(roles, role).contains(principal)
+}
+
+// Method to add a principal to a role, returns an error when the role is full
+// adding a principal that already has the role doesnt change the registry
+pub fn add_operator(
+    roles: &mut OperatorRoles,
+    role: OperatorRole,
+    principal: Principal,
+) -> Result<(), String> {
+    if principal == Principal::anonymous() {
+        return Err("The anonymous principal can't be an operator".to_string());
+    }
+
+    let principals = role_principals_mut(roles, role);
+    if principals.contains(&principal) {
+        return Ok(());
+    }
+    if principals.len() >= MAX_PRINCIPALS_PER_ROLE {
+        return Err(format!(
+            "A role can have at most {} principals",
+            MAX_PRINCIPALS_PER_ROLE
+        ));
+    }
+
+    principals.push(principal);
+    Ok(())
+}
+
+// Method to remove a principal from a role, returns false when the principal didnt have the role
+pub fn remove_operator(
+    roles: &mut OperatorRoles,
+    role: OperatorRole,
+    principal: &Principal,
+) -> bool {
+    let principals = role_principals_mut(roles, role);
+    let len = principals.len();
+    principals.retain(|p| p != principal);
+    principals.len() != len
+}
+
+// Method to check if the caller is a controller of the canister or the configured SNS governance canister
+pub fn is_controller_or_sns_governance(
+    roles: &OperatorRoles,
+    caller: &Principal,
+    is_controller: bool,
+) -> bool {
+    is_controller || roles.sns_governance.as_ref() == Some(caller)
+}
+
+fn role_principals(roles: &OperatorRoles, role: OperatorRole) -> &Vec<Principal> {
+    match role {
+        OperatorRole::Owner => &roles.owners,
+        OperatorRole::Monitor => &roles.monitors,
+    }
+}
+
+fn role_principals_mut(roles: &mut OperatorRoles, role: OperatorRole) -> &mut Vec<Principal> {
+    match role {
+        OperatorRole::Owner => &mut roles.owners,
+        OperatorRole::Monitor => &mut roles.monitors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_and_removes_operators_per_role() {
+        let principal = Principal::from_slice(&[1, 2]);
+        let mut roles = OperatorRoles::default();
+
+        assert!(add_operator(&mut roles, OperatorRole::Owner, principal).is_ok());
+        assert!(add_operator(&mut roles, OperatorRole::Owner, principal).is_ok());
+        assert_eq!(roles.owners, vec![principal]);
+        assert!(has_operator_role(&roles, OperatorRole::Owner, &principal));
+        assert!(!has_operator_role(
+            &roles,
+            OperatorRole::Monitor,
+            &principal
+        ));
+
+        assert!(remove_operator(&mut roles, OperatorRole::Owner, &principal));
+        assert!(!remove_operator(
+            &mut roles,
+            OperatorRole::Owner,
+            &principal
+        ));
+        assert!(roles.owners.is_empty());
+    }
+
+    #[test]
+    fn rejects_the_anonymous_principal_and_full_roles() {
+        let mut roles = OperatorRoles::default();
+        assert!(add_operator(&mut roles, OperatorRole::Monitor, Principal::anonymous()).is_err());
+
+        for i in 0..MAX_PRINCIPALS_PER_ROLE {
+            assert!(add_operator(
+                &mut roles,
+                OperatorRole::Monitor,
+                Principal::from_slice(&[i as u8, 1])
+            )
+            .is_ok());
+        }
+        assert!(add_operator(
+            &mut roles,
+            OperatorRole::Monitor,
+            Principal::from_slice(&[99])
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn only_the_configured_governance_canister_is_accepted() {
+        let governance = Principal::from_slice(&[7]);
+        let mut roles = OperatorRoles::default();
+        assert!(!is_controller_or_sns_governance(&roles, &governance, false));
+        assert!(is_controller_or_sns_governance(&roles, &governance, true));
+
+        roles.sns_governance = Some(governance);
+        assert!(is_controller_or_sns_governance(&roles, &governance, false));
+        assert!(!is_controller_or_sns_governance(
+            &roles,
+            &Principal::from_slice(&[8]),
+            false
+        ));
+    }
+}
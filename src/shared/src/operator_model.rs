@@ -0,0 +1,45 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Serialize;
+
+use crate::attendee_model::ChildCanisterError;
+
+// Operator roles that give access to the privileged methods
+#[derive(CandidType, Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub enum OperatorRole {
+    // Can download and restore backups
+    Owner,
+    // Can read the outbox and audit log and trigger the maintenance methods
+    Monitor,
+}
+
+// Registry of the operator principals, seeded at init and managed through the parent
+#[derive(CandidType, Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct OperatorRoles {
+    pub owners: Vec<Principal>,
+    pub monitors: Vec<Principal>,
+    // SNS governance canister that can manage the roles next to the controllers
+    pub sns_governance: Option<Principal>,
+    pub updated_at: u64,
+}
+
+impl Storable for OperatorRoles {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Result of a role change on the parent, the child canisters that couldnt be updated can be retried with `propagate_operator_roles`
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize)]
+pub struct OperatorRolesUpdate {
+    pub roles: OperatorRoles,
+    pub failed_canisters: Vec<ChildCanisterError>,
+}
@@ -0,0 +1,65 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_scalable_misc::traits::stable_storage_trait::StableStorableTrait;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Serialize;
+
+// Pending attendee count notification for a single event canister
+// multiple updates for the same event are coalesced into a single entry
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct OutboxEntry {
+    pub event_identifier: Principal,
+    pub status: OutboxStatus,
+    // Sum of the deltas that are queued but not yet part of a delivery
+    pub pending_delta: i64,
+    // The delta that is being delivered, retried unchanged until it succeeds
+    pub in_flight: Option<AttendeeCountDelta>,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+    pub updated_at: u64,
+    pub created_at: u64,
+}
+
+impl StableStorableTrait for OutboxEntry {}
+
+impl Storable for OutboxEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum OutboxStatus {
+    // Waiting to be delivered (or retried) by the outbox timer
+    Pending,
+    // Delivery gave up after the max number of attempts
+    Failed,
+}
+
+// Attendee count change send to the event canister
+// the sequence is increasing per child canister so the event canister can drop duplicates
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct AttendeeCountDelta {
+    pub event_identifier: Principal,
+    pub child_identifier: Principal,
+    pub sequence: u64,
+    pub delta: i64,
+    pub created_at: u64,
+}
+
+// Absolute attendee count of a single child canister, used to correct drift on the event canister
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct AttendeeCountReconciliation {
+    pub event_identifier: Principal,
+    pub child_identifier: Principal,
+    pub sequence: u64,
+    pub count: u64,
+}
@@ -0,0 +1,109 @@
+use candid::Principal;
+
+use crate::rollout_model::{
+    ChildRollout, ChildRolloutStatus, RolloutSettings, MAX_ROLLOUT_BATCH_DELAY_SECONDS,
+    MAX_ROLLOUT_BATCH_SIZE,
+};
+
+pub fn validate_rollout_settings(settings: &RolloutSettings) -> Result<(), String> {
+    if !(1..=100).contains(&settings.canary_percentage) {
+        return Err("The canary percentage should be between 1 and 100".to_string());
+    }
+
+    if !(1..=MAX_ROLLOUT_BATCH_SIZE).contains(&settings.batch_size) {
+        return Err(format!(
+            "The batch size should be between 1 and {}",
+            MAX_ROLLOUT_BATCH_SIZE
+        ));
+    }
+
+    if settings.batch_delay_seconds > MAX_ROLLOUT_BATCH_DELAY_SECONDS {
+        return Err(format!(
+            "The batch delay can be at most {} seconds",
+            MAX_ROLLOUT_BATCH_DELAY_SECONDS
+        ));
+    }
+
+    Ok(())
+}
+
+// Method to divide the child canisters over the batches of a rollout
+// the first batch is the canary batch with the canary percentage of the child canisters (rounded up), the rest is divided by the batch size
+pub fn plan_rollout(
+    canisters: Vec<Principal>,
+    settings: &RolloutSettings,
+    now: u64,
+) -> (Vec<ChildRollout>, u64) {
+    if canisters.is_empty() {
+        return (vec![], 0);
+    }
+
+    let canary_size = (canisters.len() * settings.canary_percentage as usize).div_ceil(100);
+    let batch_size = settings.batch_size.max(1) as usize;
+
+    let children: Vec<ChildRollout> = canisters
+        .into_iter()
+        .enumerate()
+        .map(|(index, canister_principal)| ChildRollout {
+            canister_principal,
+            batch: match index < canary_size {
+                true => 0,
+                false => ((index - canary_size) / batch_size + 1) as u64,
+            },
+            status: ChildRolloutStatus::Pending,
+            updated_at: now,
+        })
+        .collect();
+
+    let total_batches = children.last().map(|child| child.batch + 1).unwrap_or(0);
+    (children, total_batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canisters(count: u8) -> Vec<Principal> {
+        (0..count)
+            .map(|index| Principal::from_slice(&[index]))
+            .collect()
+    }
+
+    fn batch_sizes(children: &[ChildRollout], total_batches: u64) -> Vec<usize> {
+        (0..total_batches)
+            .map(|batch| children.iter().filter(|child| child.batch == batch).count())
+            .collect()
+    }
+
+    #[test]
+    fn plans_a_canary_batch_and_fixed_size_batches() {
+        let settings = RolloutSettings {
+            canary_percentage: 10,
+            batch_size: 4,
+            batch_delay_seconds: 0,
+        };
+
+        let (children, total_batches) = plan_rollout(canisters(15), &settings, 0);
+        assert_eq!(batch_sizes(&children, total_batches), vec![2, 4, 4, 4, 1]);
+
+        let (children, total_batches) = plan_rollout(canisters(1), &settings, 0);
+        assert_eq!(batch_sizes(&children, total_batches), vec![1]);
+
+        assert_eq!(plan_rollout(vec![], &settings, 0).1, 0);
+    }
+
+    #[test]
+    fn validates_the_rollout_settings() {
+        assert!(validate_rollout_settings(&RolloutSettings::default()).is_ok());
+        assert!(validate_rollout_settings(&RolloutSettings {
+            canary_percentage: 0,
+            ..RolloutSettings::default()
+        })
+        .is_err());
+        assert!(validate_rollout_settings(&RolloutSettings {
+            batch_size: 0,
+            ..RolloutSettings::default()
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use candid::{CandidType, Deserialize, Principal};
+use ic_scalable_misc::enums::wasm_version_type::WasmVersion;
+use serde::Serialize;
+
+// Max number of child canisters in a single batch
+pub const MAX_ROLLOUT_BATCH_SIZE: u64 = 50;
+// Max delay between two batches, in seconds
+pub const MAX_ROLLOUT_BATCH_DELAY_SECONDS: u64 = 24 * 60 * 60;
+
+// Settings of the staged rollout of a child wasm to the child canisters
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RolloutSettings {
+    // Percentage of the child canisters that are upgraded in the first (canary) batch, at least one child canister
+    pub canary_percentage: u8,
+    // Number of child canisters that are upgraded in each batch after the canary batch
+    pub batch_size: u64,
+    // Delay between two batches, in seconds
+    pub batch_delay_seconds: u64,
+}
+
+impl Default for RolloutSettings {
+    fn default() -> Self {
+        Self {
+            canary_percentage: 10,
+            batch_size: 5,
+            batch_delay_seconds: 60,
+        }
+    }
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RolloutStatus {
+    Running,
+    // Stopped by a failed upgrade or health check, or by an operator, can be resumed
+    Halted(String),
+    Completed,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ChildRolloutStatus {
+    Pending,
+    Upgraded,
+    Failed(String),
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize)]
+pub struct ChildRollout {
+    pub canister_principal: Principal,
+    // Index of the batch the child canister is upgraded in, batch 0 is the canary batch
+    pub batch: u64,
+    pub status: ChildRolloutStatus,
+    pub updated_at: u64,
+}
+
+// Rollout of a child wasm version to the child canisters that run an other version
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize)]
+pub struct Rollout {
+    pub wasm_version: WasmVersion,
+    pub settings: RolloutSettings,
+    pub status: RolloutStatus,
+    pub total_batches: u64,
+    pub completed_batches: u64,
+    pub children: Vec<ChildRollout>,
+    pub started_at: u64,
+    pub updated_at: u64,
+}
+
+// Rollout data that is kept by the parent
+#[derive(CandidType, Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RolloutStore {
+    pub settings: RolloutSettings,
+    // The last rollout, replaced when a new rollout is started
+    pub rollout: Option<Rollout>,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize)]
+pub struct ChildWasmVersion {
+    pub canister_principal: Principal,
+    pub wasm_version: WasmVersion,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize)]
+pub struct RolloutProgress {
+    // Current child wasm version on the parent
+    pub wasm_version: WasmVersion,
+    // Settings that are used for the next rollout
+    pub settings: RolloutSettings,
+    pub rollout: Option<Rollout>,
+    // Version that is installed on every child canister
+    pub children: Vec<ChildWasmVersion>,
+}
@@ -0,0 +1,83 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_scalable_misc::traits::stable_storage_trait::StableStorableTrait;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Serialize;
+
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct SubscriptionRequest {
+    // The events to receive changes for, all events if empty
+    pub event_identifiers: Vec<Principal>,
+    // The update method on the subscribing canister that receives the AttendeeChangeBatch
+    pub method: String,
+}
+
+// Subscription of a canister on the attendee changes of this child canister
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct Subscription {
+    pub subscriber: Principal,
+    pub event_identifiers: Vec<Principal>,
+    pub method: String,
+    pub status: SubscriptionStatus,
+    // Sequence of the first change that is not yet delivered
+    pub next_sequence: u64,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+    pub updated_at: u64,
+    pub created_at: u64,
+}
+
+impl StableStorableTrait for Subscription {}
+
+impl Storable for Subscription {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum SubscriptionStatus {
+    // Changes are delivered (or retried) by the dispatcher timer
+    Active,
+    // Delivery gave up after the max number of attempts, subscribe again to resume
+    Failed,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum AttendeeChangeType {
+    Join,
+    Accept,
+    Leave,
+    Remove,
+    Invite,
+    RemoveInvite,
+}
+
+// Single change of an attendee, the sequence is the index of the block in the attendance log
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct AttendeeChange {
+    pub sequence: u64,
+    pub change_type: AttendeeChangeType,
+    pub principal: Principal,
+    pub event_identifier: Principal,
+    pub group_identifier: Principal,
+    pub created_at: u64,
+}
+
+// Batch of changes that is delivered to a subscriber or returned when fetching missed changes
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct AttendeeChangeBatch {
+    pub canister_identifier: Principal,
+    pub changes: Vec<AttendeeChange>,
+    // Sequence to continue from to get the next batch
+    pub next_sequence: u64,
+    pub log_length: u64,
+}
@@ -0,0 +1,184 @@
+use candid::Principal;
+
+use crate::{
+    chunk_helper::hash_bytes,
+    wasm_model::{
+        ChildWasmMetadata, ChildWasmUpload, ChildWasmUploadProgress, ChildWasmUploadRequest,
+        StagedChildWasm, MAX_CHILD_WASM_BYTES, MAX_CHILD_WASM_CHUNK_BYTES,
+        MAX_CHILD_WASM_LABEL_LENGTH,
+    },
+};
+
+// Magic bytes of a gzipped file and a wasm module
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const WASM_MAGIC: [u8; 4] = [0x00, 0x61, 0x73, 0x6d];
+
+// Method to check the upload request before any bytes are uploaded
+// the label has to be unique over the versions that were uploaded before
+pub fn validate_upload_request(
+    request: &ChildWasmUploadRequest,
+    used_labels: &[String],
+) -> Result<(), String> {
+    let label = request.label.trim();
+    if label.is_empty() || label.len() > MAX_CHILD_WASM_LABEL_LENGTH {
+        return Err(format!(
+            "The label should be between 1 and {} characters",
+            MAX_CHILD_WASM_LABEL_LENGTH
+        ));
+    }
+
+    if used_labels.iter().any(|used| used == label) {
+        return Err(format!("The label {} is already used", label));
+    }
+
+    if request.sha256.len() != 32 {
+        return Err("The SHA-256 hash should be 32 bytes".to_string());
+    }
+
+    if request.total_bytes == 0 || request.total_bytes > MAX_CHILD_WASM_BYTES {
+        return Err(format!(
+            "The wasm should be between 1 and {} bytes",
+            MAX_CHILD_WASM_BYTES
+        ));
+    }
+
+    Ok(())
+}
+
+// Method to check if a chunk can be added to the upload
+// the chunks need to be uploaded in order, so a retried chunk is rejected instead of added twice
+pub fn validate_chunk(upload: &ChildWasmUpload, chunk: u64, bytes: &[u8]) -> Result<(), String> {
+    if chunk != upload.received_chunks {
+        return Err(format!(
+            "Expected chunk {}, received chunk {}",
+            upload.received_chunks, chunk
+        ));
+    }
+
+    if bytes.is_empty() || bytes.len() as u64 > MAX_CHILD_WASM_CHUNK_BYTES {
+        return Err(format!(
+            "A chunk should be between 1 and {} bytes",
+            MAX_CHILD_WASM_CHUNK_BYTES
+        ));
+    }
+
+    if upload.bytes.len() as u64 + bytes.len() as u64 > upload.request.total_bytes {
+        return Err(format!(
+            "The chunk exceeds the total of {} bytes",
+            upload.request.total_bytes
+        ));
+    }
+
+    Ok(())
+}
+
+// Method to verify the size, hash and format of the uploaded wasm
+pub fn verify_upload(upload: &ChildWasmUpload) -> Result<(), String> {
+    if upload.bytes.len() as u64 != upload.request.total_bytes {
+        return Err(format!(
+            "Expected {} bytes, received {}",
+            upload.request.total_bytes,
+            upload.bytes.len()
+        ));
+    }
+
+    if hash_bytes(&upload.bytes) != upload.request.sha256 {
+        return Err("Hash of the uploaded wasm does not match".to_string());
+    }
+
+    if !upload.bytes.starts_with(&GZIP_MAGIC) && !upload.bytes.starts_with(&WASM_MAGIC) {
+        return Err("The uploaded file is not a (gzipped) wasm module".to_string());
+    }
+
+    Ok(())
+}
+
+// Method to turn a verified upload into a wasm that is ready to be rolled out
+pub fn stage_upload(upload: ChildWasmUpload, version: u64) -> StagedChildWasm {
+    StagedChildWasm {
+        metadata: ChildWasmMetadata {
+            label: upload.request.label.trim().to_string(),
+            version,
+            sha256: upload.request.sha256,
+            size: upload.request.total_bytes,
+            uploaded_by: upload.uploaded_by,
+            uploaded_at: upload.started_at,
+            rolled_out_at: None,
+        },
+        bytes: upload.bytes,
+    }
+}
+
+pub fn get_upload_progress(upload: &ChildWasmUpload) -> ChildWasmUploadProgress {
+    ChildWasmUploadProgress {
+        label: upload.request.label.clone(),
+        sha256: upload.request.sha256.clone(),
+        total_bytes: upload.request.total_bytes,
+        received_bytes: upload.bytes.len() as u64,
+        received_chunks: upload.received_chunks,
+    }
+}
+
+pub fn new_upload(
+    request: ChildWasmUploadRequest,
+    uploaded_by: Principal,
+    started_at: u64,
+) -> ChildWasmUpload {
+    ChildWasmUpload {
+        request,
+        uploaded_by,
+        started_at,
+        bytes: vec![],
+        received_chunks: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upload_for(bytes: &[u8]) -> ChildWasmUpload {
+        new_upload(
+            ChildWasmUploadRequest {
+                label: "v1.0.0".to_string(),
+                sha256: hash_bytes(bytes),
+                total_bytes: bytes.len() as u64,
+            },
+            Principal::anonymous(),
+            0,
+        )
+    }
+
+    #[test]
+    fn verifies_chunked_uploads() {
+        let wasm = [&WASM_MAGIC[..], &[1, 2, 3, 4, 5]].concat();
+        let mut upload = upload_for(&wasm);
+        assert!(validate_upload_request(&upload.request, &[]).is_ok());
+        assert!(validate_upload_request(&upload.request, &["v1.0.0".to_string()]).is_err());
+
+        for (index, chunk) in wasm.chunks(4).enumerate() {
+            assert!(validate_chunk(&upload, index as u64 + 1, chunk).is_err());
+            validate_chunk(&upload, index as u64, chunk).unwrap();
+            upload.bytes.extend_from_slice(chunk);
+            upload.received_chunks += 1;
+        }
+        assert!(validate_chunk(&upload, upload.received_chunks, &[1]).is_err());
+        assert!(verify_upload(&upload).is_ok());
+
+        let staged = stage_upload(upload, 2);
+        assert_eq!(staged.metadata.version, 2);
+        assert_eq!(staged.bytes, wasm);
+    }
+
+    #[test]
+    fn rejects_changed_or_invalid_uploads() {
+        let wasm = [&GZIP_MAGIC[..], &[1, 2, 3]].concat();
+        let mut upload = upload_for(&wasm);
+        upload.bytes = [&GZIP_MAGIC[..], &[1, 2, 4]].concat();
+        assert!(verify_upload(&upload).is_err());
+
+        let mut upload = upload_for(&[1, 2, 3]);
+        upload.bytes = vec![1, 2, 3];
+        assert!(verify_upload(&upload).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use candid::{CandidType, Deserialize, Principal};
+use serde::Serialize;
+
+// Max size of the child wasm, install_code sends the complete wasm in a single inter-canister call
+pub const MAX_CHILD_WASM_BYTES: u64 = 2_000_000;
+// Max size of a single uploaded chunk, keeps the upload calls below the ingress limit
+pub const MAX_CHILD_WASM_CHUNK_BYTES: u64 = 1_000_000;
+// Max length of the version label
+pub const MAX_CHILD_WASM_LABEL_LENGTH: usize = 64;
+// Max number of rolled out versions that are kept in the history, the oldest version is removed first
+pub const MAX_CHILD_WASM_HISTORY: usize = 20;
+
+// Request to start a child wasm upload, the hash and size are verified when the upload is finalized
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize)]
+pub struct ChildWasmUploadRequest {
+    // Version label of the wasm (ex; v1.4.0), has to be unique
+    pub label: String,
+    // SHA-256 hash of the complete (gzipped) wasm
+    pub sha256: Vec<u8>,
+    pub total_bytes: u64,
+}
+
+// Child wasm upload that is in progress
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize)]
+pub struct ChildWasmUpload {
+    pub request: ChildWasmUploadRequest,
+    pub uploaded_by: Principal,
+    pub started_at: u64,
+    pub bytes: Vec<u8>,
+    pub received_chunks: u64,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize)]
+pub struct ChildWasmUploadProgress {
+    pub label: String,
+    pub sha256: Vec<u8>,
+    pub total_bytes: u64,
+    pub received_bytes: u64,
+    // Index of the next chunk that is expected
+    pub received_chunks: u64,
+}
+
+// Metadata of an uploaded child wasm
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ChildWasmMetadata {
+    pub label: String,
+    // Version that is set on the child canisters, increases with every uploaded wasm
+    pub version: u64,
+    pub sha256: Vec<u8>,
+    pub size: u64,
+    pub uploaded_by: Principal,
+    pub uploaded_at: u64,
+    pub rolled_out_at: Option<u64>,
+}
+
+// Uploaded child wasm that is ready to be rolled out
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize)]
+pub struct StagedChildWasm {
+    pub metadata: ChildWasmMetadata,
+    pub bytes: Vec<u8>,
+}
+
+// Child wasm uploads and versions that are kept by the parent
+#[derive(CandidType, Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ChildWasmStore {
+    pub upload: Option<ChildWasmUpload>,
+    pub staged: Option<StagedChildWasm>,
+    // Rolled out versions, the last version is the current child wasm
+    pub history: Vec<ChildWasmMetadata>,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize)]
+pub struct ChildWasmStatus {
+    pub current: Option<ChildWasmMetadata>,
+    pub staged: Option<ChildWasmMetadata>,
+    pub upload: Option<ChildWasmUploadProgress>,
+    pub history: Vec<ChildWasmMetadata>,
+}